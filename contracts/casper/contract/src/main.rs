@@ -8,13 +8,15 @@ extern crate alloc;
 
 use alloc::{
     boxed::Box,
+    collections::BTreeMap,
+    format,
     string::{String, ToString},
     vec,
     vec::Vec,
 };
 
 use casper_contract::{
-    contract_api::{runtime, storage},
+    contract_api::{runtime, storage, system},
     unwrap_or_revert::UnwrapOrRevert,
 };
 
@@ -22,258 +24,5502 @@ use casper_contract::contract_api::cryptography::verify_signature;
 use casper_contract::contract_api::runtime::blake2b;
 
 use casper_types::{
-    bytesrepr::{Bytes, ToBytes},
-    contracts::{ContractHash, EntryPoint, EntryPoints},
-    runtime_args, ApiError, CLType, CLValue, EntryPointAccess, EntryPointType, NamedKeys,
-    Parameter, PublicKey, Signature, URef,
+    account::AccountHash,
+    bytesrepr::{self, Bytes, FromBytes, ToBytes},
+    contracts::{
+        ContractHash, ContractPackage, ContractPackageHash, ContractVersion, EntryPoint,
+        EntryPoints,
+    },
+    runtime_args, ApiError, CLType, CLTyped, CLValue, EntryPointAccess, EntryPointType, Key,
+    NamedKeys, Parameter, PublicKey, RuntimeArgs, Signature, URef, U256, U512,
 };
 
 /// ------------------------------------------------
 /// Named Keys
 /// ------------------------------------------------
 
+/// Outbound sequence across all chains; keys the `messages` dictionary
 const KEY_NONCE: &str = "nonce";
+/// Outbound sequence nonces below this have been pruned from `messages`
+const KEY_MESSAGES_FLOOR: &str = "messages_floor";
+/// Entries of `messages` not yet pruned
+const KEY_ACTIVE_MESSAGE_COUNT: &str = "active_message_count";
+/// Active messages at which `send_message` stops until the owner prunes; 0 is off
+const KEY_MAX_ACTIVE_MESSAGES: &str = "max_active_messages";
+/// `(base, per_payload_byte, per_receiver)` for `estimate_delivery_weight`
+const KEY_WEIGHT_COEFFICIENTS: &str = "weight_coefficients";
+/// Per-destination nonce carried in the message layout
+const KEY_CHAIN_NONCES: &str = "chain_nonces";
 const KEY_SUPPORTED_CHAINS: &str = "supported_chains";
 const KEY_EXECUTED_MESSAGES: &str = "executed_messages";
+const KEY_REPLAY_MARKS: &str = "replay_marks";
 const KEY_RELAYER_PUBKEY: &str = "relayer_pubkey";
+/// Block time (ms) from which `relayer_pubkey` no longer verifies; 0 never
+const KEY_RELAYER_KEY_EXPIRY: &str = "relayer_key_expiry";
 const KEY_MESSAGES: &str = "messages";
+const KEY_ALLOW_NULL_PAYLOAD: &str = "allow_null_payload";
+const KEY_STRICT_SOURCE_CHAINS: &str = "strict_source_chains";
+const KEY_MESSAGE_SENDERS: &str = "message_senders";
+/// `MessageMeta` of each outbound message, by message id
+const KEY_MESSAGE_META: &str = "message_meta";
+/// Id of each outbound message, by `nonce_key` of its destination and nonce
+const KEY_NONCE_TO_ID: &str = "nonce_to_id";
+/// Payload length per stored message; the layout has no receiver length,
+/// so this is what locates the payload
+const KEY_MESSAGE_PAYLOAD_LENS: &str = "message_payload_lens";
+const KEY_RECEIVER_VERSIONS: &str = "receiver_versions";
+const KEY_RECENT_IDS: &str = "recent_message_ids";
+const KEY_RECENT_IDS_HEAD: &str = "recent_message_ids_head";
+const KEY_RECENT_IDS_CAPACITY: &str = "recent_message_ids_capacity";
+/// Installing account; the only caller allowed on owner entry points
+const KEY_OWNER: &str = "owner";
+/// Purse collecting `send_message` fees
+const KEY_FEE_PURSE: &str = "fee_purse";
+const KEY_MESSAGE_FEE: &str = "message_fee";
+/// Purse relayers are paid from, funded through `fund_rewards`
+const KEY_REWARD_PURSE: &str = "reward_purse";
+/// Paid to the caller of each successful `execute_message`
+const KEY_RELAYER_REWARD: &str = "relayer_reward";
+/// CEP-18 token rewards are paid in instead of CSPR, if any
+const KEY_REWARD_TOKEN: &str = "reward_token";
+const KEY_MAX_FANOUT: &str = "max_fanout";
+/// Most messages one `execute_messages_batch` call may carry
+const KEY_MAX_BATCH_SIZE: &str = "max_batch_size";
+/// Confirmed deliveries of outbound messages, by message id
+const KEY_MESSAGE_ACKS: &str = "message_acks";
+/// `(fee, deadline)` of paid messages that can still be refunded
+const KEY_REFUNDABLE_FEES: &str = "refundable_fees";
+/// Sum of the fees in `refundable_fees` that are not yet refunded or acked
+const KEY_REFUNDABLE_TOTAL: &str = "refundable_total";
+/// `(error code, context)` of failures recorded under `soft_fail`, by sequence
+const KEY_ERROR_EVENTS: &str = "error_events";
+const KEY_ERROR_EVENT_COUNT: &str = "error_event_count";
+/// Chain id an error event was about, by error event index
+const KEY_ERROR_CHAINS: &str = "error_chains";
+/// Folded into every message id, so gateways sharing a deployment never
+/// produce the same id; empty leaves ids as the plain hash
+const KEY_GATEWAY_NAMESPACE: &str = "gateway_namespace";
+/// Protocol version (`major.minor.patch`) the deployment follows
+const KEY_PROTOCOL_VERSION: &str = "protocol_version";
+/// Times the protocol version has been bumped
+const KEY_PROTOCOL_VERSION_COUNT: &str = "protocol_version_count";
+/// `(new version, block time)` of each bump, by bump sequence
+const KEY_VERSION_EVENTS: &str = "version_events";
+/// Payload versions (first payload byte) a receiver accepts, by receiver hex
+const KEY_ALLOWED_PAYLOAD_VERSIONS: &str = "allowed_payload_versions";
+/// Bytes a receiver's payloads must start with, by receiver hex
+const KEY_REQUIRED_PREFIXES: &str = "required_prefixes";
+/// Per-chain delivery settings (`ChainConfig`), by chain id
+const KEY_CHAIN_CONFIGS: &str = "chain_configs";
+/// Next inbound nonce an ordered chain must deliver, by `stream_key`
+const KEY_INBOUND_NONCES: &str = "inbound_nonces";
+/// Entry point for each message type a receiver handles, by receiver hex
+const KEY_RECEIVER_HANDLERS: &str = "receiver_handlers";
+/// Receivers that deliveries are withheld from, by receiver hex
+const KEY_PAUSED_RECEIVERS: &str = "paused_receivers";
+/// Idempotent receivers that accept re-deliveries, by receiver hex
+const KEY_REPLAYABLE_RECEIVERS: &str = "replayable_receivers";
+const KEY_EXECUTED_COUNT: &str = "executed_count";
+/// Messages sent to each chain, by destination chain id
+const KEY_OUTBOUND_COUNT: &str = "outbound_count";
+/// Messages executed from each chain, by canonical source chain id
+const KEY_INBOUND_COUNT: &str = "inbound_count";
+const KEY_SUPPORTED_CHAIN_COUNT: &str = "supported_chain_count";
+/// Supported chain ids, in the order they were enabled
+const KEY_SUPPORTED_CHAIN_LIST: &str = "supported_chain_list";
+const KEY_PAUSED: &str = "paused";
+/// Block time (ms) until which a paused contract still executes inbound messages
+const KEY_PAUSE_GRACE_UNTIL: &str = "pause_grace_until";
+/// M-of-N relayer committee; empty means the single `relayer_pubkey` signs
+const KEY_RELAYERS: &str = "relayers";
+const KEY_THRESHOLD: &str = "threshold";
+/// Holds CSPR escrowed by sends and paid out by value-carrying deliveries
+const KEY_ESCROW_PURSE: &str = "escrow_purse";
+const KEY_MAX_INBOUND_PAYLOAD_LEN: &str = "max_inbound_payload_len";
+/// Longest source or sender gateway address a message may carry, in bytes
+const KEY_MAX_GATEWAY_LEN: &str = "max_gateway_len";
+/// Shortest source gateway address `execute_message` accepts, in bytes
+const KEY_MIN_GATEWAY_LEN: &str = "min_gateway_len";
+/// Canonical chain id for a retired or renamed one, by old chain id
+const KEY_CHAIN_ALIASES: &str = "chain_aliases";
+/// `(max messages, window ms)` any one sender may send; 0 messages is no limit
+const KEY_SENDER_RATE_LIMIT: &str = "sender_rate_limit";
+/// `(window index, messages sent in it)`, by sender account hash hex
+const KEY_SENDER_WINDOWS: &str = "sender_windows";
+/// When set, a sender may use each `idem_key` for one send only
+const KEY_DEDUP_IDEM_KEYS: &str = "dedup_idem_keys";
+/// Lets messages this contract sent to its own chain be delivered back here
+const KEY_LOOPBACK: &str = "loopback";
+/// Message id sent under each `(sender, idem_key)`, by their hash
+const KEY_SENDER_IDEM_KEYS: &str = "sender_idem_keys";
+/// When set, deliveries to a pinned receiver whose pinned version is not on
+/// chain fail with `ReceiverNotFound`; unpinned receivers are not checked
+const KEY_CHECK_RECEIVERS: &str = "check_receivers";
+/// When set, receiver handlers must return a `bool` acknowledgement
+const KEY_RECEIVER_RETURNS_BOOL: &str = "receiver_returns_bool";
+/// Largest gap between a relayer's `sig_timestamp` and block time; 0 is off
+const KEY_MAX_SIG_AGE_MS: &str = "max_sig_age_ms";
+/// How long after execution the owner may still revoke it
+const KEY_DISPUTE_WINDOW_MS: &str = "dispute_window_ms";
+/// The only gateway a source chain may deliver from, by chain id
+const KEY_TRUSTED_GATEWAYS: &str = "trusted_gateways";
+/// Sequence number the next signed admin action must carry
+const KEY_ADMIN_NONCE: &str = "admin_nonce";
+/// Key allowed to authorize admin actions by signature, if any
+const KEY_ADMIN_PUBKEY: &str = "admin_pubkey";
+/// Relayer key dedicated to one source chain, by chain id
+const KEY_CHAIN_RELAYERS: &str = "chain_relayers";
+/// Authenticated `execute_message` calls per message id, failed ones included
+const KEY_DELIVERY_ATTEMPTS: &str = "delivery_attempts";
+/// Set while a receiver is being called, so it cannot reach admin methods
+const KEY_IN_DELIVERY: &str = "in_delivery";
+/// When set, `send_message` needs a relayer signature over the outbound bytes
+const KEY_SIGNED_SENDS: &str = "signed_sends";
+/// When false, `send_message` returns the message id instead of its bytes
+const KEY_RETURN_MESSAGE_BYTES: &str = "return_message_bytes";
+/// Receiver that takes messages the signed receiver could not, if any
+const KEY_FALLBACK_RECEIVER: &str = "fallback_receiver";
+/// Receiver every governance-typed message goes to, if any
+const KEY_GOVERNANCE_RECEIVER: &str = "governance_receiver";
+/// Contract deciding outbound chain support instead of `supported_chains`, if any
+const KEY_CHAIN_REGISTRY: &str = "chain_registry";
+/// Signed receiver of each message delivered to the fallback, by message id
+const KEY_REDIRECTED_MESSAGES: &str = "redirected_messages";
+
+/// Inbound message bytes of store-only deliveries, by message id
+const KEY_STORED_MESSAGES: &str = "stored_messages";
+/// Epoch of the key new messages are tagged with; 0 while tagging is off
+const KEY_MAC_KEY_EPOCH: &str = "mac_key_epoch";
+/// Every tagging key by epoch, so older tags stay checkable after a rotation
+const KEY_MAC_KEYS: &str = "mac_keys";
+/// `execute_message` args of verified deliveries that failed softly, by
+/// message id, for `retry_failed`; `None` once delivered or dead-lettered
+const KEY_FAILED_MESSAGES: &str = "failed_messages";
+/// `(message id, execute_message args)` of deliveries given up on, by index
+const KEY_DEAD_LETTERS: &str = "dead_letters";
+const KEY_DEAD_LETTER_COUNT: &str = "dead_letter_count";
+/// Delivery attempts after which `retry_failed` moves a message to the
+/// dead letters
+const KEY_MAX_DELIVERY_ATTEMPTS: &str = "max_delivery_attempts";
+/// `(epoch, blake2b(key ‖ message))` of each tagged message, by the same key
+/// as in `messages` or `stored_messages`
+const KEY_MESSAGE_TAGS: &str = "message_tags";
+
+/// Committee members that confirmed a not yet executed message, by message id
+const KEY_CONFIRMATIONS: &str = "confirmations";
+/// blake2b of the signed bytes `confirmations` were signed over, by message id
+const KEY_CONFIRMATION_DIGESTS: &str = "confirmation_digests";
+/// Partly confirmed messages per source chain, by canonical chain id
+const KEY_PENDING_COUNTS: &str = "pending_counts";
+/// Most partly confirmed messages a source chain may have; 0 is no limit
+const KEY_MAX_PENDING: &str = "max_pending";
+
+/// Stamped on every state change, giving one order across event kinds
+const KEY_GLOBAL_SEQ: &str = "global_seq";
+/// When set, each state change is recorded as a `GatewayEvent`
+const KEY_EMIT_EVENTS: &str = "emit_events";
+/// Where events go: `EVENT_BACKEND_DICTIONARY` or `EVENT_BACKEND_CES`
+const KEY_EVENT_BACKEND: &str = "event_backend";
+/// `GatewayEvent` of each state change, by its `global_seq`
+const KEY_EVENTS: &str = "events";
+/// Events recorded in `events`
+const KEY_EVENT_COUNT: &str = "event_count";
+
+/// Casper Event Standard named keys, present on CES installs only
+const KEY_CES_EVENTS: &str = "__events";
+const KEY_CES_EVENTS_LENGTH: &str = "__events_length";
+const KEY_CES_EVENTS_SCHEMA: &str = "__events_schema";
+const KEY_CES_VERSION: &str = "__events_ces_version";
+const CES_VERSION: &str = "0.1.0";
+/// CES name of `GatewayEvent`; emitted events are prefixed `event_`
+const CES_EVENT_NAME: &str = "GatewayEvent";
+
+/// Event backends: the plain `events` log, or the CES layout
+const EVENT_BACKEND_DICTIONARY: u8 = 0;
+const EVENT_BACKEND_CES: u8 = 1;
+
+/// Protocol version of a fresh install
+const DEFAULT_PROTOCOL_VERSION: &str = "1.0.0";
+
+/// Dictionaries exposed through `get_dict_uref`
+const DICTIONARIES: [&str; 40] = [
+    KEY_CHAIN_NONCES,
+    KEY_SUPPORTED_CHAINS,
+    KEY_EXECUTED_MESSAGES,
+    KEY_REPLAY_MARKS,
+    KEY_MESSAGES,
+    KEY_MESSAGE_SENDERS,
+    KEY_MESSAGE_META,
+    KEY_NONCE_TO_ID,
+    KEY_MESSAGE_PAYLOAD_LENS,
+    KEY_RECEIVER_VERSIONS,
+    KEY_MESSAGE_ACKS,
+    KEY_REFUNDABLE_FEES,
+    KEY_ERROR_EVENTS,
+    KEY_ERROR_CHAINS,
+    KEY_ALLOWED_PAYLOAD_VERSIONS,
+    KEY_REQUIRED_PREFIXES,
+    KEY_PAUSED_RECEIVERS,
+    KEY_REPLAYABLE_RECEIVERS,
+    KEY_RECEIVER_HANDLERS,
+    KEY_CHAIN_CONFIGS,
+    KEY_INBOUND_NONCES,
+    KEY_TRUSTED_GATEWAYS,
+    KEY_DELIVERY_ATTEMPTS,
+    KEY_CHAIN_RELAYERS,
+    KEY_SENDER_WINDOWS,
+    KEY_SENDER_IDEM_KEYS,
+    KEY_CHAIN_ALIASES,
+    KEY_REDIRECTED_MESSAGES,
+    KEY_CONFIRMATIONS,
+    KEY_CONFIRMATION_DIGESTS,
+    KEY_PENDING_COUNTS,
+    KEY_VERSION_EVENTS,
+    KEY_OUTBOUND_COUNT,
+    KEY_INBOUND_COUNT,
+    KEY_EVENTS,
+    KEY_STORED_MESSAGES,
+    KEY_MAC_KEYS,
+    KEY_MESSAGE_TAGS,
+    KEY_FAILED_MESSAGES,
+    KEY_DEAD_LETTERS,
+];
+
+/// Keeps admin signatures from being valid as message signatures
+const ADMIN_ACTION_DOMAIN: &[u8] = b"knotx-admin-v1";
 
 /// Casper chain id
 const CASPER_CHAIN_ID: u32 = 3;
 
-/// ------------------------------------------------
-/// Errors
-/// ------------------------------------------------
+/// `CallerInfo` fields holding the contract package hash and contract hash
+const CALLER_FIELD_CONTRACT_PACKAGE: u8 = 2;
+const CALLER_FIELD_CONTRACT: u8 = 4;
+
+/// Account named key the gateway contract hash is stored under by default
+const DEFAULT_CONTRACT_KEY_NAME: &str = "knotx_gateway";
+
+/// Namespace for replay marks. Bump only to deliberately start a fresh
+/// replay domain; layout changes alone do not affect replay keys.
+const REPLAY_NAMESPACE_VERSION: u32 = 1;
+
+/// Receiver that discards the message instead of calling a contract
+const NULL_RECEIVER: [u8; 32] = [0u8; 32];
+
+/// Default number of recent outbound ids kept in the ring buffer
+const DEFAULT_RECENT_IDS_CAPACITY: u32 = 16;
+
+/// Default cap on receivers in one fan-out delivery
+const DEFAULT_MAX_FANOUT: u32 = 8;
+
+/// Default cap on messages in one `execute_messages_batch` call
+const DEFAULT_MAX_BATCH_SIZE: u32 = 16;
+
+/// Default delivery weight model, in motes: a fixed cost for verification
+/// and bookkeeping, plus the payload bytes and each receiver call
+const DEFAULT_WEIGHT_COEFFICIENTS: (u64, u64, u64) = (2_500_000_000, 1_000_000, 1_000_000_000);
+
+/// Default delivery attempts before a failed message is dead-lettered
+const DEFAULT_MAX_DELIVERY_ATTEMPTS: u32 = 3;
+
+/// Most dead letters one `get_dead_letters` call returns
+const MAX_DEAD_LETTERS_PAGE: u32 = 100;
+
+/// Default cap on the payload `execute_message` accepts, in bytes
+const DEFAULT_MAX_INBOUND_PAYLOAD_LEN: u32 = 64 * 1024;
+
+/// Default cap on gateway addresses, in bytes
+const DEFAULT_MAX_GATEWAY_LEN: u32 = 128;
+
+/// Default floor on inbound source gateways; the shortest address of any
+/// supported chain family (EVM) is 20 bytes
+const DEFAULT_MIN_GATEWAY_LEN: u32 = 20;
+
+/// `msg_type` of governance messages, delivered to the governance receiver
+/// whatever their `receiver` field says
+const MSG_TYPE_GOVERNANCE: u8 = 255;
+
+/// Chain lifecycle: disabled chains take no traffic, deprecated ones take
+/// no new sends but still deliver messages already in flight
+const CHAIN_STATUS_DISABLED: u8 = 0;
+const CHAIN_STATUS_ACTIVE: u8 = 1;
+const CHAIN_STATUS_DEPRECATED: u8 = 2;
+
+/// `sig_scheme` layout values: how the attached signatures were made
+const SIG_SCHEME_SECP256K1: u8 = 0;
+const SIG_SCHEME_ED25519: u8 = 1;
+
+/// secp256k1 curve order divided by two, big-endian.
+/// Signatures with `s` above this value are the malleable high-s form.
+const SECP256K1_HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// ------------------------------------------------
+/// Build metadata
+/// ------------------------------------------------
+
+const BUILD_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Set `KNOTX_GIT_COMMIT` when building the wasm to stamp the commit in.
+const BUILD_GIT_COMMIT: &str = match option_env!("KNOTX_GIT_COMMIT") {
+    Some(commit) => commit,
+    None => "unknown",
+};
+
+/// Returned by `get_build_info`
+struct BuildInfo {
+    version: String,
+    git_commit: String,
+}
+
+impl ToBytes for BuildInfo {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut out = bytesrepr::allocate_buffer(self)?;
+        out.extend(self.version.to_bytes()?);
+        out.extend(self.git_commit.to_bytes()?);
+        Ok(out)
+    }
+
+    fn serialized_length(&self) -> usize {
+        self.version.serialized_length() + self.git_commit.serialized_length()
+    }
+}
+
+impl CLTyped for BuildInfo {
+    fn cl_type() -> CLType {
+        CLType::Any
+    }
+}
+
+/// Returned by `get_stats`
+struct Stats {
+    sent: u64,
+    executed: u64,
+    nonce: u64,
+    supported_chains: u32,
+    paused: bool,
+}
+
+impl ToBytes for Stats {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut out = bytesrepr::allocate_buffer(self)?;
+        out.extend(self.sent.to_bytes()?);
+        out.extend(self.executed.to_bytes()?);
+        out.extend(self.nonce.to_bytes()?);
+        out.extend(self.supported_chains.to_bytes()?);
+        out.extend(self.paused.to_bytes()?);
+        Ok(out)
+    }
+
+    fn serialized_length(&self) -> usize {
+        self.sent.serialized_length()
+            + self.executed.serialized_length()
+            + self.nonce.serialized_length()
+            + self.supported_chains.serialized_length()
+            + self.paused.serialized_length()
+    }
+}
+
+impl CLTyped for Stats {
+    fn cl_type() -> CLType {
+        CLType::Any
+    }
+}
+
+/// Returned by `get_storage_stats`: entries held in the growing dictionaries
+struct StorageStats {
+    /// Sent messages not yet pruned
+    messages: u64,
+    /// Executions not revoked
+    executed_messages: u64,
+    /// Recorded error events
+    error_events: u64,
+}
+
+impl ToBytes for StorageStats {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut out = bytesrepr::allocate_buffer(self)?;
+        out.extend(self.messages.to_bytes()?);
+        out.extend(self.executed_messages.to_bytes()?);
+        out.extend(self.error_events.to_bytes()?);
+        Ok(out)
+    }
+
+    fn serialized_length(&self) -> usize {
+        self.messages.serialized_length()
+            + self.executed_messages.serialized_length()
+            + self.error_events.serialized_length()
+    }
+}
+
+impl CLTyped for StorageStats {
+    fn cl_type() -> CLType {
+        CLType::Any
+    }
+}
+
+/// A state change, recorded under `emit_events` by the event backend
+struct GatewayEvent {
+    global_seq: u64,
+    /// What happened, e.g. `message_sent` or `admin_action`
+    kind: String,
+    /// What it happened to: a message id, chain id or receiver hex
+    subject: String,
+    timestamp: u64,
+}
+
+impl ToBytes for GatewayEvent {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut out = bytesrepr::allocate_buffer(self)?;
+        out.extend(self.global_seq.to_bytes()?);
+        out.extend(self.kind.to_bytes()?);
+        out.extend(self.subject.to_bytes()?);
+        out.extend(self.timestamp.to_bytes()?);
+        Ok(out)
+    }
+
+    fn serialized_length(&self) -> usize {
+        self.global_seq.serialized_length()
+            + self.kind.serialized_length()
+            + self.subject.serialized_length()
+            + self.timestamp.serialized_length()
+    }
+}
+
+impl FromBytes for GatewayEvent {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+        let (global_seq, rem) = u64::from_bytes(bytes)?;
+        let (kind, rem) = String::from_bytes(rem)?;
+        let (subject, rem) = String::from_bytes(rem)?;
+        let (timestamp, rem) = u64::from_bytes(rem)?;
+        Ok((
+            GatewayEvent {
+                global_seq,
+                kind,
+                subject,
+                timestamp,
+            },
+            rem,
+        ))
+    }
+}
+
+impl CLTyped for GatewayEvent {
+    fn cl_type() -> CLType {
+        CLType::Any
+    }
+}
+
+/// CES schemas (event name -> `(field, CLType)` list) of the one event the
+/// gateway emits, with the fields of `GatewayEvent` in serialization order.
+struct CesSchemas;
+
+impl CesSchemas {
+    fn fields() -> [(&'static str, CLType); 4] {
+        [
+            ("global_seq", CLType::U64),
+            ("kind", CLType::String),
+            ("subject", CLType::String),
+            ("timestamp", CLType::U64),
+        ]
+    }
+}
+
+impl ToBytes for CesSchemas {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let fields = Self::fields();
+
+        let mut out = bytesrepr::allocate_buffer(self)?;
+        out.extend(1u32.to_bytes()?);
+        out.extend(CES_EVENT_NAME.to_bytes()?);
+        out.extend((fields.len() as u32).to_bytes()?);
+        for (name, cl_type) in fields {
+            out.extend(name.to_bytes()?);
+            out.extend(cl_type_bytes(cl_type)?);
+        }
+        Ok(out)
+    }
+
+    fn serialized_length(&self) -> usize {
+        let fields = Self::fields();
+
+        8 + CES_EVENT_NAME.serialized_length()
+            + fields
+                .iter()
+                .map(|(name, cl_type)| name.serialized_length() + cl_type.serialized_length())
+                .sum::<usize>()
+    }
+}
+
+impl CLTyped for CesSchemas {
+    fn cl_type() -> CLType {
+        CLType::map(
+            CLType::String,
+            CLType::List(Box::new(CLType::Tuple2([
+                Box::new(CLType::String),
+                Box::new(CLType::Any),
+            ]))),
+        )
+    }
+}
+
+/// `CLType` has no public `ToBytes`; a `CLValue` serializes its bytes
+/// followed by its type, so take the type from an empty value.
+fn cl_type_bytes(cl_type: CLType) -> Result<Vec<u8>, bytesrepr::Error> {
+    let bytes = CLValue::from_components(cl_type, Vec::new()).to_bytes()?;
+    Ok(bytes[4..].to_vec())
+}
+
+/// Where an executed message came from, stored in `executed_messages`
+struct ExecutionRecord {
+    /// Canonical source chain id
+    src_chain_id: u32,
+    src_gateway: Bytes,
+    nonce: u64,
+    /// Block time of the execution
+    executed_at: u64,
+}
+
+impl ToBytes for ExecutionRecord {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut out = bytesrepr::allocate_buffer(self)?;
+        out.extend(self.src_chain_id.to_bytes()?);
+        out.extend(self.src_gateway.to_bytes()?);
+        out.extend(self.nonce.to_bytes()?);
+        out.extend(self.executed_at.to_bytes()?);
+        Ok(out)
+    }
+
+    fn serialized_length(&self) -> usize {
+        self.src_chain_id.serialized_length()
+            + self.src_gateway.serialized_length()
+            + self.nonce.serialized_length()
+            + self.executed_at.serialized_length()
+    }
+}
+
+impl FromBytes for ExecutionRecord {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+        let (src_chain_id, rem) = u32::from_bytes(bytes)?;
+        let (src_gateway, rem) = Bytes::from_bytes(rem)?;
+        let (nonce, rem) = u64::from_bytes(rem)?;
+        let (executed_at, rem) = u64::from_bytes(rem)?;
+        Ok((
+            ExecutionRecord {
+                src_chain_id,
+                src_gateway,
+                nonce,
+                executed_at,
+            },
+            rem,
+        ))
+    }
+}
+
+impl CLTyped for ExecutionRecord {
+    fn cl_type() -> CLType {
+        CLType::Any
+    }
+}
+
+/// One receiver call `execute_message` would make, returned by
+/// `preview_delivery`
+struct DeliveryCall {
+    receiver: [u8; 32],
+    /// Pinned contract version; `receiver` is then a package hash
+    version: Option<ContractVersion>,
+    entry_point: String,
+    payload_len: u32,
+    amount: U512,
+}
+
+impl ToBytes for DeliveryCall {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut out = bytesrepr::allocate_buffer(self)?;
+        out.extend(self.receiver.to_bytes()?);
+        out.extend(self.version.to_bytes()?);
+        out.extend(self.entry_point.to_bytes()?);
+        out.extend(self.payload_len.to_bytes()?);
+        out.extend(self.amount.to_bytes()?);
+        Ok(out)
+    }
+
+    fn serialized_length(&self) -> usize {
+        self.receiver.serialized_length()
+            + self.version.serialized_length()
+            + self.entry_point.serialized_length()
+            + self.payload_len.serialized_length()
+            + self.amount.serialized_length()
+    }
+}
+
+impl CLTyped for DeliveryCall {
+    fn cl_type() -> CLType {
+        CLType::Any
+    }
+}
+
+/// Outbound message summary, stored in `message_meta` and returned by
+/// `get_messages_meta`
+struct MessageMeta {
+    sender: AccountHash,
+    dst_chain_id: u32,
+    /// Destination chain nonce carried in the message
+    nonce: u64,
+    /// Block time of the send
+    timestamp: u64,
+    /// Block time after which the message may not be executed; 0 never
+    deadline: u64,
+}
+
+impl ToBytes for MessageMeta {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut out = bytesrepr::allocate_buffer(self)?;
+        out.extend(self.sender.to_bytes()?);
+        out.extend(self.dst_chain_id.to_bytes()?);
+        out.extend(self.nonce.to_bytes()?);
+        out.extend(self.timestamp.to_bytes()?);
+        out.extend(self.deadline.to_bytes()?);
+        Ok(out)
+    }
+
+    fn serialized_length(&self) -> usize {
+        self.sender.serialized_length()
+            + self.dst_chain_id.serialized_length()
+            + self.nonce.serialized_length()
+            + self.timestamp.serialized_length()
+            + self.deadline.serialized_length()
+    }
+}
+
+impl FromBytes for MessageMeta {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+        let (sender, rem) = AccountHash::from_bytes(bytes)?;
+        let (dst_chain_id, rem) = u32::from_bytes(rem)?;
+        let (nonce, rem) = u64::from_bytes(rem)?;
+        let (timestamp, rem) = u64::from_bytes(rem)?;
+        let (deadline, rem) = u64::from_bytes(rem)?;
+        Ok((
+            MessageMeta {
+                sender,
+                dst_chain_id,
+                nonce,
+                timestamp,
+                deadline,
+            },
+            rem,
+        ))
+    }
+}
+
+impl CLTyped for MessageMeta {
+    fn cl_type() -> CLType {
+        CLType::Any
+    }
+}
+
+/// Delivery settings for one chain, stored in `chain_configs`
+#[derive(Default)]
+struct ChainConfig {
+    /// `CHAIN_STATUS_*`; kept in step with the chain's supported flag
+    status: u8,
+    /// Each source gateway must deliver its nonces in sequence from 0
+    ordered: bool,
+    /// Committee signatures this chain's messages need, instead of the
+    /// global threshold
+    threshold: Option<u8>,
+    /// Gateway contract on this chain that outbound messages are bound to
+    dst_gateway: Option<Bytes>,
+    /// `SIG_SCHEME_*` every message from this chain must be signed with
+    required_sig_scheme: Option<u8>,
+    /// Length of an address on this chain; sends to it must name a receiver
+    /// of exactly this many bytes
+    addr_len: Option<u8>,
+}
+
+impl ToBytes for ChainConfig {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut out = bytesrepr::allocate_buffer(self)?;
+        out.extend(self.status.to_bytes()?);
+        out.extend(self.ordered.to_bytes()?);
+        out.extend(self.threshold.to_bytes()?);
+        out.extend(self.dst_gateway.to_bytes()?);
+        out.extend(self.required_sig_scheme.to_bytes()?);
+        out.extend(self.addr_len.to_bytes()?);
+        Ok(out)
+    }
+
+    fn serialized_length(&self) -> usize {
+        self.status.serialized_length()
+            + self.ordered.serialized_length()
+            + self.threshold.serialized_length()
+            + self.dst_gateway.serialized_length()
+            + self.required_sig_scheme.serialized_length()
+            + self.addr_len.serialized_length()
+    }
+}
+
+impl FromBytes for ChainConfig {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+        let (status, rem) = u8::from_bytes(bytes)?;
+        let (ordered, rem) = bool::from_bytes(rem)?;
+        let (threshold, rem) = Option::<u8>::from_bytes(rem)?;
+        let (dst_gateway, rem) = Option::<Bytes>::from_bytes(rem)?;
+        let (required_sig_scheme, rem) = Option::<u8>::from_bytes(rem)?;
+        let (addr_len, rem) = Option::<u8>::from_bytes(rem)?;
+        Ok((
+            ChainConfig {
+                status,
+                ordered,
+                threshold,
+                dst_gateway,
+                required_sig_scheme,
+                addr_len,
+            },
+            rem,
+        ))
+    }
+}
+
+impl CLTyped for ChainConfig {
+    fn cl_type() -> CLType {
+        CLType::Any
+    }
+}
+
+/// Whatever a receiver handler returned, undecoded. Taking the raw bytes
+/// means a reply of the wrong type can be handled instead of trapping in
+/// `call_contract`.
+struct ReceiverReply(Vec<u8>);
+
+impl FromBytes for ReceiverReply {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+        Ok((ReceiverReply(bytes.to_vec()), &[]))
+    }
+}
+
+impl CLTyped for ReceiverReply {
+    fn cl_type() -> CLType {
+        CLType::Any
+    }
+}
+
+/// ------------------------------------------------
+/// Errors
+/// ------------------------------------------------
+
+/// Declares `Error` together with `error_name`, so every variant has its
+/// label by construction.
+macro_rules! errors {
+    ($($name:ident = $code:literal,)*) => {
+        #[repr(u16)]
+        enum Error {
+            $($name = $code,)*
+        }
+
+        /// Variant name for a user error code; "Unknown" for anything else.
+        fn error_name(code: u16) -> &'static str {
+            match code {
+                $($code => stringify!($name),)*
+                _ => "Unknown",
+            }
+        }
+    };
+}
+
+errors! {
+    UnsupportedChain = 1,
+    AlreadyExecuted = 2,
+    InvalidReceiver = 3,
+    MissingKey = 4,
+    InvalidSignature = 5,
+    NullReceiverPayload = 6,
+    UnknownMessage = 7,
+    Unauthorized = 8,
+    FeeNotPaid = 9,
+    NotRefundable = 10,
+    InboundPayloadTooLarge = 11,
+    UntrustedGateway = 12,
+    Paused = 13,
+    InvalidCommittee = 14,
+    ThresholdNotMet = 15,
+    ValueNotDeliverable = 16,
+    InsufficientEscrow = 17,
+    InDelivery = 18,
+    InvalidAdminAction = 19,
+    DisputeWindowClosed = 20,
+    RateLimited = 21,
+    NoFallbackReceiver = 22,
+    OutOfOrder = 23,
+    UnmappedMessageType = 24,
+    UnsupportedSigScheme = 25,
+    DuplicateIdemKey = 26,
+    InvalidVersion = 27,
+    VersionNotIncreased = 28,
+    InvalidSourceChain = 29,
+    TooManyReceivers = 30,
+    CasMismatch = 31,
+    UnsupportedPayloadVersion = 32,
+    NoTrustedGateway = 33,
+    AdminReplay = 34,
+    PrefixMismatch = 35,
+    CorruptState = 36,
+    GatewayTooLarge = 37,
+    ReceiverPaused = 38,
+    SignatureTooOld = 39,
+    RelayerKeyExpired = 40,
+    TooManyPending = 41,
+    ReceiverNotFound = 42,
+    BatchTooLarge = 43,
+    StorageFull = 44,
+    MessageExpired = 45,
+    NoGovernanceReceiver = 46,
+    ChainDeprecated = 47,
+    MessageTagMismatch = 48,
+    GatewayTooShort = 49,
+    LoopbackMismatch = 50,
+    UnexpectedReturnType = 51,
+    ConfirmationMismatch = 52,
+}
+
+impl From<Error> for ApiError {
+    fn from(e: Error) -> Self {
+        ApiError::User(e as u16)
+    }
+}
+
+/// ------------------------------------------------
+/// Install
+/// ------------------------------------------------
+
+#[no_mangle]
+pub extern "C" fn call() {
+    let relayer_pubkey: PublicKey = runtime::get_named_arg("relayer_pubkey");
+
+    if !is_relayer_key(&relayer_pubkey) {
+        runtime::revert(Error::InvalidSignature);
+    }
+
+    // Optional committee to start in committee mode, as serialized keys
+    // (the format `get_relayers` returns).
+    let initial_relayers: Vec<PublicKey> =
+        runtime::try_get_named_arg::<Vec<Bytes>>("initial_relayers")
+            .unwrap_or_default()
+            .iter()
+            .map(|key| relayer_key_from_bytes(key.as_ref()))
+            .collect();
+    let initial_threshold: u8 = runtime::try_get_named_arg("initial_threshold").unwrap_or(0);
+
+    if initial_relayers.is_empty() && initial_threshold != 0 {
+        runtime::revert(Error::InvalidCommittee);
+    }
+    check_committee(&initial_relayers, initial_threshold);
+
+    let allow_null_payload: bool =
+        runtime::try_get_named_arg("allow_null_payload").unwrap_or(false);
+    let recent_ids_capacity: u32 =
+        runtime::try_get_named_arg("recent_ids_capacity").unwrap_or(DEFAULT_RECENT_IDS_CAPACITY);
+    let strict_source_chains: bool =
+        runtime::try_get_named_arg("strict_source_chains").unwrap_or(false);
+    let contract_key_name: String = runtime::try_get_named_arg("contract_key_name")
+        .unwrap_or_else(|| DEFAULT_CONTRACT_KEY_NAME.to_string());
+    let message_fee: U512 = runtime::try_get_named_arg("message_fee").unwrap_or_default();
+    let relayer_reward: U512 = runtime::try_get_named_arg("relayer_reward").unwrap_or_default();
+    let max_fanout: u32 = runtime::try_get_named_arg("max_fanout").unwrap_or(DEFAULT_MAX_FANOUT);
+    let max_batch_size: u32 =
+        runtime::try_get_named_arg("max_batch_size").unwrap_or(DEFAULT_MAX_BATCH_SIZE);
+    let max_delivery_attempts: u32 = runtime::try_get_named_arg("max_delivery_attempts")
+        .unwrap_or(DEFAULT_MAX_DELIVERY_ATTEMPTS);
+    let max_inbound_payload_len: u32 = runtime::try_get_named_arg("max_inbound_payload_len")
+        .unwrap_or(DEFAULT_MAX_INBOUND_PAYLOAD_LEN);
+    let max_gateway_len: u32 =
+        runtime::try_get_named_arg("max_gateway_len").unwrap_or(DEFAULT_MAX_GATEWAY_LEN);
+    let min_gateway_len: u32 =
+        runtime::try_get_named_arg("min_gateway_len").unwrap_or(DEFAULT_MIN_GATEWAY_LEN);
+    let signed_sends: bool = runtime::try_get_named_arg("signed_sends").unwrap_or(false);
+    let dispute_window_ms: u64 = runtime::try_get_named_arg("dispute_window_ms").unwrap_or(0);
+    let max_sig_age_ms: u64 = runtime::try_get_named_arg("max_sig_age_ms").unwrap_or(0);
+    let return_message_bytes: bool =
+        runtime::try_get_named_arg("return_message_bytes").unwrap_or(true);
+    let dedup_idem_keys: bool = runtime::try_get_named_arg("dedup_idem_keys").unwrap_or(false);
+    let loopback: bool = runtime::try_get_named_arg("loopback").unwrap_or(false);
+    let check_receivers: bool = runtime::try_get_named_arg("check_receivers").unwrap_or(false);
+    let receiver_returns_bool: bool =
+        runtime::try_get_named_arg("receiver_returns_bool").unwrap_or(false);
+    let max_active_messages: u64 = runtime::try_get_named_arg("max_active_messages").unwrap_or(0);
+    let emit_events: bool = runtime::try_get_named_arg("emit_events").unwrap_or(false);
+    let event_backend: u8 =
+        runtime::try_get_named_arg("event_backend").unwrap_or(EVENT_BACKEND_DICTIONARY);
+
+    if event_backend > EVENT_BACKEND_CES {
+        runtime::revert(ApiError::InvalidArgument);
+    }
+    let gateway_namespace: String =
+        runtime::try_get_named_arg("gateway_namespace").unwrap_or_default();
+
+    // Create storage
+    let nonce = storage::new_uref(0u64);
+    let relayer = storage::new_uref(relayer_pubkey);
+    let relayer_key_expiry =
+        storage::new_uref(runtime::try_get_named_arg::<u64>("relayer_key_expiry").unwrap_or(0));
+    let allow_null_payload = storage::new_uref(allow_null_payload);
+    let strict_source_chains = storage::new_uref(strict_source_chains);
+    let recent_ids = storage::new_uref(Vec::<String>::new());
+    let recent_ids_head = storage::new_uref(0u32);
+    let recent_ids_capacity = storage::new_uref(recent_ids_capacity);
+    let owner = storage::new_uref(runtime::get_caller());
+    let fee_purse = system::create_purse();
+    let escrow_purse = system::create_purse();
+    let admin_nonce = storage::new_uref(0u64);
+    let admin_pubkey: Option<PublicKey> = runtime::try_get_named_arg("admin_pubkey");
+    if !matches!(admin_pubkey, None | Some(PublicKey::Secp256k1(_))) {
+        runtime::revert(Error::InvalidSignature);
+    }
+    let admin_pubkey = storage::new_uref(admin_pubkey);
+    let in_delivery = storage::new_uref(false);
+    let message_fee = storage::new_uref(message_fee);
+    let refundable_total = storage::new_uref(U512::zero());
+    let reward_purse = system::create_purse();
+    let relayer_reward = storage::new_uref(relayer_reward);
+    let max_fanout = storage::new_uref(max_fanout);
+    let max_batch_size = storage::new_uref(max_batch_size);
+    let max_delivery_attempts = storage::new_uref(max_delivery_attempts);
+    let failed_messages = new_dictionary(KEY_FAILED_MESSAGES);
+    let dead_letters = new_dictionary(KEY_DEAD_LETTERS);
+    let dead_letter_count = storage::new_uref(0u64);
+    let max_inbound_payload_len = storage::new_uref(max_inbound_payload_len);
+    let max_gateway_len = storage::new_uref(max_gateway_len);
+    let min_gateway_len = storage::new_uref(min_gateway_len);
+    let signed_sends = storage::new_uref(signed_sends);
+    let dispute_window_ms = storage::new_uref(dispute_window_ms);
+    let max_sig_age_ms = storage::new_uref(max_sig_age_ms);
+    let return_message_bytes = storage::new_uref(return_message_bytes);
+    let sender_rate_limit = storage::new_uref((0u32, 0u64));
+    let sender_windows = new_dictionary(KEY_SENDER_WINDOWS);
+    let dedup_idem_keys = storage::new_uref(dedup_idem_keys);
+    let loopback = storage::new_uref(loopback);
+    let check_receivers = storage::new_uref(check_receivers);
+    let receiver_returns_bool = storage::new_uref(receiver_returns_bool);
+    let max_active_messages = storage::new_uref(max_active_messages);
+    let weight_coefficients = storage::new_uref(DEFAULT_WEIGHT_COEFFICIENTS);
+    let global_seq = storage::new_uref(0u64);
+    let emit_events = storage::new_uref(emit_events);
+    let event_count = storage::new_uref(0u64);
+    let events = new_dictionary(KEY_EVENTS);
+    let gateway_namespace = storage::new_uref(gateway_namespace);
+    let sender_idem_keys = new_dictionary(KEY_SENDER_IDEM_KEYS);
+    let confirmations = new_dictionary(KEY_CONFIRMATIONS);
+    let confirmation_digests = new_dictionary(KEY_CONFIRMATION_DIGESTS);
+    let pending_counts = new_dictionary(KEY_PENDING_COUNTS);
+    let max_pending = storage::new_uref(0u32);
+    let chain_aliases = new_dictionary(KEY_CHAIN_ALIASES);
+    let fallback_receiver = storage::new_uref(None::<Bytes>);
+    let governance_receiver = storage::new_uref(None::<Bytes>);
+    let chain_registry = storage::new_uref(None::<ContractHash>);
+    let reward_token = storage::new_uref(None::<ContractHash>);
+    let redirected_messages = new_dictionary(KEY_REDIRECTED_MESSAGES);
+    let stored_messages = new_dictionary(KEY_STORED_MESSAGES);
+    let mac_keys = new_dictionary(KEY_MAC_KEYS);
+    let message_tags = new_dictionary(KEY_MESSAGE_TAGS);
+    // Optional install-time tagging key. Global state is readable by anyone,
+    // so the tag catches corrupted state, not a party holding the key.
+    let mac_key_epoch = match runtime::try_get_named_arg::<Bytes>("mac_key") {
+        Some(mac_key) if !mac_key.is_empty() => {
+            storage::dictionary_put(mac_keys, "1", mac_key);
+            1u32
+        }
+        Some(_) => runtime::revert(ApiError::InvalidArgument),
+        None => 0u32,
+    };
+    let mac_key_epoch = storage::new_uref(mac_key_epoch);
+    let paused = storage::new_uref(false);
+    let pause_grace_until = storage::new_uref(0u64);
+    let relayers = storage::new_uref(initial_relayers);
+    let threshold = storage::new_uref(initial_threshold);
+    let error_event_count = storage::new_uref(0u64);
+    let protocol_version: String = runtime::try_get_named_arg("protocol_version")
+        .unwrap_or_else(|| DEFAULT_PROTOCOL_VERSION.to_string());
+    parse_version(&protocol_version).unwrap_or_revert();
+    let protocol_version = storage::new_uref(protocol_version);
+    let protocol_version_count = storage::new_uref(0u32);
+    let version_events = new_dictionary(KEY_VERSION_EVENTS);
+    let executed_count = storage::new_uref(0u64);
+    let outbound_count = new_dictionary(KEY_OUTBOUND_COUNT);
+    let inbound_count = new_dictionary(KEY_INBOUND_COUNT);
+    let messages_floor = storage::new_uref(0u64);
+    let active_message_count = storage::new_uref(0u64);
+    let supported_chain_count = storage::new_uref(0u32);
+    let supported_chain_list = storage::new_uref(Vec::<u32>::new());
+
+    let chain_nonces = new_dictionary(KEY_CHAIN_NONCES);
+    let supported = new_dictionary(KEY_SUPPORTED_CHAINS);
+    let executed = new_dictionary(KEY_EXECUTED_MESSAGES);
+    let replay_marks = new_dictionary(KEY_REPLAY_MARKS);
+    let messages = new_dictionary(KEY_MESSAGES);
+    let message_senders = new_dictionary(KEY_MESSAGE_SENDERS);
+    let message_meta = new_dictionary(KEY_MESSAGE_META);
+    let nonce_to_id = new_dictionary(KEY_NONCE_TO_ID);
+    let message_payload_lens = new_dictionary(KEY_MESSAGE_PAYLOAD_LENS);
+    let receiver_versions = new_dictionary(KEY_RECEIVER_VERSIONS);
+    let message_acks = new_dictionary(KEY_MESSAGE_ACKS);
+    let refundable_fees = new_dictionary(KEY_REFUNDABLE_FEES);
+    let error_events = new_dictionary(KEY_ERROR_EVENTS);
+    let error_chains = new_dictionary(KEY_ERROR_CHAINS);
+    let allowed_payload_versions = new_dictionary(KEY_ALLOWED_PAYLOAD_VERSIONS);
+    let required_prefixes = new_dictionary(KEY_REQUIRED_PREFIXES);
+    let paused_receivers = new_dictionary(KEY_PAUSED_RECEIVERS);
+    let replayable_receivers = new_dictionary(KEY_REPLAYABLE_RECEIVERS);
+    let receiver_handlers = new_dictionary(KEY_RECEIVER_HANDLERS);
+    let chain_configs = new_dictionary(KEY_CHAIN_CONFIGS);
+    let inbound_nonces = new_dictionary(KEY_INBOUND_NONCES);
+    let trusted_gateways = new_dictionary(KEY_TRUSTED_GATEWAYS);
+    let delivery_attempts = new_dictionary(KEY_DELIVERY_ATTEMPTS);
+    let chain_relayers = new_dictionary(KEY_CHAIN_RELAYERS);
+
+    let mut named_keys = NamedKeys::new();
+    named_keys.insert(KEY_NONCE.to_string(), nonce.into());
+    named_keys.insert(KEY_CHAIN_NONCES.to_string(), chain_nonces.into());
+    named_keys.insert(KEY_RELAYER_PUBKEY.to_string(), relayer.into());
+    named_keys.insert(
+        KEY_RELAYER_KEY_EXPIRY.to_string(),
+        relayer_key_expiry.into(),
+    );
+    named_keys.insert(KEY_SUPPORTED_CHAINS.to_string(), supported.into());
+    named_keys.insert(KEY_EXECUTED_MESSAGES.to_string(), executed.into());
+    named_keys.insert(KEY_REPLAY_MARKS.to_string(), replay_marks.into());
+    named_keys.insert(KEY_MESSAGES.to_string(), messages.into());
+    named_keys.insert(KEY_MESSAGE_SENDERS.to_string(), message_senders.into());
+    named_keys.insert(KEY_MESSAGE_META.to_string(), message_meta.into());
+    named_keys.insert(KEY_NONCE_TO_ID.to_string(), nonce_to_id.into());
+    named_keys.insert(
+        KEY_MESSAGE_PAYLOAD_LENS.to_string(),
+        message_payload_lens.into(),
+    );
+    named_keys.insert(KEY_RECEIVER_VERSIONS.to_string(), receiver_versions.into());
+    named_keys.insert(KEY_MESSAGE_ACKS.to_string(), message_acks.into());
+    named_keys.insert(KEY_REFUNDABLE_FEES.to_string(), refundable_fees.into());
+    named_keys.insert(KEY_ERROR_EVENTS.to_string(), error_events.into());
+    named_keys.insert(KEY_ERROR_CHAINS.to_string(), error_chains.into());
+    named_keys.insert(KEY_ERROR_EVENT_COUNT.to_string(), error_event_count.into());
+    named_keys.insert(KEY_PROTOCOL_VERSION.to_string(), protocol_version.into());
+    named_keys.insert(
+        KEY_PROTOCOL_VERSION_COUNT.to_string(),
+        protocol_version_count.into(),
+    );
+    named_keys.insert(KEY_VERSION_EVENTS.to_string(), version_events.into());
+    named_keys.insert(KEY_EXECUTED_COUNT.to_string(), executed_count.into());
+    named_keys.insert(KEY_OUTBOUND_COUNT.to_string(), outbound_count.into());
+    named_keys.insert(KEY_INBOUND_COUNT.to_string(), inbound_count.into());
+    named_keys.insert(KEY_MESSAGES_FLOOR.to_string(), messages_floor.into());
+    named_keys.insert(
+        KEY_ACTIVE_MESSAGE_COUNT.to_string(),
+        active_message_count.into(),
+    );
+    named_keys.insert(
+        KEY_SUPPORTED_CHAIN_COUNT.to_string(),
+        supported_chain_count.into(),
+    );
+    named_keys.insert(
+        KEY_SUPPORTED_CHAIN_LIST.to_string(),
+        supported_chain_list.into(),
+    );
+    named_keys.insert(
+        KEY_ALLOWED_PAYLOAD_VERSIONS.to_string(),
+        allowed_payload_versions.into(),
+    );
+    named_keys.insert(KEY_REQUIRED_PREFIXES.to_string(), required_prefixes.into());
+    named_keys.insert(KEY_PAUSED_RECEIVERS.to_string(), paused_receivers.into());
+    named_keys.insert(
+        KEY_REPLAYABLE_RECEIVERS.to_string(),
+        replayable_receivers.into(),
+    );
+    named_keys.insert(KEY_RECEIVER_HANDLERS.to_string(), receiver_handlers.into());
+    named_keys.insert(KEY_CHAIN_CONFIGS.to_string(), chain_configs.into());
+    named_keys.insert(KEY_INBOUND_NONCES.to_string(), inbound_nonces.into());
+    named_keys.insert(KEY_TRUSTED_GATEWAYS.to_string(), trusted_gateways.into());
+    named_keys.insert(KEY_DELIVERY_ATTEMPTS.to_string(), delivery_attempts.into());
+    named_keys.insert(KEY_CHAIN_RELAYERS.to_string(), chain_relayers.into());
+    named_keys.insert(KEY_SIGNED_SENDS.to_string(), signed_sends.into());
+    named_keys.insert(KEY_PAUSED.to_string(), paused.into());
+    named_keys.insert(KEY_PAUSE_GRACE_UNTIL.to_string(), pause_grace_until.into());
+    named_keys.insert(KEY_RELAYERS.to_string(), relayers.into());
+    named_keys.insert(KEY_THRESHOLD.to_string(), threshold.into());
+    named_keys.insert(
+        KEY_ALLOW_NULL_PAYLOAD.to_string(),
+        allow_null_payload.into(),
+    );
+    named_keys.insert(
+        KEY_STRICT_SOURCE_CHAINS.to_string(),
+        strict_source_chains.into(),
+    );
+    named_keys.insert(KEY_RECENT_IDS.to_string(), recent_ids.into());
+    named_keys.insert(KEY_RECENT_IDS_HEAD.to_string(), recent_ids_head.into());
+    named_keys.insert(
+        KEY_RECENT_IDS_CAPACITY.to_string(),
+        recent_ids_capacity.into(),
+    );
+    named_keys.insert(KEY_OWNER.to_string(), owner.into());
+    named_keys.insert(KEY_FEE_PURSE.to_string(), fee_purse.into());
+    named_keys.insert(KEY_ESCROW_PURSE.to_string(), escrow_purse.into());
+    named_keys.insert(KEY_ADMIN_NONCE.to_string(), admin_nonce.into());
+    named_keys.insert(KEY_ADMIN_PUBKEY.to_string(), admin_pubkey.into());
+    named_keys.insert(KEY_IN_DELIVERY.to_string(), in_delivery.into());
+    named_keys.insert(KEY_MESSAGE_FEE.to_string(), message_fee.into());
+    named_keys.insert(KEY_REFUNDABLE_TOTAL.to_string(), refundable_total.into());
+    named_keys.insert(KEY_REWARD_PURSE.to_string(), reward_purse.into());
+    named_keys.insert(KEY_RELAYER_REWARD.to_string(), relayer_reward.into());
+    named_keys.insert(KEY_REWARD_TOKEN.to_string(), reward_token.into());
+    named_keys.insert(KEY_MAX_FANOUT.to_string(), max_fanout.into());
+    named_keys.insert(KEY_MAX_BATCH_SIZE.to_string(), max_batch_size.into());
+    named_keys.insert(
+        KEY_MAX_DELIVERY_ATTEMPTS.to_string(),
+        max_delivery_attempts.into(),
+    );
+    named_keys.insert(KEY_FAILED_MESSAGES.to_string(), failed_messages.into());
+    named_keys.insert(KEY_DEAD_LETTERS.to_string(), dead_letters.into());
+    named_keys.insert(KEY_DEAD_LETTER_COUNT.to_string(), dead_letter_count.into());
+    named_keys.insert(
+        KEY_MAX_INBOUND_PAYLOAD_LEN.to_string(),
+        max_inbound_payload_len.into(),
+    );
+    named_keys.insert(KEY_MAX_GATEWAY_LEN.to_string(), max_gateway_len.into());
+    named_keys.insert(KEY_MIN_GATEWAY_LEN.to_string(), min_gateway_len.into());
+    named_keys.insert(KEY_DISPUTE_WINDOW_MS.to_string(), dispute_window_ms.into());
+    named_keys.insert(KEY_MAX_SIG_AGE_MS.to_string(), max_sig_age_ms.into());
+    named_keys.insert(
+        KEY_RETURN_MESSAGE_BYTES.to_string(),
+        return_message_bytes.into(),
+    );
+    named_keys.insert(KEY_SENDER_RATE_LIMIT.to_string(), sender_rate_limit.into());
+    named_keys.insert(KEY_SENDER_WINDOWS.to_string(), sender_windows.into());
+    named_keys.insert(KEY_DEDUP_IDEM_KEYS.to_string(), dedup_idem_keys.into());
+    named_keys.insert(KEY_LOOPBACK.to_string(), loopback.into());
+    named_keys.insert(KEY_CHECK_RECEIVERS.to_string(), check_receivers.into());
+    named_keys.insert(
+        KEY_RECEIVER_RETURNS_BOOL.to_string(),
+        receiver_returns_bool.into(),
+    );
+    named_keys.insert(
+        KEY_MAX_ACTIVE_MESSAGES.to_string(),
+        max_active_messages.into(),
+    );
+    named_keys.insert(
+        KEY_WEIGHT_COEFFICIENTS.to_string(),
+        weight_coefficients.into(),
+    );
+    named_keys.insert(KEY_GLOBAL_SEQ.to_string(), global_seq.into());
+    named_keys.insert(KEY_EMIT_EVENTS.to_string(), emit_events.into());
+    named_keys.insert(
+        KEY_EVENT_BACKEND.to_string(),
+        storage::new_uref(event_backend).into(),
+    );
+    named_keys.insert(KEY_EVENT_COUNT.to_string(), event_count.into());
+
+    if event_backend == EVENT_BACKEND_CES {
+        named_keys.insert(
+            KEY_CES_EVENTS.to_string(),
+            new_dictionary(KEY_CES_EVENTS).into(),
+        );
+        named_keys.insert(
+            KEY_CES_EVENTS_LENGTH.to_string(),
+            storage::new_uref(0u32).into(),
+        );
+        named_keys.insert(
+            KEY_CES_EVENTS_SCHEMA.to_string(),
+            storage::new_uref(CesSchemas).into(),
+        );
+        named_keys.insert(
+            KEY_CES_VERSION.to_string(),
+            storage::new_uref(CES_VERSION.to_string()).into(),
+        );
+    }
+    named_keys.insert(KEY_EVENTS.to_string(), events.into());
+    named_keys.insert(KEY_GATEWAY_NAMESPACE.to_string(), gateway_namespace.into());
+    named_keys.insert(KEY_SENDER_IDEM_KEYS.to_string(), sender_idem_keys.into());
+    named_keys.insert(KEY_CONFIRMATIONS.to_string(), confirmations.into());
+    named_keys.insert(
+        KEY_CONFIRMATION_DIGESTS.to_string(),
+        confirmation_digests.into(),
+    );
+    named_keys.insert(KEY_PENDING_COUNTS.to_string(), pending_counts.into());
+    named_keys.insert(KEY_MAX_PENDING.to_string(), max_pending.into());
+    named_keys.insert(KEY_CHAIN_ALIASES.to_string(), chain_aliases.into());
+    named_keys.insert(KEY_FALLBACK_RECEIVER.to_string(), fallback_receiver.into());
+    named_keys.insert(
+        KEY_GOVERNANCE_RECEIVER.to_string(),
+        governance_receiver.into(),
+    );
+    named_keys.insert(KEY_CHAIN_REGISTRY.to_string(), chain_registry.into());
+    named_keys.insert(KEY_STORED_MESSAGES.to_string(), stored_messages.into());
+    named_keys.insert(KEY_MAC_KEY_EPOCH.to_string(), mac_key_epoch.into());
+    named_keys.insert(KEY_MAC_KEYS.to_string(), mac_keys.into());
+    named_keys.insert(KEY_MESSAGE_TAGS.to_string(), message_tags.into());
+    named_keys.insert(
+        KEY_REDIRECTED_MESSAGES.to_string(),
+        redirected_messages.into(),
+    );
+
+    // Entry points
+    let mut entry_points = EntryPoints::new();
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "send_message",
+        vec![
+            Parameter::new("dst_chain_id", CLType::U32),
+            Parameter::new("receiver", CLType::List(Box::new(CLType::U8))),
+            Parameter::new("payload", CLType::List(Box::new(CLType::U8))),
+        ],
+        CLType::List(Box::new(CLType::U8)),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "execute_message",
+        vec![
+            Parameter::new("src_chain_id", CLType::U32),
+            Parameter::new("src_gateway", CLType::List(Box::new(CLType::U8))),
+            Parameter::new("receiver", CLType::List(Box::new(CLType::U8))),
+            Parameter::new("nonce", CLType::U64),
+            Parameter::new("payload", CLType::List(Box::new(CLType::U8))),
+            Parameter::new("signature", CLType::List(Box::new(CLType::U8))),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "retry_failed",
+        vec![Parameter::new("message_id", CLType::String)],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_dead_letters",
+        vec![
+            Parameter::new("offset", CLType::U64),
+            Parameter::new("limit", CLType::U32),
+        ],
+        CLType::List(Box::new(CLType::String)),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "execute_messages_batch",
+        vec![Parameter::new(
+            "messages",
+            CLType::List(Box::new(CLType::List(Box::new(CLType::U8)))),
+        )],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_max_batch_size",
+        vec![Parameter::new("max_batch_size", CLType::U32)],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "confirm_message",
+        vec![
+            Parameter::new("src_chain_id", CLType::U32),
+            Parameter::new("src_gateway", CLType::List(Box::new(CLType::U8))),
+            Parameter::new("receiver", CLType::List(Box::new(CLType::U8))),
+            Parameter::new("nonce", CLType::U64),
+            Parameter::new("payload", CLType::List(Box::new(CLType::U8))),
+            Parameter::new(
+                "signatures",
+                CLType::List(Box::new(CLType::List(Box::new(CLType::U8)))),
+            ),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_max_payload_len",
+        vec![Parameter::new("max_payload_len", CLType::U32)],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_error_event",
+        vec![Parameter::new("index", CLType::U64)],
+        CLType::Tuple3([
+            Box::new(CLType::U32),
+            Box::new(CLType::String),
+            Box::new(CLType::Option(Box::new(CLType::U32))),
+        ]),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_max_payload_len",
+        vec![],
+        CLType::U32,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "estimate_delivery_weight",
+        vec![
+            Parameter::new("payload_len", CLType::U32),
+            Parameter::new("receiver_count", CLType::U32),
+        ],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_weight_coefficients",
+        vec![
+            Parameter::new("base", CLType::U64),
+            Parameter::new("per_byte", CLType::U64),
+            Parameter::new("per_receiver", CLType::U64),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_max_active_messages",
+        vec![Parameter::new("max_active_messages", CLType::U64)],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_max_pending",
+        vec![Parameter::new("max_pending", CLType::U32)],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_supported_chain",
+        vec![
+            Parameter::new("chain_id", CLType::U32),
+            Parameter::new("supported", CLType::Bool),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_supported_chain_cas",
+        vec![
+            Parameter::new("chain_id", CLType::U32),
+            Parameter::new("expected", CLType::Bool),
+            Parameter::new("supported", CLType::Bool),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_receiver_version",
+        vec![
+            Parameter::new("receiver", CLType::List(Box::new(CLType::U8))),
+            Parameter::new("version", CLType::U32),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_chain_alias",
+        vec![
+            Parameter::new("alias", CLType::U32),
+            Parameter::new("chain_id", CLType::U32),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_sender_rate_limit",
+        vec![
+            Parameter::new("max_messages", CLType::U32),
+            Parameter::new("window_ms", CLType::U64),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_chain_registry",
+        vec![Parameter::new(
+            "registry",
+            CLType::Option(Box::new(ContractHash::cl_type())),
+        )],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_governance_receiver",
+        vec![Parameter::new(
+            "receiver",
+            CLType::Option(Box::new(CLType::List(Box::new(CLType::U8)))),
+        )],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_fallback_receiver",
+        vec![Parameter::new(
+            "receiver",
+            CLType::Option(Box::new(CLType::List(Box::new(CLType::U8)))),
+        )],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "revoke_execution",
+        vec![Parameter::new("message_id", CLType::String)],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "ack_message",
+        vec![Parameter::new("message_id", CLType::String)],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "request_refund",
+        vec![Parameter::new("message_id", CLType::String)],
+        CLType::U512,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "rotate_fee_purse",
+        vec![Parameter::new("sweep", CLType::Bool)],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "fund_rewards",
+        vec![
+            Parameter::new("payment_purse", CLType::URef),
+            Parameter::new("amount", CLType::U512),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_reward_token",
+        vec![Parameter::new(
+            "token",
+            CLType::Option(Box::new(ContractHash::cl_type())),
+        )],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_reward_balance",
+        vec![],
+        CLType::U512,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_allowed_payload_versions",
+        vec![
+            Parameter::new("receiver", CLType::List(Box::new(CLType::U8))),
+            Parameter::new("versions", CLType::List(Box::new(CLType::U8))),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_required_prefix",
+        vec![
+            Parameter::new("receiver", CLType::List(Box::new(CLType::U8))),
+            Parameter::new("prefix", CLType::List(Box::new(CLType::U8))),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_receiver_handler",
+        vec![
+            Parameter::new("receiver", CLType::List(Box::new(CLType::U8))),
+            Parameter::new("msg_type", CLType::U8),
+            Parameter::new("entry_point", CLType::String),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_allow_replay",
+        vec![
+            Parameter::new("receiver", CLType::List(Box::new(CLType::U8))),
+            Parameter::new("allow_replay", CLType::Bool),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "pause_receiver",
+        vec![Parameter::new(
+            "receiver",
+            CLType::List(Box::new(CLType::U8)),
+        )],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "unpause_receiver",
+        vec![Parameter::new(
+            "receiver",
+            CLType::List(Box::new(CLType::U8)),
+        )],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_trusted_gateway",
+        vec![
+            Parameter::new("src_chain_id", CLType::U32),
+            Parameter::new("gateway", CLType::List(Box::new(CLType::U8))),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_relayer_key",
+        vec![
+            Parameter::new("relayer_pubkey", CLType::PublicKey),
+            Parameter::new("expiry", CLType::U64),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_admin_key",
+        vec![Parameter::new(
+            "admin_pubkey",
+            CLType::Option(Box::new(CLType::PublicKey)),
+        )],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "signed_admin_action",
+        vec![
+            Parameter::new("action", CLType::String),
+            Parameter::new("params", CLType::List(Box::new(CLType::U8))),
+            Parameter::new("admin_nonce", CLType::U64),
+            Parameter::new("signature", CLType::List(Box::new(CLType::U8))),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_admin_nonce",
+        vec![],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_chain_relayer",
+        vec![
+            Parameter::new("src_chain_id", CLType::U32),
+            Parameter::new("relayer", CLType::Option(Box::new(CLType::PublicKey))),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "rotate_mac_key",
+        vec![Parameter::new(
+            "mac_key",
+            CLType::List(Box::new(CLType::U8)),
+        )],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_chain_status",
+        vec![
+            Parameter::new("chain_id", CLType::U32),
+            Parameter::new("status", CLType::U8),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "import_chain_configs",
+        vec![Parameter::new(
+            "configs",
+            CLType::List(Box::new(CLType::U8)),
+        )],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "configure_chain",
+        vec![
+            Parameter::new("chain_id", CLType::U32),
+            Parameter::new("supported", CLType::Bool),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_chain_sig_scheme",
+        vec![
+            Parameter::new("chain_id", CLType::U32),
+            Parameter::new("sig_scheme", CLType::Option(Box::new(CLType::U8))),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_chain_threshold",
+        vec![
+            Parameter::new("chain_id", CLType::U32),
+            Parameter::new("threshold", CLType::Option(Box::new(CLType::U8))),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_chain_ordered",
+        vec![
+            Parameter::new("chain_id", CLType::U32),
+            Parameter::new("ordered", CLType::Bool),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_chain_config",
+        vec![Parameter::new("chain_id", CLType::U32)],
+        CLType::Any,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "is_chain_ordered",
+        vec![Parameter::new("chain_id", CLType::U32)],
+        CLType::Bool,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_trusted_gateway",
+        vec![Parameter::new("src_chain_id", CLType::U32)],
+        CLType::List(Box::new(CLType::U8)),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_delivery_attempts",
+        vec![Parameter::new("message_id", CLType::String)],
+        CLType::U32,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "migrate_to_committee",
+        vec![],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_committee",
+        vec![
+            Parameter::new("relayers", CLType::List(Box::new(CLType::PublicKey))),
+            Parameter::new("threshold", CLType::U8),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "pause",
+        vec![Parameter::new("grace_until", CLType::U64)],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "unpause",
+        vec![],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_dict_uref",
+        vec![Parameter::new("name", CLType::String)],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "describe_named_key",
+        vec![Parameter::new("name", CLType::String)],
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_recent_message_ids",
+        vec![],
+        CLType::List(Box::new(CLType::String)),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_execution_record",
+        vec![Parameter::new("message_id", CLType::String)],
+        CLType::Any,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_messages_meta",
+        vec![Parameter::new(
+            "message_ids",
+            CLType::List(Box::new(CLType::String)),
+        )],
+        CLType::Any,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "are_executed",
+        vec![Parameter::new(
+            "message_ids",
+            CLType::List(Box::new(CLType::String)),
+        )],
+        CLType::List(Box::new(CLType::Bool)),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "are_chains_supported",
+        vec![Parameter::new(
+            "chain_ids",
+            CLType::List(Box::new(CLType::U32)),
+        )],
+        CLType::List(Box::new(CLType::Bool)),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_message_sender",
+        vec![Parameter::new("message_id", CLType::String)],
+        CLType::ByteArray(32),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_message_id_by_nonce",
+        vec![
+            Parameter::new("dst_chain_id", CLType::U32),
+            Parameter::new("nonce", CLType::U64),
+        ],
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_nonce",
+        vec![],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_nonce_range",
+        vec![],
+        CLType::Tuple2([Box::new(CLType::U64), Box::new(CLType::U64)]),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_chain_traffic",
+        vec![Parameter::new("chain_id", CLType::U32)],
+        CLType::Tuple2([Box::new(CLType::U64), Box::new(CLType::U64)]),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_chain_nonce",
+        vec![Parameter::new("chain_id", CLType::U32)],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "is_executed",
+        vec![Parameter::new("message_id", CLType::String)],
+        CLType::Bool,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_message",
+        vec![Parameter::new("nonce", CLType::U64)],
+        CLType::List(Box::new(CLType::U8)),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_message_payload",
+        vec![Parameter::new("nonce", CLType::U64)],
+        CLType::List(Box::new(CLType::U8)),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "build_message",
+        vec![
+            Parameter::new("src_chain_id", CLType::U32),
+            Parameter::new("dst_chain_id", CLType::U32),
+            Parameter::new("src_gateway", CLType::List(Box::new(CLType::U8))),
+            Parameter::new("receiver", CLType::List(Box::new(CLType::U8))),
+            Parameter::new("nonce", CLType::U64),
+            Parameter::new("payload", CLType::List(Box::new(CLType::U8))),
+        ],
+        CLType::List(Box::new(CLType::U8)),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "verify_message_id",
+        vec![
+            Parameter::new("message_id", CLType::String),
+            Parameter::new("src_chain_id", CLType::U32),
+            Parameter::new("dst_chain_id", CLType::U32),
+            Parameter::new("src_gateway", CLType::List(Box::new(CLType::U8))),
+            Parameter::new("receiver", CLType::List(Box::new(CLType::U8))),
+            Parameter::new("nonce", CLType::U64),
+            Parameter::new("payload", CLType::List(Box::new(CLType::U8))),
+        ],
+        CLType::Bool,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "layout_selftest",
+        vec![],
+        CLType::Tuple2([
+            Box::new(CLType::List(Box::new(CLType::U8))),
+            Box::new(CLType::String),
+        ]),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "describe_error",
+        vec![Parameter::new("code", CLType::U32)],
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "bump_version",
+        vec![Parameter::new("version", CLType::String)],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_protocol_version",
+        vec![],
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_build_info",
+        vec![],
+        CLType::Any,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_threshold",
+        vec![],
+        CLType::U8,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_relayer_count",
+        vec![],
+        CLType::U32,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_self_identity",
+        vec![],
+        CLType::List(Box::new(CLType::U8)),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_supported_chains",
+        vec![],
+        CLType::List(Box::new(CLType::U32)),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "export_chain_configs",
+        vec![],
+        CLType::List(Box::new(CLType::U8)),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_relayers",
+        vec![],
+        CLType::List(Box::new(CLType::List(Box::new(CLType::U8)))),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "preview_delivery",
+        vec![
+            Parameter::new("receiver", CLType::List(Box::new(CLType::U8))),
+            Parameter::new("payload", CLType::List(Box::new(CLType::U8))),
+        ],
+        CLType::Any,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "prune_messages",
+        vec![Parameter::new("up_to", CLType::U64)],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_storage_stats",
+        vec![],
+        CLType::Any,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_stats",
+        vec![],
+        CLType::Any,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    // Deploy contract
+    let (contract_hash, _) =
+        storage::new_contract(entry_points.into(), Some(named_keys), None, None, None);
+
+    runtime::put_key(&contract_key_name, contract_hash.into());
+}
+
+/// Create a dictionary for the contract's named keys.
+///
+/// `storage::new_dictionary` also parks the seed under the installing
+/// account, which would make a second install from the same account
+/// collide. The contract owns the dictionary, so drop the account's copy.
+fn new_dictionary(name: &str) -> URef {
+    let uref = storage::new_dictionary(name).unwrap_or_revert();
+    runtime::remove_key(name);
+    uref
+}
+
+/// ------------------------------------------------
+/// send_message
+/// ------------------------------------------------
+
+#[no_mangle]
+pub extern "C" fn send_message() {
+    let dst_chain_id = resolve_chain_id(runtime::get_named_arg("dst_chain_id"));
+    let receiver: Bytes = runtime::get_named_arg("receiver");
+    let payload: Bytes = runtime::get_named_arg("payload");
+
+    if is_paused() {
+        runtime::revert(Error::Paused);
+    }
+
+    if !is_destination_supported(dst_chain_id) {
+        fail_for_chain(Error::UnsupportedChain, dst_chain_id);
+    }
+
+    let dst_config = read_chain_config(dst_chain_id);
+
+    if dst_config.status == CHAIN_STATUS_DEPRECATED {
+        fail_for_chain(Error::ChainDeprecated, dst_chain_id);
+    }
+
+    if let Some(addr_len) = dst_config.addr_len {
+        if receiver.len() != addr_len as usize {
+            fail_for_chain(Error::InvalidReceiver, dst_chain_id);
+        }
+    }
+
+    // Storage is bounded explicitly: at the cap, sends wait for a prune.
+    let max_active_messages: u64 = storage::read(get_uref(KEY_MAX_ACTIVE_MESSAGES))
+        .unwrap_or_revert()
+        .unwrap_or(0);
+    let active: u64 = storage::read(get_uref(KEY_ACTIVE_MESSAGE_COUNT))
+        .unwrap_or_revert()
+        .unwrap_or(0);
+
+    if max_active_messages > 0 && active >= max_active_messages {
+        fail(Error::StorageFull, &active.to_string());
+    }
+
+    let sender = runtime::get_caller();
+
+    check_sender_rate_limit(sender);
+
+    let fee = collect_message_fee();
+
+    // CSPR sent along with the message, escrowed until delivered elsewhere.
+    let amount: U512 = runtime::try_get_named_arg("amount").unwrap_or_default();
+    escrow_amount(amount);
+
+    let nonce_ref = get_uref(KEY_NONCE);
+    let sequence: u64 = storage::read(nonce_ref).unwrap_or_revert().unwrap_or(0);
+
+    // Each destination chain sees its own gap-free nonce sequence.
+    let nonce = read_chain_nonce(dst_chain_id);
+
+    let sender_bytes = sender.to_bytes().unwrap_or_default();
+    check_gateway_len(&sender_bytes);
+
+    // Tells the destination which receiver method handles the message.
+    let msg_type: u8 = runtime::try_get_named_arg("msg_type").unwrap_or(0);
+    let sig_scheme: u8 = runtime::try_get_named_arg("sig_scheme").unwrap_or(SIG_SCHEME_SECP256K1);
+
+    // Binds the message to the destination contract when one is configured.
+    let dst_gateway = dst_config.dst_gateway.unwrap_or_default();
+
+    let message_bytes = build_message_bytes(
+        CASPER_CHAIN_ID,
+        dst_chain_id,
+        &sender_bytes,
+        receiver.as_ref(),
+        nonce,
+        amount,
+        msg_type,
+        sig_scheme,
+        dst_gateway.as_ref(),
+        payload.as_ref(),
+    );
+
+    // Permissioned mode: the relayer signs the exact outbound bytes. They
+    // carry the sender and the chain nonce, so a signature is good for one
+    // send only.
+    let signed_sends: bool = storage::read(get_uref(KEY_SIGNED_SENDS))
+        .unwrap_or_revert()
+        .unwrap_or(false);
+
+    if signed_sends {
+        let signature: Bytes =
+            runtime::try_get_named_arg("signature").unwrap_or_revert_with(Error::InvalidSignature);
+        verify_relayer_signature(sig_scheme, &message_bytes, signature.as_ref()).unwrap_or_revert();
+    }
+
+    let messages = get_dictionary(KEY_MESSAGES);
+
+    storage::dictionary_put(
+        messages,
+        &sequence.to_string(),
+        Bytes::from(message_bytes.clone()),
+    );
+    tag_stored_message(&sequence.to_string(), &message_bytes);
+    storage::dictionary_put(
+        get_dictionary(KEY_MESSAGE_PAYLOAD_LENS),
+        &sequence.to_string(),
+        payload.len() as u32,
+    );
+
+    storage::write(nonce_ref, sequence + 1);
+
+    let active_ref = get_uref(KEY_ACTIVE_MESSAGE_COUNT);
+    let active: u64 = storage::read(active_ref).unwrap_or_revert().unwrap_or(0);
+    storage::write(active_ref, active + 1);
+    storage::dictionary_put(
+        get_dictionary(KEY_CHAIN_NONCES),
+        &dst_chain_id.to_string(),
+        nonce + 1,
+    );
+    bump_chain_count(KEY_OUTBOUND_COUNT, dst_chain_id);
+
+    let message_id = message_key(&message_bytes);
+
+    // A relative `ttl_ms` becomes an absolute deadline on this chain's
+    // clock; the relayer hands it to the destination with the message.
+    let timestamp = u64::from(runtime::get_blocktime());
+    let ttl_ms: u64 = runtime::try_get_named_arg("ttl_ms").unwrap_or(0);
+    let expires_at = if ttl_ms > 0 {
+        timestamp.saturating_add(ttl_ms)
+    } else {
+        0
+    };
+
+    storage::dictionary_put(get_dictionary(KEY_MESSAGE_SENDERS), &message_id, sender);
+    storage::dictionary_put(
+        get_dictionary(KEY_NONCE_TO_ID),
+        &nonce_key(dst_chain_id, nonce),
+        message_id.clone(),
+    );
+    storage::dictionary_put(
+        get_dictionary(KEY_MESSAGE_META),
+        &message_id,
+        MessageMeta {
+            sender,
+            dst_chain_id,
+            nonce,
+            timestamp,
+            deadline: expires_at,
+        },
+    );
+
+    // A paid message sent with a `ttl_ms` can be refunded if it never gets
+    // acked. Its refund deadline is the one the destination enforces, so a
+    // refunded message can no longer execute there.
+    if expires_at > 0 && !fee.is_zero() {
+        storage::dictionary_put(
+            get_dictionary(KEY_REFUNDABLE_FEES),
+            &message_id,
+            (fee, expires_at),
+        );
+        write_refundable_total(read_refundable_total() + fee);
+    }
+
+    record_idem_key(sender, &message_id);
+    record_recent_id(message_id.clone());
+    emit_event("message_sent", message_id.clone());
+
+    let return_message_bytes: bool = storage::read(get_uref(KEY_RETURN_MESSAGE_BYTES))
+        .unwrap_or_revert()
+        .unwrap_or(true);
+
+    // The id serializes like `Bytes`, so `call_contract::<Bytes>` callers
+    // still decode it, as the id's UTF-8.
+    if !return_message_bytes {
+        runtime::ret(CLValue::from_t(message_id).unwrap_or_revert());
+    }
+
+    runtime::ret(CLValue::from_t(Bytes::from(message_bytes)).unwrap_or_revert());
+}
+
+/// execute_message
+///
+/// Every check (signatures, replay, ordering, delivery rules) and every
+/// gateway write (replay mark, execution record, counters, reward) happens
+/// before the first receiver call. Receivers run last, with whatever gas is
+/// left, and already see the message as executed.
+///
+/// `check_receivers` only covers receivers pinned with `set_receiver_version`:
+/// their package must hold the pinned version, enabled. A plain contract hash
+/// cannot be probed, so a missing one still traps in the receiver call.
+#[no_mangle]
+pub extern "C" fn execute_message() {
+    let src_chain_id: u32 = runtime::get_named_arg("src_chain_id");
+    let src_gateway: Bytes = runtime::get_named_arg("src_gateway");
+    let receiver: Bytes = runtime::get_named_arg("receiver");
+    let nonce: u64 = runtime::get_named_arg("nonce");
+    let payload: Bytes = runtime::get_named_arg("payload");
+    let amount: U512 = runtime::try_get_named_arg("amount").unwrap_or_default();
+    let msg_type: u8 = runtime::try_get_named_arg("msg_type").unwrap_or(0);
+    let sig_scheme: u8 = runtime::try_get_named_arg("sig_scheme").unwrap_or(SIG_SCHEME_SECP256K1);
+    let use_fallback: bool = runtime::try_get_named_arg("use_fallback").unwrap_or(false);
+
+    require_not_in_delivery();
+
+    // Skipping a receiver that works is the owner's call alone.
+    if use_fallback {
+        require_owner();
+    }
+
+    // While paused, inbound messages still drain until the grace deadline.
+    if is_paused() {
+        let grace_until: u64 = storage::read(get_uref(KEY_PAUSE_GRACE_UNTIL))
+            .unwrap_or_revert()
+            .unwrap_or(0);
+
+        if u64::from(runtime::get_blocktime()) > grace_until {
+            runtime::revert(Error::Paused);
+        }
+    }
+
+    // Reject oversized payloads before paying for signature verification.
+    if payload.len() > read_max_payload_len() as usize {
+        runtime::revert(Error::InboundPayloadTooLarge);
+    }
+
+    check_gateway_len(src_gateway.as_ref());
+    check_min_gateway_len(src_gateway.as_ref());
+
+    // Fan-out: the signed `receiver` commits to this list instead of naming
+    // a single contract.
+    let receivers: Option<Vec<Bytes>> = runtime::try_get_named_arg("receivers");
+
+    if let Some(receivers) = &receivers {
+        let max_fanout: u32 = storage::read(get_uref(KEY_MAX_FANOUT))
+            .unwrap_or_revert()
+            .unwrap_or(DEFAULT_MAX_FANOUT);
+
+        if receivers.len() > max_fanout as usize {
+            runtime::revert(Error::TooManyReceivers);
+        }
+
+        if receiver.as_ref() != fanout_commitment(receivers) {
+            runtime::revert(Error::InvalidReceiver);
+        }
+    }
+
+    // The signed bytes carry the id the source chain used; everything the
+    // gateway keys on uses the canonical id.
+    let chain_id = resolve_chain_id(src_chain_id);
+
+    // A message can never originate from the chain it is delivered on,
+    // except a loopback of one this contract sent itself.
+    let loopback = chain_id == CASPER_CHAIN_ID;
+    let loopback_enabled: bool = storage::read(get_uref(KEY_LOOPBACK))
+        .unwrap_or_revert()
+        .unwrap_or(false);
+
+    if loopback && !loopback_enabled {
+        runtime::revert(Error::InvalidSourceChain);
+    }
+
+    // In strict mode only configured chains may deliver inbound messages.
+    let strict: bool = storage::read(get_uref(KEY_STRICT_SOURCE_CHAINS))
+        .unwrap_or_revert()
+        .unwrap_or(false);
+
+    if strict && !is_chain_supported(chain_id) {
+        runtime::revert(Error::UnsupportedChain);
+    }
+
+    // Once a chain has a trusted gateway, nothing else may speak for it.
+    if let Some(trusted) = read_trusted_gateway(chain_id) {
+        if trusted != src_gateway {
+            runtime::revert(Error::UntrustedGateway);
+        }
+    }
+
+    let dst_gateway = inbound_dst_gateway(chain_id);
+
+    let message_bytes = build_message_bytes(
+        src_chain_id,
+        CASPER_CHAIN_ID,
+        src_gateway.as_ref(),
+        receiver.as_ref(),
+        nonce,
+        amount,
+        msg_type,
+        sig_scheme,
+        dst_gateway.as_ref(),
+        payload.as_ref(),
+    );
+
+    let message_key = message_key(&message_bytes);
+
+    // The id covers the receiver, so a loopback aimed anywhere but where the
+    // message was sent matches no sent message.
+    if loopback
+        && read_dictionary::<AccountHash>(get_dictionary(KEY_MESSAGE_SENDERS), &message_key)
+            .is_none()
+    {
+        runtime::revert(Error::LoopbackMismatch);
+    }
+
+    // Store-only: keep the message for pull-based reads via `get_message`
+    // instead of calling any receiver.
+    let store_only: bool = runtime::try_get_named_arg("store_only").unwrap_or(false);
+    let stored_bytes = store_only.then(|| Bytes::from(message_bytes.clone()));
+
+    let signed_bytes = inbound_signed_bytes(message_bytes, &message_key);
+
+    if let Err(error) = verify_inbound_signatures(chain_id, sig_scheme, &signed_bytes, &message_key)
+    {
+        fail(error, &message_key);
+    }
+
+    // Counted before anything below can fail. A hard revert rolls the count
+    // back with everything else; `soft_fail` failures keep it.
+    let attempts_dict = get_dictionary(KEY_DELIVERY_ATTEMPTS);
+    let attempts: u32 = read_dictionary(attempts_dict, &message_key).unwrap_or(0);
+    storage::dictionary_put(attempts_dict, &message_key, attempts + 1);
+
+    // Replay is keyed on where the message came from, not on its id, so a
+    // change to the message layout cannot make an old delivery look new.
+    // Outside ordered mode the nonce is only part of that key: any u64 the
+    // source chain uses works, in any order.
+    let replay_key = replay_key(chain_id, src_gateway.as_ref(), nonce);
+    let replay_marks = get_dictionary(KEY_REPLAY_MARKS);
+
+    // A receiver that opted into replays skips the check: the same message
+    // can be delivered to it again and again. The mark is still written, so
+    // a replay is known as one and pays the relayer nothing.
+    let replayable = !store_only && receivers.is_none() && is_replayable(receiver.as_ref());
+    let seen: Option<bool> = read_dictionary(replay_marks, &replay_key);
+
+    if seen == Some(true) && !replayable {
+        fail(Error::AlreadyExecuted, &message_key);
+    }
+
+    // From here on a soft failure leaves the delivery retryable. A hard
+    // revert takes this write back with everything else.
+    let soft_fail: bool = runtime::try_get_named_arg("soft_fail").unwrap_or(false);
+    let failed_messages = get_dictionary(KEY_FAILED_MESSAGES);
+
+    if soft_fail {
+        let args = delivery_args().to_bytes().unwrap_or_revert();
+        storage::dictionary_put(failed_messages, &message_key, Some(Bytes::from(args)));
+    }
+
+    let ordered_stream = if read_chain_config(chain_id).ordered {
+        let stream = stream_key(chain_id, src_gateway.as_ref());
+        let expected: u64 =
+            read_dictionary(get_dictionary(KEY_INBOUND_NONCES), &stream).unwrap_or(0);
+
+        if nonce != expected {
+            fail(Error::OutOfOrder, &message_key);
+        }
+
+        Some(stream)
+    } else {
+        None
+    };
+
+    // Value goes to exactly one contract, once; there is no split or sink
+    // for it, and a replay would pay it out of escrow again.
+    if !amount.is_zero()
+        && (replayable || store_only || receivers.is_some() || receiver.as_ref() == NULL_RECEIVER)
+    {
+        runtime::revert(Error::ValueNotDeliverable);
+    }
+
+    // A revert inside a receiver cannot be caught: it undoes this whole call.
+    // So only a receiver address that cannot name a contract falls back on
+    // its own; for a receiver that reverts, the owner retries the delivery
+    // with `use_fallback`.
+    let governance = msg_type == MSG_TYPE_GOVERNANCE;
+
+    if store_only && (governance || use_fallback) {
+        runtime::revert(Error::InvalidReceiver);
+    }
+
+    let single =
+        !store_only && (governance || (receivers.is_none() && receiver.as_ref() != NULL_RECEIVER));
+    let target = if governance {
+        // Only the owner's governance receiver acts on governance messages.
+        if receivers.is_some() || use_fallback || !amount.is_zero() {
+            runtime::revert(Error::InvalidReceiver);
+        }
+
+        let governance_receiver: Option<Bytes> = storage::read(get_uref(KEY_GOVERNANCE_RECEIVER))
+            .unwrap_or_revert()
+            .flatten();
+
+        governance_receiver.unwrap_or_revert_with(Error::NoGovernanceReceiver)
+    } else if single && (use_fallback || receiver_to_hash_addr(receiver.as_ref()).is_err()) {
+        let fallback: Option<Bytes> = storage::read(get_uref(KEY_FALLBACK_RECEIVER))
+            .unwrap_or_revert()
+            .flatten();
+
+        match fallback {
+            Some(fallback) => fallback,
+            None if use_fallback => runtime::revert(Error::NoFallbackReceiver),
+            None => receiver.clone(),
+        }
+    } else if use_fallback {
+        runtime::revert(Error::InvalidReceiver);
+    } else {
+        receiver.clone()
+    };
+
+    // Check every receiver before marking or calling any of them.
+    match &receivers {
+        _ if store_only => {}
+        Some(receivers) => {
+            for receiver in receivers {
+                check_delivery_rules(receiver.as_ref(), msg_type, payload.as_ref(), &message_key);
+            }
+        }
+        None if single => {
+            check_delivery_rules(target.as_ref(), msg_type, payload.as_ref(), &message_key);
+        }
+        None => {
+            let allow_payload: bool = storage::read(get_uref(KEY_ALLOW_NULL_PAYLOAD))
+                .unwrap_or_revert()
+                .unwrap_or(false);
+
+            if !payload.is_empty() && !allow_payload {
+                runtime::revert(Error::NullReceiverPayload);
+            }
+        }
+    }
+
+    storage::dictionary_put(replay_marks, &replay_key, true);
+
+    if let Some(stream) = ordered_stream {
+        storage::dictionary_put(
+            get_dictionary(KEY_INBOUND_NONCES),
+            &stream,
+            nonce.saturating_add(1),
+        );
+    }
+
+    let record = ExecutionRecord {
+        src_chain_id: chain_id,
+        src_gateway: src_gateway.clone(),
+        nonce,
+        executed_at: u64::from(runtime::get_blocktime()),
+    };
+    if !replayable {
+        storage::dictionary_put(
+            get_dictionary(KEY_EXECUTED_MESSAGES),
+            &message_key,
+            Some(record),
+        );
+    }
+
+    let executed_ref = get_uref(KEY_EXECUTED_COUNT);
+    let executed: u64 = storage::read(executed_ref).unwrap_or_revert().unwrap_or(0);
+    storage::write(executed_ref, executed + 1);
+    bump_chain_count(KEY_INBOUND_COUNT, chain_id);
+
+    let failed: Option<Option<Bytes>> = read_dictionary(failed_messages, &message_key);
+    if failed.flatten().is_some() {
+        storage::dictionary_put(failed_messages, &message_key, None::<Bytes>);
+    }
+
+    settle_confirmations(chain_id, &message_key);
+    emit_event("message_executed", message_key.clone());
+
+    if target != receiver && !governance {
+        storage::dictionary_put(
+            get_dictionary(KEY_REDIRECTED_MESSAGES),
+            &message_key,
+            receiver.clone(),
+        );
+    }
+
+    if seen != Some(true) {
+        pay_relayer_reward();
+    }
+
+    if let Some(stored_bytes) = stored_bytes {
+        tag_stored_message(&message_key, stored_bytes.as_ref());
+        storage::dictionary_put(
+            get_dictionary(KEY_STORED_MESSAGES),
+            &message_key,
+            stored_bytes,
+        );
+        return;
+    }
+
+    let args = runtime_args! {
+        "src_chain_id" => chain_id,
+        "src_gateway" => src_gateway,
+        "payload" => payload.clone(),
+        "amount" => amount,
+        "msg_type" => msg_type,
+        "message_id" => message_key.clone(),
+    };
+
+    // Fan-out receivers are called one after another in list order. Under
+    // `soft_fail` a failed reply is recorded and the rest still get theirs.
+    if let Some(receivers) = receivers {
+        for receiver in receivers {
+            if let Err(error) = deliver(receiver.as_ref(), msg_type, args.clone()) {
+                record_failure(error, &message_key);
+            }
+        }
+        return;
+    }
+
+    // Null receiver: record the delivery, call nothing.
+    if receiver.as_ref() == NULL_RECEIVER && !governance {
+        return;
+    }
+
+    // The value lands in the receiver's deposit purse before its handler runs.
+    if !amount.is_zero() {
+        let deposit_purse: URef =
+            call_receiver(target.as_ref(), "deposit_purse", RuntimeArgs::new());
+
+        system::transfer_from_purse_to_purse(
+            get_uref(KEY_ESCROW_PURSE),
+            deposit_purse,
+            amount,
+            None,
+        )
+        .unwrap_or_revert_with(Error::InsufficientEscrow);
+    }
+
+    if let Err(error) = deliver(target.as_ref(), msg_type, args) {
+        fail(error, &message_key);
+    }
+}
+
+/// Call the receiver's handler for `msg_type`.
+///
+/// With `receiver_returns_bool` set the handler must return a `bool`. Any
+/// other reply is `UnexpectedReturnType`, left to the caller to fail with.
+/// The handler has run by then, so the message stays executed.
+fn deliver(receiver: &[u8], msg_type: u8, args: RuntimeArgs) -> Result<(), Error> {
+    let entry_point =
+        receiver_entry_point(receiver, msg_type).unwrap_or_revert_with(Error::UnmappedMessageType);
+
+    let returns_bool: bool = storage::read(get_uref(KEY_RECEIVER_RETURNS_BOOL))
+        .unwrap_or_revert()
+        .unwrap_or(false);
+
+    if !returns_bool {
+        call_receiver::<()>(receiver, &entry_point, args);
+        return Ok(());
+    }
+
+    let ReceiverReply(reply) = call_receiver(receiver, &entry_point, args);
+
+    bytesrepr::deserialize::<bool>(reply)
+        .map(|_| ())
+        .map_err(|_| Error::UnexpectedReturnType)
+}
+
+/// Handler the receiver registered for `msg_type`. Type 0 and governance
+/// messages fall back to `on_call`; any other type must be mapped.
+fn receiver_entry_point(receiver: &[u8], msg_type: u8) -> Option<String> {
+    let handlers: Option<BTreeMap<u8, String>> =
+        read_dictionary(get_dictionary(KEY_RECEIVER_HANDLERS), &to_hex(receiver));
+
+    match handlers.and_then(|mut handlers| handlers.remove(&msg_type)) {
+        Some(entry_point) => Some(entry_point),
+        None if msg_type == 0 || msg_type == MSG_TYPE_GOVERNANCE => Some("on_call".to_string()),
+        None => None,
+    }
+}
+
+/// Call an entry point on a receiver, honouring any version pin.
+fn call_receiver<T: CLTyped + FromBytes>(
+    receiver: &[u8],
+    entry_point: &str,
+    args: RuntimeArgs,
+) -> T {
+    let receiver_hash = receiver_to_contract_hash(receiver).unwrap_or_revert();
+    let in_delivery = get_uref(KEY_IN_DELIVERY);
+
+    // The receiver may send onward, but admin methods stay closed to it.
+    storage::write(in_delivery, true);
+
+    // A pinned receiver is a package hash; call exactly that version.
+    let result = match pinned_receiver_version(receiver) {
+        Some(version) => {
+            let package_hash = ContractPackageHash::new(receiver_hash.value());
+            runtime::call_versioned_contract(package_hash, Some(version), entry_point, args)
+        }
+        None => runtime::call_contract(receiver_hash, entry_point, args),
+    };
+
+    storage::write(in_delivery, false);
+    result
+}
+
+/// Execute several inbound messages in one call. Each entry of `messages`
+/// is the serialized `RuntimeArgs` of one `execute_message` call, run in
+/// order; any hard failure reverts the whole batch.
+#[no_mangle]
+pub extern "C" fn execute_messages_batch() {
+    let messages: Vec<Bytes> = runtime::get_named_arg("messages");
+
+    // Refuse before any work, so a batch that cannot fit costs little.
+    let max_batch_size: u32 = storage::read(get_uref(KEY_MAX_BATCH_SIZE))
+        .unwrap_or_revert()
+        .unwrap_or(DEFAULT_MAX_BATCH_SIZE);
+
+    if messages.len() > max_batch_size as usize {
+        runtime::revert(Error::BatchTooLarge);
+    }
+
+    require_not_in_delivery();
+
+    let gateway = self_contract_hash();
+
+    for message in messages {
+        let args: RuntimeArgs =
+            bytesrepr::deserialize(message.into()).unwrap_or_revert_with(ApiError::InvalidArgument);
+        runtime::call_contract::<()>(gateway, "execute_message", args);
+    }
+}
+
+/// The `execute_message` args the caller passed, as far as they describe
+/// the delivery itself.
+fn delivery_args() -> RuntimeArgs {
+    let mut args = RuntimeArgs::new();
+
+    copy_arg::<u32>(&mut args, "src_chain_id");
+    copy_arg::<Bytes>(&mut args, "src_gateway");
+    copy_arg::<Bytes>(&mut args, "receiver");
+    copy_arg::<u64>(&mut args, "nonce");
+    copy_arg::<Bytes>(&mut args, "payload");
+    copy_arg::<U512>(&mut args, "amount");
+    copy_arg::<u8>(&mut args, "msg_type");
+    copy_arg::<u8>(&mut args, "sig_scheme");
+    copy_arg::<Vec<Bytes>>(&mut args, "receivers");
+    copy_arg::<u64>(&mut args, "sig_timestamp");
+    copy_arg::<u64>(&mut args, "deadline");
+    copy_arg::<bool>(&mut args, "store_only");
+    copy_arg::<Bytes>(&mut args, "signature");
+    copy_arg::<Vec<Bytes>>(&mut args, "signatures");
+
+    args
+}
+
+fn copy_arg<T: CLTyped + FromBytes + ToBytes>(args: &mut RuntimeArgs, name: &str) {
+    if let Some(value) = runtime::try_get_named_arg::<T>(name) {
+        args.insert(name, value).unwrap_or_revert();
+    }
+}
+
+/// Run a softly failed delivery again, e.g. once its receiver is unpaused.
+/// If it fails again after `max_delivery_attempts` attempts in all, it
+/// moves to the dead letters and cannot be retried any more.
+#[no_mangle]
+pub extern "C" fn retry_failed() {
+    require_owner();
+
+    let message_id: String = runtime::get_named_arg("message_id");
+
+    let failed_messages = get_dictionary(KEY_FAILED_MESSAGES);
+    let failed: Option<Option<Bytes>> = read_dictionary(failed_messages, &message_id);
+    let stored_args = failed
+        .flatten()
+        .unwrap_or_revert_with(Error::UnknownMessage);
+
+    let mut args: RuntimeArgs = bytesrepr::deserialize(stored_args.clone().into())
+        .unwrap_or_revert_with(Error::CorruptState);
+    args.insert("soft_fail", true).unwrap_or_revert();
+
+    runtime::call_contract::<()>(self_contract_hash(), "execute_message", args);
+
+    if is_message_executed(&message_id) {
+        return;
+    }
+
+    let attempts: u32 =
+        read_dictionary(get_dictionary(KEY_DELIVERY_ATTEMPTS), &message_id).unwrap_or(0);
+    let max_attempts: u32 = storage::read(get_uref(KEY_MAX_DELIVERY_ATTEMPTS))
+        .unwrap_or_revert()
+        .unwrap_or(DEFAULT_MAX_DELIVERY_ATTEMPTS);
+
+    if attempts < max_attempts {
+        return;
+    }
+
+    let count_ref = get_uref(KEY_DEAD_LETTER_COUNT);
+    let count: u64 = storage::read(count_ref).unwrap_or_revert().unwrap_or(0);
+
+    storage::dictionary_put(
+        get_dictionary(KEY_DEAD_LETTERS),
+        &count.to_string(),
+        (message_id.clone(), stored_args),
+    );
+    storage::write(count_ref, count + 1);
+    storage::dictionary_put(failed_messages, &message_id, None::<Bytes>);
+}
+
+/// Message ids in the dead letters, oldest first, from index `offset`.
+#[no_mangle]
+pub extern "C" fn get_dead_letters() {
+    let offset: u64 = runtime::get_named_arg("offset");
+    let limit: u32 = runtime::get_named_arg("limit");
+
+    let count: u64 = storage::read(get_uref(KEY_DEAD_LETTER_COUNT))
+        .unwrap_or_revert()
+        .unwrap_or(0);
+    let end = offset
+        .saturating_add(u64::from(limit.min(MAX_DEAD_LETTERS_PAGE)))
+        .min(count);
+
+    let dead_letters = get_dictionary(KEY_DEAD_LETTERS);
+    let ids: Vec<String> = (offset..end)
+        .map(|index| {
+            let (message_id, _): (String, Bytes) =
+                read_dictionary(dead_letters, &index.to_string())
+                    .unwrap_or_revert_with(Error::CorruptState);
+            message_id
+        })
+        .collect();
+
+    runtime::ret(CLValue::from_t(ids).unwrap_or_revert());
+}
+
+/// Cap the messages one `execute_messages_batch` call may carry.
+#[no_mangle]
+pub extern "C" fn set_max_batch_size() {
+    require_owner();
+
+    let max_batch_size: u32 = runtime::get_named_arg("max_batch_size");
+
+    storage::write(get_uref(KEY_MAX_BATCH_SIZE), max_batch_size);
+}
+
+/// Cap the unpruned messages `send_message` lets accumulate; 0 lifts it.
+#[no_mangle]
+pub extern "C" fn set_max_active_messages() {
+    require_owner();
+
+    let max_active_messages: u64 = runtime::get_named_arg("max_active_messages");
+
+    storage::write(get_uref(KEY_MAX_ACTIVE_MESSAGES), max_active_messages);
+}
+
+/// Cap the payload an inbound message may carry, in bytes.
+#[no_mangle]
+pub extern "C" fn set_max_payload_len() {
+    require_owner();
+
+    let max_payload_len: u32 = runtime::get_named_arg("max_payload_len");
+
+    storage::write(get_uref(KEY_MAX_INBOUND_PAYLOAD_LEN), max_payload_len);
+}
+
+/// `(code, context, chain_id)` of the error event at `index`; `chain_id`
+/// is set for failures about one chain, such as an unsupported destination.
+#[no_mangle]
+pub extern "C" fn get_error_event() {
+    let index: u64 = runtime::get_named_arg("index");
+    let key = index.to_string();
+
+    let (code, context): (u32, String) = read_dictionary(get_dictionary(KEY_ERROR_EVENTS), &key)
+        .unwrap_or_revert_with(ApiError::InvalidArgument);
+    let chain_id: Option<u32> = read_dictionary(get_dictionary(KEY_ERROR_CHAINS), &key);
+
+    runtime::ret(CLValue::from_t((code, context, chain_id)).unwrap_or_revert());
+}
+
+/// Longest payload `execute_message` accepts, so remote senders can size
+/// theirs before sending.
+#[no_mangle]
+pub extern "C" fn get_max_payload_len() {
+    runtime::ret(CLValue::from_t(read_max_payload_len()).unwrap_or_revert());
+}
+
+/// Rough cost of delivering a message, for relayers budgeting deploys:
+/// `base + per_byte * payload_len + per_receiver * receiver_count`. Not
+/// exact, only monotonic in both inputs.
+#[no_mangle]
+pub extern "C" fn estimate_delivery_weight() {
+    let payload_len: u32 = runtime::get_named_arg("payload_len");
+    let receiver_count: u32 = runtime::get_named_arg("receiver_count");
+
+    let (base, per_byte, per_receiver): (u64, u64, u64) =
+        storage::read(get_uref(KEY_WEIGHT_COEFFICIENTS))
+            .unwrap_or_revert()
+            .unwrap_or(DEFAULT_WEIGHT_COEFFICIENTS);
+
+    let weight = base
+        .saturating_add(per_byte.saturating_mul(u64::from(payload_len)))
+        .saturating_add(per_receiver.saturating_mul(u64::from(receiver_count)));
+
+    runtime::ret(CLValue::from_t(weight).unwrap_or_revert());
+}
+
+/// Retune the `estimate_delivery_weight` model.
+#[no_mangle]
+pub extern "C" fn set_weight_coefficients() {
+    require_owner();
+
+    let base: u64 = runtime::get_named_arg("base");
+    let per_byte: u64 = runtime::get_named_arg("per_byte");
+    let per_receiver: u64 = runtime::get_named_arg("per_receiver");
+
+    storage::write(
+        get_uref(KEY_WEIGHT_COEFFICIENTS),
+        (base, per_byte, per_receiver),
+    );
+}
+
+/// Record committee signatures for an inbound message without executing
+/// it. Confirmations add up across calls and `execute_message` counts them
+/// toward the threshold. They are signed over the same bytes as
+/// `execute_message` signatures, so `sig_timestamp`, `deadline` and
+/// `store_only` are passed here too, and only count toward an execution
+/// that passes the same values.
+///
+/// Once a source chain has `max_pending` partly confirmed messages, no new
+/// message from it can start collecting confirmations.
+#[no_mangle]
+pub extern "C" fn confirm_message() {
+    let src_chain_id: u32 = runtime::get_named_arg("src_chain_id");
+    let src_gateway: Bytes = runtime::get_named_arg("src_gateway");
+    let receiver: Bytes = runtime::get_named_arg("receiver");
+    let nonce: u64 = runtime::get_named_arg("nonce");
+    let payload: Bytes = runtime::get_named_arg("payload");
+    let signatures: Vec<Bytes> = runtime::get_named_arg("signatures");
+    let amount: U512 = runtime::try_get_named_arg("amount").unwrap_or_default();
+    let msg_type: u8 = runtime::try_get_named_arg("msg_type").unwrap_or(0);
+    let sig_scheme: u8 = runtime::try_get_named_arg("sig_scheme").unwrap_or(SIG_SCHEME_SECP256K1);
+
+    require_not_in_delivery();
+
+    let chain_id = resolve_chain_id(src_chain_id);
+
+    // A chain with its own relayer key is verified by that key alone, so
+    // committee confirmations would never count for it.
+    let relayers = read_relayers();
+
+    if relayers.is_empty() || read_chain_relayer(chain_id).is_some() {
+        runtime::revert(Error::InvalidCommittee);
+    }
+
+    if signatures.len() != relayers.len() {
+        runtime::revert(Error::InvalidSignature);
+    }
+
+    check_required_sig_scheme(chain_id, sig_scheme).unwrap_or_revert();
+
+    let dst_gateway = inbound_dst_gateway(chain_id);
+
+    let message_bytes = build_message_bytes(
+        src_chain_id,
+        CASPER_CHAIN_ID,
+        src_gateway.as_ref(),
+        receiver.as_ref(),
+        nonce,
+        amount,
+        msg_type,
+        sig_scheme,
+        dst_gateway.as_ref(),
+        payload.as_ref(),
+    );
+    let message_key = message_key(&message_bytes);
+
+    if is_message_executed(&message_key) {
+        runtime::revert(Error::AlreadyExecuted);
+    }
+
+    let signed_bytes = inbound_signed_bytes(message_bytes, &message_key);
+    let digest = blake2b(&signed_bytes);
+
+    let confirmations = get_dictionary(KEY_CONFIRMATIONS);
+    let mut confirmed: Vec<PublicKey> =
+        read_dictionary(confirmations, &message_key).unwrap_or_default();
+    let newly_pending = confirmed.is_empty();
+
+    // Every confirmation of a message covers the same signed bytes.
+    let digests = get_dictionary(KEY_CONFIRMATION_DIGESTS);
+
+    if !newly_pending && read_dictionary::<[u8; 32]>(digests, &message_key) != Some(digest) {
+        runtime::revert(Error::ConfirmationMismatch);
+    }
+
+    for (relayer, signature) in relayers.iter().zip(signatures.iter()) {
+        if signature.is_empty() {
+            continue;
+        }
+        verify_with_scheme(sig_scheme, relayer, &signed_bytes, signature.as_ref())
+            .unwrap_or_revert();
+
+        if !confirmed.contains(relayer) {
+            confirmed.push(relayer.clone());
+        }
+    }
+
+    if newly_pending && !confirmed.is_empty() {
+        let max_pending: u32 = storage::read(get_uref(KEY_MAX_PENDING))
+            .unwrap_or_revert()
+            .unwrap_or(0);
+        let pending = read_pending_count(chain_id);
+
+        if max_pending != 0 && pending >= max_pending {
+            runtime::revert(Error::TooManyPending);
+        }
+
+        write_pending_count(chain_id, pending + 1);
+    }
+
+    storage::dictionary_put(digests, &message_key, digest);
+    storage::dictionary_put(confirmations, &message_key, confirmed);
+    emit_event("message_confirmed", message_key);
+}
+
+/// Admin
+#[no_mangle]
+pub extern "C" fn set_supported_chain() {
+    require_owner();
+
+    let chain_id: u32 = runtime::get_named_arg("chain_id");
+    let supported: bool = runtime::get_named_arg("supported");
+
+    write_chain_supported(chain_id, supported);
+
+    // Optional: the gateway contract on that chain, written into every
+    // message sent there from now on.
+    if let Some(dst_gateway) = runtime::try_get_named_arg::<Bytes>("dst_gateway") {
+        check_gateway_len(dst_gateway.as_ref());
+
+        let mut config = read_chain_config(chain_id);
+        config.dst_gateway = Some(dst_gateway).filter(|gateway| !gateway.is_empty());
+        write_chain_config(chain_id, config);
+    }
+
+    emit_event("chain_support", chain_id.to_string());
+}
+
+/// `set_supported_chain` that only applies if the chain is currently
+/// `expected`; an unset chain counts as unsupported.
+#[no_mangle]
+pub extern "C" fn set_supported_chain_cas() {
+    require_owner();
+
+    let chain_id: u32 = runtime::get_named_arg("chain_id");
+    let expected: bool = runtime::get_named_arg("expected");
+    let supported: bool = runtime::get_named_arg("supported");
+
+    if is_chain_supported(chain_id) != expected {
+        runtime::revert(Error::CasMismatch);
+    }
+
+    write_chain_supported(chain_id, supported);
+    emit_event("chain_support", chain_id.to_string());
+}
+
+/// Pin deliveries to `receiver` (a package hash) to one contract version.
+/// Version 0 clears the pin and `receiver` is treated as a contract hash again.
+#[no_mangle]
+pub extern "C" fn set_receiver_version() {
+    require_owner();
+
+    let receiver: Bytes = runtime::get_named_arg("receiver");
+    let version: ContractVersion = runtime::get_named_arg("version");
+
+    receiver_to_hash_addr(receiver.as_ref()).unwrap_or_revert();
+
+    let dict = get_dictionary(KEY_RECEIVER_VERSIONS);
+    storage::dictionary_put(dict, &to_hex(receiver.as_ref()), version);
+    emit_event("receiver_version", to_hex(receiver.as_ref()));
+}
+
+/// Record that an outbound message was delivered; it is no longer refundable.
+#[no_mangle]
+pub extern "C" fn ack_message() {
+    require_owner();
+
+    let message_id: String = runtime::get_named_arg("message_id");
+
+    storage::dictionary_put(get_dictionary(KEY_MESSAGE_ACKS), &message_id, true);
+
+    let refundable = get_dictionary(KEY_REFUNDABLE_FEES);
+    if let Some((fee, deadline)) = read_dictionary::<(U512, u64)>(refundable, &message_id) {
+        if !fee.is_zero() {
+            storage::dictionary_put(refundable, &message_id, (U512::zero(), deadline));
+            write_refundable_total(read_refundable_total().saturating_sub(fee));
+        }
+    }
+}
+
+/// Route `alias` to `chain_id` in sends and deliveries. Aliasing a chain
+/// to itself removes the alias.
+#[no_mangle]
+pub extern "C" fn set_chain_alias() {
+    require_owner();
+
+    let alias: u32 = runtime::get_named_arg("alias");
+    let chain_id: u32 = runtime::get_named_arg("chain_id");
+
+    // Targets must be canonical, and never the local chain.
+    if alias != chain_id && (resolve_chain_id(chain_id) != chain_id || chain_id == CASPER_CHAIN_ID)
+    {
+        runtime::revert(Error::InvalidSourceChain);
+    }
+
+    let dict = get_dictionary(KEY_CHAIN_ALIASES);
+    storage::dictionary_put(dict, &alias.to_string(), chain_id);
+}
+
+/// Let each sender send at most `max_messages` per `window_ms` window;
+/// `window_ms` 0 makes it a lifetime cap. `max_messages` 0 turns the
+/// limit off.
+#[no_mangle]
+pub extern "C" fn set_sender_rate_limit() {
+    require_owner();
+
+    let max_messages: u32 = runtime::get_named_arg("max_messages");
+    let window_ms: u64 = runtime::get_named_arg("window_ms");
+
+    storage::write(get_uref(KEY_SENDER_RATE_LIMIT), (max_messages, window_ms));
+}
+
+/// Deliver governance-typed messages to `receiver`. `None` removes it, and
+/// governance messages fail until one is set again.
+#[no_mangle]
+pub extern "C" fn set_governance_receiver() {
+    require_owner();
+
+    let receiver: Option<Bytes> = runtime::get_named_arg("receiver");
+
+    if let Some(receiver) = &receiver {
+        receiver_to_hash_addr(receiver.as_ref()).unwrap_or_revert();
+
+        if receiver.as_ref() == NULL_RECEIVER {
+            runtime::revert(Error::InvalidReceiver);
+        }
+    }
+
+    storage::write(get_uref(KEY_GOVERNANCE_RECEIVER), receiver);
+}
+
+/// Ask `registry` whether a destination chain is supported on send, via its
+/// `is_chain_supported(chain_id) -> bool`. `None` goes back to the local
+/// `supported_chains` dictionary.
+#[no_mangle]
+pub extern "C" fn set_chain_registry() {
+    require_owner();
+
+    let registry: Option<ContractHash> = runtime::get_named_arg("registry");
+
+    storage::write(get_uref(KEY_CHAIN_REGISTRY), registry);
+}
+
+/// Route undeliverable messages to `receiver`, e.g. a dead-letter queue.
+/// `None` removes the fallback.
+#[no_mangle]
+pub extern "C" fn set_fallback_receiver() {
+    require_owner();
+
+    let receiver: Option<Bytes> = runtime::get_named_arg("receiver");
+
+    if let Some(receiver) = &receiver {
+        receiver_to_hash_addr(receiver.as_ref()).unwrap_or_revert();
+
+        if receiver.as_ref() == NULL_RECEIVER {
+            runtime::revert(Error::InvalidReceiver);
+        }
+    }
+
+    storage::write(get_uref(KEY_FALLBACK_RECEIVER), receiver);
+}
+
+/// Undo an execution proven invalid, within `dispute_window_ms` of it. The
+/// message id and its source nonce both become deliverable again, so a
+/// corrected message can take the nonce.
+#[no_mangle]
+pub extern "C" fn revoke_execution() {
+    require_owner();
+
+    let message_id: String = runtime::get_named_arg("message_id");
+
+    let record = read_execution_record(&message_id).unwrap_or_revert_with(Error::UnknownMessage);
+    let dispute_window_ms: u64 = storage::read(get_uref(KEY_DISPUTE_WINDOW_MS))
+        .unwrap_or_revert()
+        .unwrap_or(0);
+
+    if u64::from(runtime::get_blocktime()) > record.executed_at.saturating_add(dispute_window_ms) {
+        runtime::revert(Error::DisputeWindowClosed);
+    }
+
+    let replay_key = replay_key(
+        record.src_chain_id,
+        record.src_gateway.as_ref(),
+        record.nonce,
+    );
+
+    storage::dictionary_put(
+        get_dictionary(KEY_EXECUTED_MESSAGES),
+        &message_id,
+        None::<ExecutionRecord>,
+    );
+    storage::dictionary_put(get_dictionary(KEY_REPLAY_MARKS), &replay_key, false);
+
+    let executed_ref = get_uref(KEY_EXECUTED_COUNT);
+    let executed: u64 = storage::read(executed_ref).unwrap_or_revert().unwrap_or(0);
+    storage::write(executed_ref, executed.saturating_sub(1));
+    drop_chain_count(KEY_INBOUND_COUNT, record.src_chain_id);
+}
+
+/// Return the fee of an expired, unacked message to its sender.
+#[no_mangle]
+pub extern "C" fn request_refund() {
+    let message_id: String = runtime::get_named_arg("message_id");
+
+    let sender: AccountHash = read_dictionary(get_dictionary(KEY_MESSAGE_SENDERS), &message_id)
+        .unwrap_or_revert_with(Error::UnknownMessage);
+
+    if runtime::get_caller() != sender {
+        runtime::revert(Error::Unauthorized);
+    }
+
+    let refundable = get_dictionary(KEY_REFUNDABLE_FEES);
+    let (fee, deadline): (U512, u64) =
+        read_dictionary(refundable, &message_id).unwrap_or_revert_with(Error::NotRefundable);
+
+    let acked: Option<bool> = read_dictionary(get_dictionary(KEY_MESSAGE_ACKS), &message_id);
+
+    if fee.is_zero() || acked == Some(true) || u64::from(runtime::get_blocktime()) <= deadline {
+        runtime::revert(Error::NotRefundable);
+    }
+
+    storage::dictionary_put(refundable, &message_id, (U512::zero(), deadline));
+    write_refundable_total(read_refundable_total().saturating_sub(fee));
+    emit_event("fee_refunded", message_id);
+
+    system::transfer_from_purse_to_account(get_uref(KEY_FEE_PURSE), sender, fee, None)
+        .unwrap_or_revert();
+
+    runtime::ret(CLValue::from_t(fee).unwrap_or_revert());
+}
+
+/// Replace the fee purse with a fresh one; future fees land in the new purse.
+/// With `sweep`, the old purse's balance is moved over as well. Without it,
+/// fees that may still be refunded move over anyway, since refunds are paid
+/// from the current purse; the rest stays behind.
+#[no_mangle]
+pub extern "C" fn rotate_fee_purse() {
+    require_owner();
+
+    let sweep: bool = runtime::get_named_arg("sweep");
+
+    let old_purse = get_uref(KEY_FEE_PURSE);
+    let new_purse = system::create_purse();
+
+    let balance = system::get_purse_balance(old_purse).unwrap_or_revert();
+    let amount = if sweep {
+        balance
+    } else {
+        balance.min(read_refundable_total())
+    };
+
+    if !amount.is_zero() {
+        system::transfer_from_purse_to_purse(old_purse, new_purse, amount, None).unwrap_or_revert();
+    }
+
+    runtime::put_key(KEY_FEE_PURSE, new_purse.into());
+
+    runtime::ret(CLValue::from_t(new_purse).unwrap_or_revert());
+}
+
+/// Top up the reward purse with `amount` from `payment_purse`.
+#[no_mangle]
+pub extern "C" fn fund_rewards() {
+    require_owner();
+
+    let payment_purse: URef = runtime::get_named_arg("payment_purse");
+    let amount: U512 = runtime::get_named_arg("amount");
+
+    system::transfer_from_purse_to_purse(payment_purse, get_uref(KEY_REWARD_PURSE), amount, None)
+        .unwrap_or_revert();
+}
+
+/// Pay relayer rewards in the CEP-18 `token`, from this gateway's own token
+/// balance. `None` goes back to CSPR from the reward purse.
+#[no_mangle]
+pub extern "C" fn set_reward_token() {
+    require_owner();
+
+    let token: Option<ContractHash> = runtime::get_named_arg("token");
+
+    storage::write(get_uref(KEY_REWARD_TOKEN), token);
+}
+
+/// Replace the relayer key with an M-of-N committee of relayer keys.
+/// An empty `relayers` list goes back to the single relayer key. Every
+/// supported chain's threshold override must still fit the new committee.
+#[no_mangle]
+pub extern "C" fn set_committee() {
+    require_owner();
+
+    let relayers: Vec<PublicKey> = runtime::get_named_arg("relayers");
+    let threshold: u8 = runtime::get_named_arg("threshold");
+
+    check_committee(&relayers, threshold);
+
+    if !relayers.is_empty() {
+        let chains: Vec<u32> = storage::read(get_uref(KEY_SUPPORTED_CHAIN_LIST))
+            .unwrap_or_revert()
+            .unwrap_or_default();
+
+        for chain_id in chains {
+            check_chain_threshold(chain_id, relayers.len());
+        }
+    }
+
+    storage::write(get_uref(KEY_RELAYERS), relayers);
+    storage::write(get_uref(KEY_THRESHOLD), threshold);
+}
+
+/// Switch a single-relayer deployment to committee mode: a 1-of-1
+/// committee of the current relayer key, to be grown with `set_committee`.
+#[no_mangle]
+pub extern "C" fn migrate_to_committee() {
+    require_owner();
+
+    if !read_relayers().is_empty() {
+        runtime::revert(Error::InvalidCommittee);
+    }
+
+    let relayer_pubkey: PublicKey = storage::read(get_uref(KEY_RELAYER_PUBKEY))
+        .unwrap_or_revert()
+        .unwrap_or_revert_with(Error::MissingKey);
+
+    storage::write(get_uref(KEY_RELAYERS), vec![relayer_pubkey]);
+    storage::write(get_uref(KEY_THRESHOLD), 1u8);
+}
+
+/// Move the protocol version forward and record the change in
+/// `version_events` for downstream systems to pick up.
+#[no_mangle]
+pub extern "C" fn bump_version() {
+    require_owner();
+
+    let version: String = runtime::get_named_arg("version");
+
+    let version_ref = get_uref(KEY_PROTOCOL_VERSION);
+    let current: String = storage::read(version_ref)
+        .unwrap_or_revert()
+        .unwrap_or_revert_with(Error::MissingKey);
+
+    if parse_version(&version).unwrap_or_revert() <= parse_version(&current).unwrap_or_revert() {
+        runtime::revert(Error::VersionNotIncreased);
+    }
+
+    let count_ref = get_uref(KEY_PROTOCOL_VERSION_COUNT);
+    let count: u32 = storage::read(count_ref).unwrap_or_revert().unwrap_or(0);
+
+    storage::dictionary_put(
+        get_dictionary(KEY_VERSION_EVENTS),
+        &count.to_string(),
+        (version.clone(), u64::from(runtime::get_blocktime())),
+    );
+    storage::write(count_ref, count + 1);
+    storage::write(version_ref, version);
+}
+
+/// Cap each source chain's partly confirmed messages; 0 removes the cap.
+#[no_mangle]
+pub extern "C" fn set_max_pending() {
+    require_owner();
+
+    let max_pending: u32 = runtime::get_named_arg("max_pending");
+
+    storage::write(get_uref(KEY_MAX_PENDING), max_pending);
+}
+
+/// Stop outbound sends at once. Inbound deliveries keep working until block
+/// time `grace_until` (ms) so messages already in flight can land.
+#[no_mangle]
+pub extern "C" fn pause() {
+    require_owner();
+
+    let grace_until: u64 = runtime::get_named_arg("grace_until");
+
+    write_paused(true, grace_until);
+}
+
+#[no_mangle]
+pub extern "C" fn unpause() {
+    require_owner();
+
+    write_paused(false, 0);
+}
+
+/// Rotate the relayer key. It stops verifying at block time `expiry`;
+/// 0 means it never expires.
+#[no_mangle]
+pub extern "C" fn set_relayer_key() {
+    require_owner();
+
+    let relayer_pubkey: PublicKey = runtime::get_named_arg("relayer_pubkey");
+    let expiry: u64 = runtime::get_named_arg("expiry");
+
+    if !is_relayer_key(&relayer_pubkey) {
+        runtime::revert(Error::InvalidSignature);
+    }
+
+    storage::write(get_uref(KEY_RELAYER_PUBKEY), relayer_pubkey);
+    storage::write(get_uref(KEY_RELAYER_KEY_EXPIRY), expiry);
+}
+
+#[no_mangle]
+pub extern "C" fn set_admin_key() {
+    require_owner();
+
+    let admin_pubkey: Option<PublicKey> = runtime::get_named_arg("admin_pubkey");
+
+    if let Some(admin_pubkey) = &admin_pubkey {
+        if !matches!(admin_pubkey, PublicKey::Secp256k1(_)) {
+            runtime::revert(Error::InvalidSignature);
+        }
+    }
+
+    storage::write(get_uref(KEY_ADMIN_PUBKEY), admin_pubkey);
+}
+
+/// Run an admin action authorized by the admin key instead of the owner
+/// account. The signature covers `admin_action_bytes`, including the
+/// current admin nonce, so each signed action runs at most once.
+///
+/// Actions and their `params`:
+/// - `pause`: grace_until (u64 BE)
+/// - `unpause`: empty
+/// - `set_supported_chain`: chain_id (u32 BE) | supported (1 byte, 0 or 1)
+#[no_mangle]
+pub extern "C" fn signed_admin_action() {
+    require_not_in_delivery();
+
+    let action: String = runtime::get_named_arg("action");
+    let params: Bytes = runtime::get_named_arg("params");
+    let admin_nonce: u64 = runtime::get_named_arg("admin_nonce");
+    let signature: Bytes = runtime::get_named_arg("signature");
+
+    let admin_pubkey: PublicKey = storage::read(get_uref(KEY_ADMIN_PUBKEY))
+        .unwrap_or_revert()
+        .flatten()
+        .unwrap_or_revert_with(Error::Unauthorized);
+
+    let current: u64 = storage::read(get_uref(KEY_ADMIN_NONCE))
+        .unwrap_or_revert()
+        .unwrap_or(0);
+
+    if admin_nonce != current {
+        runtime::revert(Error::AdminReplay);
+    }
+
+    let message = admin_action_bytes(admin_nonce, &action, params.as_ref());
+    verify_secp256k1(&admin_pubkey, &message, signature.as_ref()).unwrap_or_revert();
+
+    bump_admin_nonce();
+
+    match (action.as_str(), params.as_ref()) {
+        ("pause", params) if params.len() == 8 => {
+            let mut grace_until = [0u8; 8];
+            grace_until.copy_from_slice(params);
+            write_paused(true, u64::from_be_bytes(grace_until));
+        }
+        ("unpause", []) => write_paused(false, 0),
+        ("set_supported_chain", [a, b, c, d, supported @ (0 | 1)]) => {
+            write_chain_supported(u32::from_be_bytes([*a, *b, *c, *d]), *supported == 1);
+        }
+        _ => runtime::revert(Error::InvalidAdminAction),
+    }
+}
+
+/// Only accept inbound messages from `src_chain_id` that name `gateway`
+/// as their source gateway.
+#[no_mangle]
+pub extern "C" fn set_trusted_gateway() {
+    require_owner();
+
+    let src_chain_id: u32 = runtime::get_named_arg("src_chain_id");
+    let gateway: Bytes = runtime::get_named_arg("gateway");
+
+    check_gateway_len(gateway.as_ref());
+
+    let dict = get_dictionary(KEY_TRUSTED_GATEWAYS);
+    storage::dictionary_put(dict, &src_chain_id.to_string(), gateway);
+}
+
+/// Require messages from `chain_id` to arrive in nonce order, per source
+/// gateway. A revoked execution does not rewind the expected nonce.
+#[no_mangle]
+pub extern "C" fn set_chain_ordered() {
+    require_owner();
+
+    let chain_id: u32 = runtime::get_named_arg("chain_id");
+    let ordered: bool = runtime::get_named_arg("ordered");
+
+    let mut config = read_chain_config(chain_id);
+    config.ordered = ordered;
+    write_chain_config(chain_id, config);
+}
+
+/// Move `chain_id` to `status`. Deprecating keeps the chain supported, so
+/// inbound messages from it still execute; only new sends to it stop.
+#[no_mangle]
+pub extern "C" fn set_chain_status() {
+    require_owner();
+
+    let chain_id: u32 = runtime::get_named_arg("chain_id");
+    let status: u8 = runtime::get_named_arg("status");
+
+    if status > CHAIN_STATUS_DEPRECATED {
+        runtime::revert(ApiError::InvalidArgument);
+    }
+
+    write_chain_supported(chain_id, status != CHAIN_STATUS_DISABLED);
+
+    let mut config = read_chain_config(chain_id);
+    config.status = status;
+    write_chain_config(chain_id, config);
+}
+
+/// Restore chain configs from an `export_chain_configs` dump. Each chain's
+/// supported flag follows its imported status; chains not in the dump are
+/// left alone.
+#[no_mangle]
+pub extern "C" fn import_chain_configs() {
+    require_owner();
+
+    let configs: Bytes = runtime::get_named_arg("configs");
+    let configs: Vec<(u32, ChainConfig)> =
+        bytesrepr::deserialize(configs.into()).unwrap_or_revert_with(ApiError::InvalidArgument);
+
+    for (chain_id, config) in configs {
+        if config.status > CHAIN_STATUS_DEPRECATED {
+            runtime::revert(ApiError::InvalidArgument);
+        }
+
+        write_chain_supported(chain_id, config.status != CHAIN_STATUS_DISABLED);
+        write_chain_config(chain_id, config);
+    }
+}
+
+/// Tag messages stored from now on with `mac_key`. Earlier tags keep
+/// verifying against the key they were made with.
+#[no_mangle]
+pub extern "C" fn rotate_mac_key() {
+    require_owner();
+
+    let mac_key: Bytes = runtime::get_named_arg("mac_key");
+
+    if mac_key.is_empty() {
+        runtime::revert(ApiError::InvalidArgument);
+    }
+
+    let epoch_ref = get_uref(KEY_MAC_KEY_EPOCH);
+    let epoch: u32 = storage::read(epoch_ref).unwrap_or_revert().unwrap_or(0) + 1;
+
+    storage::dictionary_put(get_dictionary(KEY_MAC_KEYS), &epoch.to_string(), mac_key);
+    storage::write(epoch_ref, epoch);
+}
+
+/// Require `threshold` committee signatures on messages from `chain_id`;
+/// `None` goes back to the global threshold.
+#[no_mangle]
+pub extern "C" fn set_chain_threshold() {
+    require_owner();
+
+    let chain_id: u32 = runtime::get_named_arg("chain_id");
+    let threshold: Option<u8> = runtime::get_named_arg("threshold");
+
+    if let Some(threshold) = threshold {
+        if threshold == 0 || threshold as usize > read_relayers().len() {
+            runtime::revert(Error::InvalidCommittee);
+        }
+    }
+
+    let mut config = read_chain_config(chain_id);
+    config.threshold = threshold;
+    write_chain_config(chain_id, config);
+}
+
+/// Set up a corridor in one call: `supported` plus any of the optional
+/// `trusted_gateway`, `dst_gateway`, `addr_len`, `ordered`, `threshold` and
+/// `sig_scheme`. Every field is checked before anything is written; fields
+/// left out keep their current value.
+#[no_mangle]
+pub extern "C" fn configure_chain() {
+    require_owner();
+
+    let chain_id: u32 = runtime::get_named_arg("chain_id");
+    let supported: bool = runtime::get_named_arg("supported");
+    let trusted_gateway: Option<Bytes> = runtime::try_get_named_arg("trusted_gateway");
+    let dst_gateway: Option<Bytes> = runtime::try_get_named_arg("dst_gateway");
+    let addr_len: Option<u8> = runtime::try_get_named_arg("addr_len");
+    let ordered: Option<bool> = runtime::try_get_named_arg("ordered");
+    let threshold: Option<Option<u8>> = runtime::try_get_named_arg("threshold");
+    let sig_scheme: Option<Option<u8>> = runtime::try_get_named_arg("sig_scheme");
+
+    for gateway in trusted_gateway.iter().chain(dst_gateway.iter()) {
+        check_gateway_len(gateway.as_ref());
+    }
+
+    if addr_len == Some(0) {
+        runtime::revert(ApiError::InvalidArgument);
+    }
+
+    if let Some(Some(threshold)) = threshold {
+        if threshold == 0 || threshold as usize > read_relayers().len() {
+            runtime::revert(Error::InvalidCommittee);
+        }
+    }
+
+    if let Some(Some(sig_scheme)) = sig_scheme {
+        if sig_scheme > SIG_SCHEME_ED25519 {
+            runtime::revert(ApiError::InvalidArgument);
+        }
+    }
+
+    write_chain_supported(chain_id, supported);
+
+    let mut config = read_chain_config(chain_id);
+    if let Some(dst_gateway) = dst_gateway {
+        config.dst_gateway = Some(dst_gateway).filter(|gateway| !gateway.is_empty());
+    }
+    if let Some(addr_len) = addr_len {
+        config.addr_len = Some(addr_len);
+    }
+    if let Some(ordered) = ordered {
+        config.ordered = ordered;
+    }
+    if let Some(threshold) = threshold {
+        config.threshold = threshold;
+    }
+    if let Some(sig_scheme) = sig_scheme {
+        config.required_sig_scheme = sig_scheme;
+    }
+    write_chain_config(chain_id, config);
+
+    if let Some(gateway) = trusted_gateway {
+        let dict = get_dictionary(KEY_TRUSTED_GATEWAYS);
+        storage::dictionary_put(dict, &chain_id.to_string(), gateway);
+    }
+
+    emit_event("chain_support", chain_id.to_string());
+}
+
+/// Only accept messages from `chain_id` signed under `sig_scheme`; `None`
+/// accepts any scheme a relayer key verifies.
+#[no_mangle]
+pub extern "C" fn set_chain_sig_scheme() {
+    require_owner();
+
+    let chain_id: u32 = runtime::get_named_arg("chain_id");
+    let sig_scheme: Option<u8> = runtime::get_named_arg("sig_scheme");
+
+    if sig_scheme.is_some_and(|sig_scheme| sig_scheme > SIG_SCHEME_ED25519) {
+        runtime::revert(ApiError::InvalidArgument);
+    }
+
+    let mut config = read_chain_config(chain_id);
+    config.required_sig_scheme = sig_scheme;
+    write_chain_config(chain_id, config);
+}
+
+/// Give `src_chain_id` its own relayer key, used instead of the global key
+/// and committee for messages from that chain. `None` restores the default.
+#[no_mangle]
+pub extern "C" fn set_chain_relayer() {
+    require_owner();
+
+    let src_chain_id: u32 = runtime::get_named_arg("src_chain_id");
+    let relayer: Option<PublicKey> = runtime::get_named_arg("relayer");
+
+    if let Some(relayer) = &relayer {
+        if !is_relayer_key(relayer) {
+            runtime::revert(Error::InvalidSignature);
+        }
+    }
+
+    let dict = get_dictionary(KEY_CHAIN_RELAYERS);
+    storage::dictionary_put(dict, &src_chain_id.to_string(), relayer);
+}
+
+/// Restrict `receiver` to payloads whose first byte (the payload version)
+/// is one of `versions`. An empty list lifts the restriction.
+#[no_mangle]
+pub extern "C" fn set_allowed_payload_versions() {
+    require_owner();
+
+    let receiver: Bytes = runtime::get_named_arg("receiver");
+    let versions: Bytes = runtime::get_named_arg("versions");
+
+    receiver_to_hash_addr(receiver.as_ref()).unwrap_or_revert();
+
+    let dict = get_dictionary(KEY_ALLOWED_PAYLOAD_VERSIONS);
+    storage::dictionary_put(dict, &to_hex(receiver.as_ref()), versions);
+}
+
+/// Only deliver payloads starting with `prefix` to `receiver`. An empty
+/// prefix lifts the restriction.
+#[no_mangle]
+pub extern "C" fn set_required_prefix() {
+    require_owner();
+
+    let receiver: Bytes = runtime::get_named_arg("receiver");
+    let prefix: Bytes = runtime::get_named_arg("prefix");
+
+    receiver_to_hash_addr(receiver.as_ref()).unwrap_or_revert();
+
+    let dict = get_dictionary(KEY_REQUIRED_PREFIXES);
+    storage::dictionary_put(dict, &to_hex(receiver.as_ref()), prefix);
+}
+
+/// Deliver messages of `msg_type` to `entry_point` on `receiver`. An empty
+/// entry point removes the mapping.
+#[no_mangle]
+pub extern "C" fn set_receiver_handler() {
+    require_owner();
+
+    let receiver: Bytes = runtime::get_named_arg("receiver");
+    let msg_type: u8 = runtime::get_named_arg("msg_type");
+    let entry_point: String = runtime::get_named_arg("entry_point");
+
+    receiver_to_hash_addr(receiver.as_ref()).unwrap_or_revert();
+
+    let dict = get_dictionary(KEY_RECEIVER_HANDLERS);
+    let key = to_hex(receiver.as_ref());
+    let mut handlers: BTreeMap<u8, String> = read_dictionary(dict, &key).unwrap_or_default();
+
+    if entry_point.is_empty() {
+        handlers.remove(&msg_type);
+    } else {
+        handlers.insert(msg_type, entry_point);
+    }
+
+    storage::dictionary_put(dict, &key, handlers);
+}
+
+/// Withhold deliveries from `receiver` while the rest of the gateway runs.
+#[no_mangle]
+pub extern "C" fn pause_receiver() {
+    write_receiver_paused(true);
+}
+
+#[no_mangle]
+pub extern "C" fn unpause_receiver() {
+    write_receiver_paused(false);
+}
+
+fn write_receiver_paused(paused: bool) {
+    require_owner();
+
+    let receiver: Bytes = runtime::get_named_arg("receiver");
+
+    receiver_to_hash_addr(receiver.as_ref()).unwrap_or_revert();
+
+    let dict = get_dictionary(KEY_PAUSED_RECEIVERS);
+    storage::dictionary_put(dict, &to_hex(receiver.as_ref()), paused);
+}
+
+/// Let `receiver` take the same message more than once.
+///
+/// This turns replay protection off for that receiver: anyone holding one
+/// signed delivery can repeat it as often as they like. Only enable it for
+/// receivers whose handlers are idempotent and that want at-least-once
+/// delivery with blind retries.
+#[no_mangle]
+pub extern "C" fn set_allow_replay() {
+    require_owner();
+
+    let receiver: Bytes = runtime::get_named_arg("receiver");
+    let allow_replay: bool = runtime::get_named_arg("allow_replay");
+
+    receiver_to_hash_addr(receiver.as_ref()).unwrap_or_revert();
+
+    let dict = get_dictionary(KEY_REPLAYABLE_RECEIVERS);
+    storage::dictionary_put(dict, &to_hex(receiver.as_ref()), allow_replay);
+}
+
+/// Drop the bodies of outbound messages with sequence nonce below `up_to`.
+///
+/// Dictionary entries can't be deleted, so pruned entries are overwritten
+/// with empty values and reads below the floor report `UnknownMessage`.
+#[no_mangle]
+pub extern "C" fn prune_messages() {
+    require_owner();
+
+    let up_to: u64 = runtime::get_named_arg("up_to");
+
+    let nonce: u64 = storage::read(get_uref(KEY_NONCE))
+        .unwrap_or_revert()
+        .unwrap_or(0);
+    let floor = read_messages_floor();
+    let end = up_to.min(nonce);
+
+    if end <= floor {
+        return;
+    }
+
+    let messages = get_dictionary(KEY_MESSAGES);
+    let payload_lens = get_dictionary(KEY_MESSAGE_PAYLOAD_LENS);
+    for sequence in floor..end {
+        let key = sequence.to_string();
+        storage::dictionary_put(messages, &key, Bytes::new());
+        storage::dictionary_put(payload_lens, &key, 0u32);
+    }
+
+    storage::write(get_uref(KEY_MESSAGES_FLOOR), end);
+
+    let active_ref = get_uref(KEY_ACTIVE_MESSAGE_COUNT);
+    let active: u64 = storage::read(active_ref).unwrap_or_revert().unwrap_or(0);
+    storage::write(active_ref, active.saturating_sub(end - floor));
+}
+
+/// Views
+#[no_mangle]
+pub extern "C" fn get_storage_stats() {
+    let stats = StorageStats {
+        messages: storage::read(get_uref(KEY_ACTIVE_MESSAGE_COUNT))
+            .unwrap_or_revert()
+            .unwrap_or(0),
+        executed_messages: storage::read(get_uref(KEY_EXECUTED_COUNT))
+            .unwrap_or_revert()
+            .unwrap_or(0),
+        error_events: storage::read(get_uref(KEY_ERROR_EVENT_COUNT))
+            .unwrap_or_revert()
+            .unwrap_or(0),
+    };
+
+    runtime::ret(CLValue::from_t(stats).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn get_stats() {
+    let nonce: u64 = storage::read(get_uref(KEY_NONCE))
+        .unwrap_or_revert()
+        .unwrap_or(0);
+
+    let stats = Stats {
+        // Every sent message takes one sequence number.
+        sent: nonce,
+        executed: storage::read(get_uref(KEY_EXECUTED_COUNT))
+            .unwrap_or_revert()
+            .unwrap_or(0),
+        nonce,
+        supported_chains: storage::read(get_uref(KEY_SUPPORTED_CHAIN_COUNT))
+            .unwrap_or_revert()
+            .unwrap_or(0),
+        paused: is_paused(),
+    };
+
+    runtime::ret(CLValue::from_t(stats).unwrap_or_revert());
+}
+
+/// The receiver calls `execute_message` would make for these args, in call
+/// order, with nothing verified or called. The null receiver makes none.
+#[no_mangle]
+pub extern "C" fn preview_delivery() {
+    let receiver: Bytes = runtime::get_named_arg("receiver");
+    let payload: Bytes = runtime::get_named_arg("payload");
+    let receivers: Option<Vec<Bytes>> = runtime::try_get_named_arg("receivers");
+    let amount: U512 = runtime::try_get_named_arg("amount").unwrap_or_default();
+    let msg_type: u8 = runtime::try_get_named_arg("msg_type").unwrap_or(0);
+
+    let targets = match receivers {
+        Some(receivers) => receivers,
+        None if receiver.as_ref() == NULL_RECEIVER => Vec::new(),
+        None => vec![receiver],
+    };
+
+    let calls: Vec<DeliveryCall> = targets
+        .iter()
+        .map(|target| DeliveryCall {
+            receiver: receiver_to_hash_addr(target.as_ref()).unwrap_or_revert(),
+            version: pinned_receiver_version(target.as_ref()),
+            entry_point: receiver_entry_point(target.as_ref(), msg_type)
+                .unwrap_or_revert_with(Error::UnmappedMessageType),
+            payload_len: payload.len() as u32,
+            amount,
+        })
+        .collect();
+
+    runtime::ret(CLValue::from_t(calls).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn get_chain_config() {
+    let chain_id: u32 = runtime::get_named_arg("chain_id");
+
+    runtime::ret(CLValue::from_t(read_chain_config(chain_id)).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn is_chain_ordered() {
+    let chain_id: u32 = runtime::get_named_arg("chain_id");
+
+    runtime::ret(CLValue::from_t(read_chain_config(chain_id).ordered).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn get_trusted_gateway() {
+    let src_chain_id: u32 = runtime::get_named_arg("src_chain_id");
+
+    let gateway = read_trusted_gateway(src_chain_id).unwrap_or_revert_with(Error::NoTrustedGateway);
+
+    runtime::ret(CLValue::from_t(gateway).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn get_delivery_attempts() {
+    let message_id: String = runtime::get_named_arg("message_id");
+
+    let attempts: u32 =
+        read_dictionary(get_dictionary(KEY_DELIVERY_ATTEMPTS), &message_id).unwrap_or(0);
+
+    runtime::ret(CLValue::from_t(attempts).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn get_reward_balance() {
+    let balance = system::get_purse_balance(get_uref(KEY_REWARD_PURSE)).unwrap_or_revert();
+
+    runtime::ret(CLValue::from_t(balance).unwrap_or_revert());
+}
+
+/// The nonce the next signed admin action must carry.
+#[no_mangle]
+pub extern "C" fn get_admin_nonce() {
+    let admin_nonce: u64 = storage::read(get_uref(KEY_ADMIN_NONCE))
+        .unwrap_or_revert()
+        .unwrap_or(0);
+
+    runtime::ret(CLValue::from_t(admin_nonce).unwrap_or_revert());
+}
+
+/// Signatures an inbound message needs; 1 without a committee.
+#[no_mangle]
+pub extern "C" fn get_threshold() {
+    runtime::ret(CLValue::from_t(read_threshold()).unwrap_or_revert());
+}
+
+/// Keys that can sign inbound messages; 1 without a committee.
+#[no_mangle]
+pub extern "C" fn get_relayer_count() {
+    let count = read_relayers().len().max(1) as u32;
+
+    runtime::ret(CLValue::from_t(count).unwrap_or_revert());
+}
+
+/// This gateway's contract hash followed by its package hash, 32 bytes
+/// each, for receivers that authenticate the caller delivering to them.
+#[no_mangle]
+pub extern "C" fn get_self_identity() {
+    let mut identity = Vec::with_capacity(64);
+    identity.extend_from_slice(&self_contract_hash().value());
+    identity.extend_from_slice(&self_package_hash().value());
+
+    runtime::ret(CLValue::from_t(Bytes::from(identity)).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn get_supported_chains() {
+    let chains: Vec<u32> = storage::read(get_uref(KEY_SUPPORTED_CHAIN_LIST))
+        .unwrap_or_revert()
+        .unwrap_or_default();
+
+    runtime::ret(CLValue::from_t(chains).unwrap_or_revert());
+}
+
+/// `(chain_id, config)` for every supported chain, serialized as a list,
+/// for backing up before an upgrade; `import_chain_configs` takes it back.
+#[no_mangle]
+pub extern "C" fn export_chain_configs() {
+    let chains: Vec<u32> = storage::read(get_uref(KEY_SUPPORTED_CHAIN_LIST))
+        .unwrap_or_revert()
+        .unwrap_or_default();
+
+    let configs: Vec<(u32, ChainConfig)> = chains
+        .into_iter()
+        .map(|chain_id| (chain_id, read_chain_config(chain_id)))
+        .collect();
+
+    runtime::ret(
+        CLValue::from_t(Bytes::from(configs.to_bytes().unwrap_or_revert())).unwrap_or_revert(),
+    );
+}
+
+/// Serialized keys that can sign inbound messages, in committee order;
+/// just the relayer key without a committee.
+#[no_mangle]
+pub extern "C" fn get_relayers() {
+    let mut relayers = read_relayers();
+
+    if relayers.is_empty() {
+        let relayer_pubkey: PublicKey = storage::read(get_uref(KEY_RELAYER_PUBKEY))
+            .unwrap_or_revert()
+            .unwrap_or_revert_with(Error::MissingKey);
+        relayers.push(relayer_pubkey);
+    }
+
+    let keys: Vec<Bytes> = relayers
+        .iter()
+        .map(|relayer| Bytes::from(relayer.to_bytes().unwrap_or_revert()))
+        .collect();
+
+    runtime::ret(CLValue::from_t(keys).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn get_nonce() {
+    let nonce: u64 = storage::read(get_uref(KEY_NONCE))
+        .unwrap_or_revert()
+        .unwrap_or(0);
+
+    runtime::ret(CLValue::from_t(nonce).unwrap_or_revert());
+}
+
+/// `(first retained, next)` outbound sequence nonces in `messages`.
+#[no_mangle]
+pub extern "C" fn get_nonce_range() {
+    let nonce: u64 = storage::read(get_uref(KEY_NONCE))
+        .unwrap_or_revert()
+        .unwrap_or(0);
+
+    runtime::ret(CLValue::from_t((read_messages_floor(), nonce)).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn get_chain_nonce() {
+    let chain_id: u32 = runtime::get_named_arg("chain_id");
+
+    let nonce = read_chain_nonce(chain_id);
+
+    runtime::ret(CLValue::from_t(nonce).unwrap_or_revert());
+}
+
+/// `(outbound, inbound)` message counts for `chain_id`
+#[no_mangle]
+pub extern "C" fn get_chain_traffic() {
+    let chain_id = resolve_chain_id(runtime::get_named_arg("chain_id"));
+
+    let traffic = (
+        read_chain_count(KEY_OUTBOUND_COUNT, chain_id),
+        read_chain_count(KEY_INBOUND_COUNT, chain_id),
+    );
+
+    runtime::ret(CLValue::from_t(traffic).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn is_executed() {
+    let message_id: String = runtime::get_named_arg("message_id");
+
+    runtime::ret(CLValue::from_t(is_message_executed(&message_id)).unwrap_or_revert());
+}
+
+/// Source chain, gateway, nonce and time of an executed message.
+#[no_mangle]
+pub extern "C" fn get_execution_record() {
+    let message_id: String = runtime::get_named_arg("message_id");
+
+    let record = read_execution_record(&message_id).unwrap_or_revert_with(Error::UnknownMessage);
+
+    runtime::ret(CLValue::from_t(record).unwrap_or_revert());
+}
+
+/// `MessageMeta` for each outbound id, in the order given; `None` for an
+/// id this gateway never sent.
+#[no_mangle]
+pub extern "C" fn get_messages_meta() {
+    let message_ids: Vec<String> = runtime::get_named_arg("message_ids");
+    let dict = get_dictionary(KEY_MESSAGE_META);
+
+    let metas: Vec<Option<MessageMeta>> = message_ids
+        .iter()
+        .map(|message_id| read_dictionary(dict, message_id))
+        .collect();
+
+    runtime::ret(CLValue::from_t(metas).unwrap_or_revert());
+}
+
+/// `is_executed` for each id, in the order given.
+#[no_mangle]
+pub extern "C" fn are_executed() {
+    let message_ids: Vec<String> = runtime::get_named_arg("message_ids");
+
+    let flags: Vec<bool> = message_ids
+        .iter()
+        .map(|message_id| is_message_executed(message_id))
+        .collect();
+
+    runtime::ret(CLValue::from_t(flags).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn get_message() {
+    // Outbound messages are read by nonce, store-only deliveries by id.
+    let message = match runtime::try_get_named_arg::<String>("message_id") {
+        Some(message_id) => {
+            let message: Bytes = read_dictionary(get_dictionary(KEY_STORED_MESSAGES), &message_id)
+                .unwrap_or_revert_with(Error::UnknownMessage);
+            check_message_tag(&message_id, message.as_ref());
+            message
+        }
+        None => read_stored_message(runtime::get_named_arg("nonce")),
+    };
+
+    runtime::ret(CLValue::from_t(message).unwrap_or_revert());
+}
+
+/// The payload of a stored message, without the header.
+#[no_mangle]
+pub extern "C" fn get_message_payload() {
+    let nonce: u64 = runtime::get_named_arg("nonce");
+    let key = nonce.to_string();
+
+    let message = read_stored_message(nonce);
+    let payload_len: u32 = read_dictionary(get_dictionary(KEY_MESSAGE_PAYLOAD_LENS), &key)
+        .unwrap_or_revert_with(Error::UnknownMessage);
+
+    let header_len = message
+        .len()
+        .checked_sub(payload_len as usize)
+        .unwrap_or_revert_with(Error::UnknownMessage);
+    let payload = Bytes::from(message[header_len..].to_vec());
+
+    runtime::ret(CLValue::from_t(payload).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn get_dict_uref() {
+    let name: String = runtime::get_named_arg("name");
+
+    if !DICTIONARIES.contains(&name.as_str()) {
+        runtime::revert(Error::MissingKey);
+    }
+
+    // Hand out read access only; writes stay with the contract.
+    let uref = get_dictionary(&name).into_read();
+
+    runtime::ret(CLValue::from_t(uref).unwrap_or_revert());
+}
+
+/// What a known named key holds: `"dictionary"`, `"uref"` for a purse, or
+/// `"uref<T>"` with the CLType of the stored value.
+#[no_mangle]
+pub extern "C" fn describe_named_key() {
+    let name: String = runtime::get_named_arg("name");
+
+    let description = if DICTIONARIES.contains(&name.as_str()) {
+        "dictionary".to_string()
+    } else if matches!(
+        name.as_str(),
+        KEY_FEE_PURSE | KEY_ESCROW_PURSE | KEY_REWARD_PURSE
+    ) {
+        "uref".to_string()
+    } else {
+        let cl_type = stored_cl_type(&name).unwrap_or_revert_with(Error::MissingKey);
+        format!("uref<{:?}>", cl_type)
+    };
+
+    runtime::ret(CLValue::from_t(description).unwrap_or_revert());
+}
+
+/// CLType of the value behind each known value-holding uref key
+fn stored_cl_type(name: &str) -> Option<CLType> {
+    let cl_type = match name {
+        KEY_NONCE
+        | KEY_RELAYER_KEY_EXPIRY
+        | KEY_ADMIN_NONCE
+        | KEY_DISPUTE_WINDOW_MS
+        | KEY_MAX_SIG_AGE_MS
+        | KEY_GLOBAL_SEQ
+        | KEY_PAUSE_GRACE_UNTIL
+        | KEY_ERROR_EVENT_COUNT
+        | KEY_EXECUTED_COUNT
+        | KEY_MESSAGES_FLOOR
+        | KEY_ACTIVE_MESSAGE_COUNT
+        | KEY_MAX_ACTIVE_MESSAGES
+        | KEY_DEAD_LETTER_COUNT
+        | KEY_EVENT_COUNT => u64::cl_type(),
+        KEY_RECENT_IDS_HEAD
+        | KEY_RECENT_IDS_CAPACITY
+        | KEY_MAX_FANOUT
+        | KEY_MAX_BATCH_SIZE
+        | KEY_MAX_DELIVERY_ATTEMPTS
+        | KEY_MAX_INBOUND_PAYLOAD_LEN
+        | KEY_MAX_GATEWAY_LEN
+        | KEY_MIN_GATEWAY_LEN
+        | KEY_MAX_PENDING
+        | KEY_PROTOCOL_VERSION_COUNT
+        | KEY_MAC_KEY_EPOCH
+        | KEY_SUPPORTED_CHAIN_COUNT => u32::cl_type(),
+        KEY_THRESHOLD | KEY_EVENT_BACKEND => u8::cl_type(),
+        KEY_ALLOW_NULL_PAYLOAD
+        | KEY_STRICT_SOURCE_CHAINS
+        | KEY_IN_DELIVERY
+        | KEY_SIGNED_SENDS
+        | KEY_RETURN_MESSAGE_BYTES
+        | KEY_DEDUP_IDEM_KEYS
+        | KEY_LOOPBACK
+        | KEY_CHECK_RECEIVERS
+        | KEY_RECEIVER_RETURNS_BOOL
+        | KEY_EMIT_EVENTS
+        | KEY_PAUSED => bool::cl_type(),
+        KEY_MESSAGE_FEE | KEY_RELAYER_REWARD | KEY_REFUNDABLE_TOTAL => U512::cl_type(),
+        KEY_GATEWAY_NAMESPACE | KEY_PROTOCOL_VERSION => String::cl_type(),
+        KEY_RELAYER_PUBKEY => PublicKey::cl_type(),
+        KEY_ADMIN_PUBKEY => Option::<PublicKey>::cl_type(),
+        KEY_OWNER => AccountHash::cl_type(),
+        KEY_RELAYERS => Vec::<PublicKey>::cl_type(),
+        KEY_RECENT_IDS => Vec::<String>::cl_type(),
+        KEY_SUPPORTED_CHAIN_LIST => Vec::<u32>::cl_type(),
+        KEY_SENDER_RATE_LIMIT => <(u32, u64)>::cl_type(),
+        KEY_WEIGHT_COEFFICIENTS => <(u64, u64, u64)>::cl_type(),
+        KEY_FALLBACK_RECEIVER | KEY_GOVERNANCE_RECEIVER => Option::<Bytes>::cl_type(),
+        KEY_CHAIN_REGISTRY | KEY_REWARD_TOKEN => Option::<ContractHash>::cl_type(),
+        _ => return None,
+    };
+
+    Some(cl_type)
+}
+
+/// Outbound ids, newest first
+#[no_mangle]
+pub extern "C" fn get_recent_message_ids() {
+    let ids: Vec<String> = storage::read(get_uref(KEY_RECENT_IDS))
+        .unwrap_or_revert()
+        .unwrap_or_default();
+    let head: u32 = storage::read(get_uref(KEY_RECENT_IDS_HEAD))
+        .unwrap_or_revert()
+        .unwrap_or(0);
+
+    // `head` is the slot that will be overwritten next, i.e. the oldest entry
+    // once the buffer is full. Walk backwards from the slot before it.
+    let len = ids.len();
+    let newest_first: Vec<String> = (1..=len)
+        .map(|i| ids[(head as usize + len - i) % len].clone())
+        .collect();
+
+    runtime::ret(CLValue::from_t(newest_first).unwrap_or_revert());
+}
+
+/// Name of the `Error` variant behind `ApiError::User(code)`.
+#[no_mangle]
+pub extern "C" fn describe_error() {
+    let code: u32 = runtime::get_named_arg("code");
+
+    let name = u16::try_from(code).map_or("Unknown", error_name);
+
+    runtime::ret(CLValue::from_t(name.to_string()).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn get_protocol_version() {
+    let version: String = storage::read(get_uref(KEY_PROTOCOL_VERSION))
+        .unwrap_or_revert()
+        .unwrap_or_revert_with(Error::MissingKey);
+
+    runtime::ret(CLValue::from_t(version).unwrap_or_revert());
+}
+
+/// Which wasm is deployed
+#[no_mangle]
+pub extern "C" fn get_build_info() {
+    let info = BuildInfo {
+        version: BUILD_VERSION.to_string(),
+        git_commit: BUILD_GIT_COMMIT.to_string(),
+    };
+
+    runtime::ret(CLValue::from_t(info).unwrap_or_revert());
+}
+
+/// `(bytes, id)` of a message built from fixed inputs, for off-chain
+/// tooling to compare against its own golden vectors:
+/// src 1, dst 3, gateway 32 x 0x11, receiver 32 x 0x22, nonce 7,
+/// amount 1000, msg_type 2, sig_scheme 1, payload `knotx`.
+#[no_mangle]
+pub extern "C" fn layout_selftest() {
+    let message_bytes = build_message_bytes(
+        1,
+        CASPER_CHAIN_ID,
+        &[0x11; 32],
+        &[0x22; 32],
+        7,
+        U512::from(1000u64),
+        2,
+        SIG_SCHEME_ED25519,
+        &[],
+        b"knotx",
+    );
+    let message_id = message_key(&message_bytes);
+
+    runtime::ret(CLValue::from_t((Bytes::from(message_bytes), message_id)).unwrap_or_revert());
+}
+
+/// Canonical message bytes for the given fields, before hashing, for
+/// byte-for-byte comparison with an off-chain encoder. `amount`,
+/// `msg_type`, `sig_scheme` and `dst_gateway` are optional. Without
+/// `dst_gateway`, a message from Casper carries the gateway configured for
+/// `dst_chain_id`, as `send_message` would write it, and any other message
+/// carries the one `execute_message` rebuilds it with.
+#[no_mangle]
+pub extern "C" fn build_message() {
+    let message_bytes = message_bytes_from_args();
+
+    runtime::ret(CLValue::from_t(Bytes::from(message_bytes)).unwrap_or_revert());
+}
+
+/// Whether `message_id` is the id of the message built from the given
+/// fields, taken as in `build_message`.
+#[no_mangle]
+pub extern "C" fn verify_message_id() {
+    let message_id: String = runtime::get_named_arg("message_id");
+
+    let matches = message_key(&message_bytes_from_args()) == message_id;
+
+    runtime::ret(CLValue::from_t(matches).unwrap_or_revert());
+}
+
+/// Message bytes from the named args `build_message` documents.
+fn message_bytes_from_args() -> Vec<u8> {
+    let src_chain_id: u32 = runtime::get_named_arg("src_chain_id");
+    let dst_chain_id: u32 = runtime::get_named_arg("dst_chain_id");
+    let src_gateway: Bytes = runtime::get_named_arg("src_gateway");
+    let receiver: Bytes = runtime::get_named_arg("receiver");
+    let nonce: u64 = runtime::get_named_arg("nonce");
+    let payload: Bytes = runtime::get_named_arg("payload");
+    let amount: U512 = runtime::try_get_named_arg("amount").unwrap_or_default();
+    let msg_type: u8 = runtime::try_get_named_arg("msg_type").unwrap_or(0);
+    let sig_scheme: u8 = runtime::try_get_named_arg("sig_scheme").unwrap_or(SIG_SCHEME_SECP256K1);
+    let dst_gateway: Bytes = runtime::try_get_named_arg("dst_gateway").unwrap_or_else(|| {
+        if src_chain_id == CASPER_CHAIN_ID {
+            read_chain_config(resolve_chain_id(dst_chain_id))
+                .dst_gateway
+                .unwrap_or_default()
+        } else {
+            inbound_dst_gateway(resolve_chain_id(src_chain_id))
+        }
+    });
+
+    build_message_bytes(
+        src_chain_id,
+        dst_chain_id,
+        src_gateway.as_ref(),
+        receiver.as_ref(),
+        nonce,
+        amount,
+        msg_type,
+        sig_scheme,
+        dst_gateway.as_ref(),
+        payload.as_ref(),
+    )
+}
+
+/// Account that sent an outbound message
+#[no_mangle]
+pub extern "C" fn get_message_sender() {
+    let message_id: String = runtime::get_named_arg("message_id");
+
+    let sender: AccountHash = read_dictionary(get_dictionary(KEY_MESSAGE_SENDERS), &message_id)
+        .unwrap_or_revert_with(Error::UnknownMessage);
+
+    runtime::ret(CLValue::from_t(sender).unwrap_or_revert());
+}
+
+/// Id of the message sent to `dst_chain_id` with chain nonce `nonce`
+#[no_mangle]
+pub extern "C" fn get_message_id_by_nonce() {
+    let dst_chain_id = resolve_chain_id(runtime::get_named_arg("dst_chain_id"));
+    let nonce: u64 = runtime::get_named_arg("nonce");
+
+    let message_id: String = read_dictionary(
+        get_dictionary(KEY_NONCE_TO_ID),
+        &nonce_key(dst_chain_id, nonce),
+    )
+    .unwrap_or_revert_with(Error::UnknownMessage);
+
+    runtime::ret(CLValue::from_t(message_id).unwrap_or_revert());
+}
+
+/// Support flags aligned with `chain_ids`, answering whether `send_message`
+/// would accept each as a destination: unknown and deprecated chains are
+/// unsupported, and the chain registry decides when one is set.
+#[no_mangle]
+pub extern "C" fn are_chains_supported() {
+    let chain_ids: Vec<u32> = runtime::get_named_arg("chain_ids");
+
+    let flags: Vec<bool> = chain_ids
+        .into_iter()
+        .map(|chain_id| is_destination_sendable(resolve_chain_id(chain_id)))
+        .collect();
+
+    runtime::ret(CLValue::from_t(flags).unwrap_or_revert());
+}
+
+/// Signature verification
+fn verify_relayer_signature(sig_scheme: u8, message: &[u8], signature: &[u8]) -> Result<(), Error> {
+    let pubkey: PublicKey = storage::read(get_uref(KEY_RELAYER_PUBKEY))
+        .unwrap_or_revert()
+        .unwrap_or_revert_with(Error::MissingKey);
+
+    let expiry: u64 = storage::read(get_uref(KEY_RELAYER_KEY_EXPIRY))
+        .unwrap_or_revert()
+        .unwrap_or(0);
+
+    if expiry != 0 && u64::from(runtime::get_blocktime()) >= expiry {
+        return Err(Error::RelayerKeyExpired);
+    }
+
+    verify_with_scheme(sig_scheme, &pubkey, message, signature)
+}
+
+/// Authorize an inbound message: the `signature` arg from the chain's own
+/// relayer key if it has one, else from the relayer key, or, with a
+/// committee configured, the `signatures` arg holding one entry per
+/// committee member in committee order (empty for members who did not
+/// sign), at least `threshold` of them valid. Members who already confirmed
+/// the message through `confirm_message` count without signing again.
+/// Every signature is checked under `sig_scheme`.
+fn verify_inbound_signatures(
+    src_chain_id: u32,
+    sig_scheme: u8,
+    message: &[u8],
+    message_id: &str,
+) -> Result<(), Error> {
+    check_required_sig_scheme(src_chain_id, sig_scheme)?;
+
+    if let Some(relayer) = read_chain_relayer(src_chain_id) {
+        let signature: Bytes = runtime::try_get_named_arg("signature").unwrap_or_default();
+        return verify_with_scheme(sig_scheme, &relayer, message, signature.as_ref());
+    }
+
+    let relayers = read_relayers();
+
+    if relayers.is_empty() {
+        let signature: Bytes = runtime::try_get_named_arg("signature").unwrap_or_default();
+        return verify_relayer_signature(sig_scheme, message, signature.as_ref());
+    }
+
+    let signatures: Vec<Bytes> = runtime::try_get_named_arg("signatures").unwrap_or_default();
+
+    if signatures.len() != relayers.len() {
+        return Err(Error::InvalidSignature);
+    }
+
+    // Confirmations only stand in for signatures over these exact bytes.
+    let digest: Option<[u8; 32]> =
+        read_dictionary(get_dictionary(KEY_CONFIRMATION_DIGESTS), message_id);
+    let confirmed: Vec<PublicKey> = if digest == Some(blake2b(message)) {
+        read_dictionary(get_dictionary(KEY_CONFIRMATIONS), message_id).unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
+    let mut signed: usize = 0;
+
+    for (relayer, signature) in relayers.iter().zip(signatures.iter()) {
+        if signature.is_empty() {
+            if confirmed.contains(relayer) {
+                signed += 1;
+            }
+            continue;
+        }
+        verify_with_scheme(sig_scheme, relayer, message, signature.as_ref())?;
+        signed += 1;
+    }
+
+    if signed < read_chain_threshold(src_chain_id) as usize {
+        return Err(Error::ThresholdNotMet);
+    }
+
+    Ok(())
+}
+
+/// A chain pinned to one scheme rejects the rest, whatever key might
+/// verify them.
+fn check_required_sig_scheme(chain_id: u32, sig_scheme: u8) -> Result<(), Error> {
+    match read_chain_config(chain_id).required_sig_scheme {
+        Some(required) if required != sig_scheme => Err(Error::InvalidSignature),
+        _ => Ok(()),
+    }
+}
+
+/// A committee needs distinct relayer keys and a threshold from 1 to its
+/// size; an empty one turns committee mode off.
+fn check_committee(relayers: &[PublicKey], threshold: u8) {
+    if relayers.is_empty() {
+        return;
+    }
+
+    if threshold == 0 || threshold as usize > relayers.len() {
+        runtime::revert(Error::InvalidCommittee);
+    }
+
+    for (i, relayer) in relayers.iter().enumerate() {
+        if !is_relayer_key(relayer) || relayers[..i].contains(relayer) {
+            runtime::revert(Error::InvalidCommittee);
+        }
+    }
+}
+
+/// Revert if `chain_id`'s threshold override needs more signatures than a
+/// committee of `committee_size` can give; its messages could never verify.
+fn check_chain_threshold(chain_id: u32, committee_size: usize) {
+    if let Some(threshold) = read_chain_config(chain_id).threshold {
+        if threshold as usize > committee_size {
+            runtime::revert(Error::InvalidCommittee);
+        }
+    }
+}
+
+/// A relayer key from its serialized form; the length must match what
+/// its tag calls for.
+fn relayer_key_from_bytes(bytes: &[u8]) -> PublicKey {
+    let key: PublicKey =
+        bytesrepr::deserialize(bytes.to_vec()).unwrap_or_revert_with(Error::InvalidCommittee);
+
+    if !is_relayer_key(&key) {
+        runtime::revert(Error::InvalidCommittee);
+    }
+
+    key
+}
+
+/// Keys relayers may sign with: one per supported `sig_scheme`.
+fn is_relayer_key(pubkey: &PublicKey) -> bool {
+    matches!(pubkey, PublicKey::Secp256k1(_) | PublicKey::Ed25519(_))
+}
+
+/// Verify under the scheme the message is tagged with. A key of another
+/// type never verifies.
+fn verify_with_scheme(
+    sig_scheme: u8,
+    pubkey: &PublicKey,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<(), Error> {
+    match sig_scheme {
+        SIG_SCHEME_SECP256K1 => verify_secp256k1(pubkey, message, signature),
+        SIG_SCHEME_ED25519 => verify_ed25519(pubkey, message, signature),
+        _ => Err(Error::UnsupportedSigScheme),
+    }
+}
+
+fn verify_ed25519(pubkey: &PublicKey, message: &[u8], signature: &[u8]) -> Result<(), Error> {
+    if !matches!(pubkey, PublicKey::Ed25519(_)) {
+        return Err(Error::InvalidSignature);
+    }
+
+    let signature: [u8; 64] = signature.try_into().map_err(|_| Error::InvalidSignature)?;
+    let sig = Signature::ed25519(signature).map_err(|_| Error::InvalidSignature)?;
+
+    verify_signature(message, &sig, pubkey).map_err(|_| Error::InvalidSignature)
+}
+
+fn verify_secp256k1(pubkey: &PublicKey, message: &[u8], signature: &[u8]) -> Result<(), Error> {
+    let signature: [u8; 64] = signature.try_into().map_err(|_| Error::InvalidSignature)?;
+
+    // Only accept the canonical low-s form so a signature cannot be flipped
+    // into a second valid encoding of the same message.
+    if signature[32..] > SECP256K1_HALF_ORDER[..] {
+        return Err(Error::InvalidSignature);
+    }
+
+    let sig = Signature::secp256k1(signature).map_err(|_| Error::InvalidSignature)?;
+
+    verify_signature(message, &sig, pubkey).map_err(|_| Error::InvalidSignature)
+}
+
+/// Revert with `error`.
+///
+/// A revert discards every write of the call, so nothing about the failure
+/// can be stored alongside it. Callers that pass `soft_fail = true` instead
+/// get the failure recorded in `error_events` and the call returns without
+/// doing any further work.
+fn fail(error: Error, context: &str) -> ! {
+    record_failure(error, context);
+
+    runtime::ret(CLValue::unit())
+}
+
+/// `fail` without the early return: under `soft_fail` the error event is
+/// recorded and the caller carries on, otherwise this reverts.
+fn record_failure(error: Error, context: &str) {
+    let code = error as u16;
+
+    let soft_fail: bool = runtime::try_get_named_arg("soft_fail").unwrap_or(false);
+
+    if !soft_fail {
+        runtime::revert(ApiError::User(code));
+    }
+
+    let count_ref = get_uref(KEY_ERROR_EVENT_COUNT);
+    let count: u64 = storage::read(count_ref).unwrap_or_revert().unwrap_or(0);
+
+    storage::dictionary_put(
+        get_dictionary(KEY_ERROR_EVENTS),
+        &count.to_string(),
+        (u32::from(code), context.to_string()),
+    );
+    storage::write(count_ref, count + 1);
+}
+
+/// `fail` for a check about one chain. `ApiError::User` has no room for
+/// the chain id, so a hard revert only says which check failed; under
+/// `soft_fail` the chain id is also stored, typed, next to the error event.
+fn fail_for_chain(error: Error, chain_id: u32) -> ! {
+    let soft_fail: bool = runtime::try_get_named_arg("soft_fail").unwrap_or(false);
+
+    if soft_fail {
+        let count: u64 = storage::read(get_uref(KEY_ERROR_EVENT_COUNT))
+            .unwrap_or_revert()
+            .unwrap_or(0);
+
+        storage::dictionary_put(
+            get_dictionary(KEY_ERROR_CHAINS),
+            &count.to_string(),
+            chain_id,
+        );
+    }
+
+    fail(error, &chain_id.to_string())
+}
+
+/// Helpers
+///
+/// `dst_gateway` goes right before the payload, so the payload stays the
+/// tail of the message; it is empty unless the destination chain has one
+/// configured.
+#[allow(clippy::too_many_arguments)]
+fn build_message_bytes(
+    src_chain_id: u32,
+    dst_chain_id: u32,
+    src_gateway: &[u8],
+    receiver: &[u8],
+    nonce: u64,
+    amount: U512,
+    msg_type: u8,
+    sig_scheme: u8,
+    dst_gateway: &[u8],
+    payload: &[u8],
+) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&src_chain_id.to_be_bytes());
+    out.extend_from_slice(&dst_chain_id.to_be_bytes());
+    out.extend_from_slice(src_gateway);
+    out.extend_from_slice(receiver);
+    out.extend_from_slice(&nonce.to_be_bytes());
+    out.extend_from_slice(&amount_to_be_bytes(amount));
+    out.push(msg_type);
+    out.push(sig_scheme);
+    out.extend_from_slice(dst_gateway);
+    out.extend_from_slice(payload);
+    out
+}
+
+/// Amount as the layout's 32-byte big-endian field (uint256 on EVM).
+fn amount_to_be_bytes(amount: U512) -> [u8; 32] {
+    let mut wide = [0u8; 64];
+    amount.to_big_endian(&mut wide);
+
+    if wide[..32].iter().any(|byte| *byte != 0) {
+        runtime::revert(Error::ValueNotDeliverable);
+    }
+
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&wide[32..]);
+    out
+}
+
+/// Push an id into the fixed-size recent-ids ring, overwriting the oldest.
+fn record_recent_id(id: String) {
+    let capacity: u32 = storage::read(get_uref(KEY_RECENT_IDS_CAPACITY))
+        .unwrap_or_revert()
+        .unwrap_or(DEFAULT_RECENT_IDS_CAPACITY);
+
+    if capacity == 0 {
+        return;
+    }
+
+    let ids_ref = get_uref(KEY_RECENT_IDS);
+    let head_ref = get_uref(KEY_RECENT_IDS_HEAD);
+
+    let mut ids: Vec<String> = storage::read(ids_ref)
+        .unwrap_or_revert()
+        .unwrap_or_default();
+    let head: u32 = storage::read(head_ref).unwrap_or_revert().unwrap_or(0);
+
+    if ids.len() < capacity as usize {
+        ids.push(id);
+    } else {
+        ids[head as usize] = id;
+    }
+
+    storage::write(ids_ref, ids);
+    storage::write(head_ref, (head + 1) % capacity);
+}
+
+fn require_owner() {
+    require_not_in_delivery();
+
+    let owner: AccountHash = storage::read(get_uref(KEY_OWNER))
+        .unwrap_or_revert()
+        .unwrap_or_revert_with(Error::MissingKey);
+
+    if runtime::get_caller() != owner {
+        runtime::revert(Error::Unauthorized);
+    }
+
+    // Owner actions move the admin nonce too, so a signed action prepared
+    // before them goes stale.
+    bump_admin_nonce();
+}
+
+fn bump_admin_nonce() {
+    let admin_nonce_ref = get_uref(KEY_ADMIN_NONCE);
+    let admin_nonce: u64 = storage::read(admin_nonce_ref)
+        .unwrap_or_revert()
+        .unwrap_or(0);
+
+    storage::write(admin_nonce_ref, admin_nonce + 1);
+    emit_event("admin_action", admin_nonce.to_string());
+}
+
+/// Take the next `global_seq` and, under `emit_events`, record the event
+/// with it through the install-time event backend. Every state-changing
+/// entry point comes through here, owner actions by way of the admin
+/// nonce, so indexers get a single order across sends, executions and
+/// admin changes.
+fn emit_event(kind: &str, subject: String) {
+    let seq_ref = get_uref(KEY_GLOBAL_SEQ);
+    let global_seq: u64 = storage::read(seq_ref).unwrap_or_revert().unwrap_or(0);
+    storage::write(seq_ref, global_seq + 1);
+
+    let emit_events: bool = storage::read(get_uref(KEY_EMIT_EVENTS))
+        .unwrap_or_revert()
+        .unwrap_or(false);
+
+    if !emit_events {
+        return;
+    }
+
+    let event = GatewayEvent {
+        global_seq,
+        kind: kind.to_string(),
+        subject,
+        timestamp: u64::from(runtime::get_blocktime()),
+    };
+
+    let event_backend: u8 = storage::read(get_uref(KEY_EVENT_BACKEND))
+        .unwrap_or_revert()
+        .unwrap_or(EVENT_BACKEND_DICTIONARY);
+
+    if event_backend == EVENT_BACKEND_CES {
+        emit_ces_event(&event);
+        return;
+    }
+
+    storage::dictionary_put(get_dictionary(KEY_EVENTS), &global_seq.to_string(), event);
+
+    let count_ref = get_uref(KEY_EVENT_COUNT);
+    let count: u64 = storage::read(count_ref).unwrap_or_revert().unwrap_or(0);
+    storage::write(count_ref, count + 1);
+}
+
+/// Append to `__events` as CES does: the `event_`-prefixed name, then the
+/// fields, as raw bytes under the next `__events_length` index.
+fn emit_ces_event(event: &GatewayEvent) {
+    let mut bytes = format!("event_{}", CES_EVENT_NAME)
+        .to_bytes()
+        .unwrap_or_revert();
+    bytes.extend(event.to_bytes().unwrap_or_revert());
+
+    let length_ref = get_uref(KEY_CES_EVENTS_LENGTH);
+    let length: u32 = storage::read(length_ref).unwrap_or_revert().unwrap_or(0);
+
+    storage::dictionary_put(
+        get_dictionary(KEY_CES_EVENTS),
+        &length.to_string(),
+        Bytes::from(bytes),
+    );
+    storage::write(length_ref, length + 1);
+}
+
+/// Bytes an admin key signs: `ADMIN_ACTION_DOMAIN | admin_nonce u64 BE |
+/// action length u32 BE | action | params`.
+fn admin_action_bytes(admin_nonce: u64, action: &str, params: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(ADMIN_ACTION_DOMAIN.len() + 12 + action.len() + params.len());
+    out.extend_from_slice(ADMIN_ACTION_DOMAIN);
+    out.extend_from_slice(&admin_nonce.to_be_bytes());
+    out.extend_from_slice(&(action.len() as u32).to_be_bytes());
+    out.extend_from_slice(action.as_bytes());
+    out.extend_from_slice(params);
+    out
+}
+
+fn read_refundable_total() -> U512 {
+    storage::read(get_uref(KEY_REFUNDABLE_TOTAL))
+        .unwrap_or_revert()
+        .unwrap_or_default()
+}
+
+fn write_refundable_total(total: U512) {
+    storage::write(get_uref(KEY_REFUNDABLE_TOTAL), total);
+}
+
+fn write_paused(paused: bool, grace_until: u64) {
+    storage::write(get_uref(KEY_PAUSED), paused);
+    storage::write(get_uref(KEY_PAUSE_GRACE_UNTIL), grace_until);
+}
+
+/// Admin methods, and re-entering `execute_message`, are refused while a
+/// receiver is running.
+fn require_not_in_delivery() {
+    let in_delivery: bool = storage::read(get_uref(KEY_IN_DELIVERY))
+        .unwrap_or_revert()
+        .unwrap_or(false);
+
+    if in_delivery {
+        runtime::revert(Error::InDelivery);
+    }
+}
+
+/// Move the configured fee from the caller's `payment_purse` into the fee purse.
+/// Nothing is charged, and no purse is needed, while the fee is zero.
+/// Returns the amount collected.
+fn collect_message_fee() -> U512 {
+    let fee: U512 = storage::read(get_uref(KEY_MESSAGE_FEE))
+        .unwrap_or_revert()
+        .unwrap_or_default();
+
+    if fee.is_zero() {
+        return fee;
+    }
+
+    let payment_purse: URef =
+        runtime::try_get_named_arg("payment_purse").unwrap_or_revert_with(Error::FeeNotPaid);
+
+    system::transfer_from_purse_to_purse(payment_purse, get_uref(KEY_FEE_PURSE), fee, None)
+        .unwrap_or_revert_with(Error::FeeNotPaid);
+
+    fee
+}
+
+/// Pay the relayer reward to the caller. Deliveries never fail for lack of
+/// rewards: an underfunded reward purse pays nothing.
+fn pay_relayer_reward() {
+    let reward: U512 = storage::read(get_uref(KEY_RELAYER_REWARD))
+        .unwrap_or_revert()
+        .unwrap_or_default();
+
+    if reward.is_zero() {
+        return;
+    }
+
+    let reward_token: Option<ContractHash> = storage::read(get_uref(KEY_REWARD_TOKEN))
+        .unwrap_or_revert()
+        .flatten();
+
+    if let Some(token) = reward_token {
+        pay_token_reward(token, reward);
+        return;
+    }
+
+    let reward_purse = get_uref(KEY_REWARD_PURSE);
+    let balance = system::get_purse_balance(reward_purse).unwrap_or_revert();
+
+    if balance < reward {
+        return;
+    }
+
+    system::transfer_from_purse_to_account(reward_purse, runtime::get_caller(), reward, None)
+        .unwrap_or_revert();
+}
+
+/// Token payout: like the purse, an underfunded balance skips the reward
+/// rather than blocking delivery.
+fn pay_token_reward(token: ContractHash, reward: U512) {
+    // U512 and U256 share their byte encoding; a reward too large for a
+    // token amount cannot be paid.
+    let amount: U256 = bytesrepr::deserialize(reward.to_bytes().unwrap_or_revert())
+        .unwrap_or_revert_with(ApiError::InvalidArgument);
+
+    // CEP-18 knows a calling contract by its package hash.
+    let gateway = Key::Hash(self_package_hash().value());
+    let balance: U256 =
+        runtime::call_contract(token, "balance_of", runtime_args! { "address" => gateway });
+
+    if balance < amount {
+        return;
+    }
+
+    runtime::call_contract::<()>(
+        token,
+        "transfer",
+        runtime_args! {
+            "recipient" => Key::Account(runtime::get_caller()),
+            "amount" => amount,
+        },
+    );
+}
+
+/// Count a send against the sender's quota for the current window. Windows
+/// are fixed, aligned to multiples of `window_ms` in block time.
+fn check_sender_rate_limit(sender: AccountHash) {
+    let (max_messages, window_ms): (u32, u64) = storage::read(get_uref(KEY_SENDER_RATE_LIMIT))
+        .unwrap_or_revert()
+        .unwrap_or_default();
+
+    if max_messages == 0 {
+        return;
+    }
+
+    let window = u64::from(runtime::get_blocktime())
+        .checked_div(window_ms)
+        .unwrap_or(0);
+    let windows = get_dictionary(KEY_SENDER_WINDOWS);
+    let key = to_hex(sender.as_bytes());
+
+    let sent = match read_dictionary::<(u64, u32)>(windows, &key) {
+        Some((last_window, sent)) if last_window == window => sent,
+        _ => 0,
+    };
+
+    if sent >= max_messages {
+        runtime::revert(Error::RateLimited);
+    }
+
+    storage::dictionary_put(windows, &key, (window, sent + 1));
+}
+
+/// Claim the send's optional `idem_key` for `sender`. With dedup on, a key
+/// the sender already used reverts the send.
+fn record_idem_key(sender: AccountHash, message_id: &str) {
+    let dedup: bool = storage::read(get_uref(KEY_DEDUP_IDEM_KEYS))
+        .unwrap_or_revert()
+        .unwrap_or(false);
+
+    if !dedup {
+        return;
+    }
+
+    let idem_key: Bytes = match runtime::try_get_named_arg("idem_key") {
+        Some(idem_key) => idem_key,
+        None => return,
+    };
+
+    let mut input = Vec::with_capacity(32 + idem_key.len());
+    input.extend_from_slice(sender.as_bytes());
+    input.extend_from_slice(idem_key.as_ref());
+    let key = to_hex(&blake2b(input));
+
+    let dict = get_dictionary(KEY_SENDER_IDEM_KEYS);
+
+    if read_dictionary::<String>(dict, &key).is_some() {
+        runtime::revert(Error::DuplicateIdemKey);
+    }
+
+    storage::dictionary_put(dict, &key, message_id.to_string());
+}
+
+/// Move a send's `amount` from the caller's `payment_purse` into escrow.
+fn escrow_amount(amount: U512) {
+    if amount.is_zero() {
+        return;
+    }
+
+    let payment_purse: URef = runtime::try_get_named_arg("payment_purse")
+        .unwrap_or_revert_with(Error::InsufficientEscrow);
+
+    system::transfer_from_purse_to_purse(payment_purse, get_uref(KEY_ESCROW_PURSE), amount, None)
+        .unwrap_or_revert_with(Error::InsufficientEscrow);
+}
+
+/// Gateway addresses go into every message and replay key; keep them bounded.
+fn check_gateway_len(gateway: &[u8]) {
+    let max_gateway_len: u32 = storage::read(get_uref(KEY_MAX_GATEWAY_LEN))
+        .unwrap_or_revert()
+        .unwrap_or(DEFAULT_MAX_GATEWAY_LEN);
+
+    if gateway.len() > max_gateway_len as usize {
+        runtime::revert(Error::GatewayTooLarge);
+    }
+}
+
+fn read_max_payload_len() -> u32 {
+    storage::read(get_uref(KEY_MAX_INBOUND_PAYLOAD_LEN))
+        .unwrap_or_revert()
+        .unwrap_or(DEFAULT_MAX_INBOUND_PAYLOAD_LEN)
+}
+
+/// A source gateway shorter than any real address is malformed, and an
+/// empty one would never match a trusted gateway.
+fn check_min_gateway_len(gateway: &[u8]) {
+    let min_gateway_len: u32 = storage::read(get_uref(KEY_MIN_GATEWAY_LEN))
+        .unwrap_or_revert()
+        .unwrap_or(DEFAULT_MIN_GATEWAY_LEN);
+
+    if gateway.len() < min_gateway_len as usize {
+        runtime::revert(Error::GatewayTooShort);
+    }
+}
+
+/// Next outbound nonce for a destination chain
+fn read_messages_floor() -> u64 {
+    storage::read(get_uref(KEY_MESSAGES_FLOOR))
+        .unwrap_or_revert()
+        .unwrap_or(0)
+}
+
+/// Outbound message with sequence `nonce`; pruned ones count as unknown.
+fn read_stored_message(nonce: u64) -> Bytes {
+    if nonce < read_messages_floor() {
+        runtime::revert(Error::UnknownMessage);
+    }
+
+    let key = nonce.to_string();
+    let message: Bytes = read_dictionary(get_dictionary(KEY_MESSAGES), &key)
+        .unwrap_or_revert_with(Error::UnknownMessage);
+    check_message_tag(&key, message.as_ref());
+    message
+}
 
-#[repr(u16)]
-enum Error {
-    UnsupportedChain = 1,
-    AlreadyExecuted = 2,
-    InvalidReceiver = 3,
-    MissingKey = 4,
-    InvalidSignature = 5,
+/// Tag a message about to be stored under `key`, if tagging is on.
+fn tag_stored_message(key: &str, message: &[u8]) {
+    let epoch: u32 = storage::read(get_uref(KEY_MAC_KEY_EPOCH))
+        .unwrap_or_revert()
+        .unwrap_or(0);
+
+    if epoch == 0 {
+        return;
+    }
+
+    let tag = message_tag(&read_mac_key(epoch), message);
+    storage::dictionary_put(get_dictionary(KEY_MESSAGE_TAGS), key, (epoch, tag));
 }
 
-impl From<Error> for ApiError {
-    fn from(e: Error) -> Self {
-        ApiError::User(e as u16)
+/// Revert if a message read back from `key` no longer matches its tag.
+/// Messages stored before tagging was turned on have none.
+fn check_message_tag(key: &str, message: &[u8]) {
+    let tag: Option<(u32, [u8; 32])> = read_dictionary(get_dictionary(KEY_MESSAGE_TAGS), key);
+
+    if let Some((epoch, tag)) = tag {
+        if message_tag(&read_mac_key(epoch), message) != tag {
+            runtime::revert(Error::MessageTagMismatch);
+        }
     }
 }
 
-/// ------------------------------------------------
-/// Install
-/// ------------------------------------------------
+fn read_mac_key(epoch: u32) -> Bytes {
+    read_dictionary(get_dictionary(KEY_MAC_KEYS), &epoch.to_string())
+        .unwrap_or_revert_with(Error::CorruptState)
+}
 
-#[no_mangle]
-pub extern "C" fn call() {
-    let relayer_pubkey: Bytes = runtime::get_named_arg("relayer_pubkey");
+/// Keyed integrity tag: blake2b(key ‖ message).
+fn message_tag(mac_key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut input = Vec::with_capacity(mac_key.len() + message.len());
+    input.extend_from_slice(mac_key);
+    input.extend_from_slice(message);
+    blake2b(input)
+}
 
-    if relayer_pubkey.len() != 33 {
-        runtime::revert(Error::InvalidSignature);
+fn read_chain_nonce(chain_id: u32) -> u64 {
+    let nonce: Option<u64> =
+        read_dictionary(get_dictionary(KEY_CHAIN_NONCES), &chain_id.to_string());
+    nonce.unwrap_or(0)
+}
+
+fn read_relayers() -> Vec<PublicKey> {
+    storage::read(get_uref(KEY_RELAYERS))
+        .unwrap_or_revert()
+        .unwrap_or_default()
+}
+
+fn read_threshold() -> u8 {
+    if read_relayers().is_empty() {
+        return 1;
     }
 
-    // Create storage
-    let nonce = storage::new_uref(0u64);
-    let relayer = storage::new_uref(relayer_pubkey);
+    storage::read(get_uref(KEY_THRESHOLD))
+        .unwrap_or_revert()
+        .unwrap_or(1)
+}
 
-    let supported = storage::new_dictionary(KEY_SUPPORTED_CHAINS).unwrap_or_revert();
-    let executed = storage::new_dictionary(KEY_EXECUTED_MESSAGES).unwrap_or_revert();
-    let messages = storage::new_dictionary(KEY_MESSAGES).unwrap_or_revert();
+/// Committee signatures needed for messages from `chain_id`.
+fn read_chain_threshold(chain_id: u32) -> u8 {
+    match read_chain_config(chain_id).threshold {
+        Some(threshold) if !read_relayers().is_empty() => threshold,
+        _ => read_threshold(),
+    }
+}
 
-    let mut named_keys = NamedKeys::new();
-    named_keys.insert(KEY_NONCE.to_string(), nonce.into());
-    named_keys.insert(KEY_RELAYER_PUBKEY.to_string(), relayer.into());
-    named_keys.insert(KEY_SUPPORTED_CHAINS.to_string(), supported.into());
-    named_keys.insert(KEY_EXECUTED_MESSAGES.to_string(), executed.into());
-    named_keys.insert(KEY_MESSAGES.to_string(), messages.into());
+fn is_paused() -> bool {
+    storage::read(get_uref(KEY_PAUSED))
+        .unwrap_or_revert()
+        .unwrap_or(false)
+}
 
-    // Entry points
-    let mut entry_points = EntryPoints::new();
+fn read_chain_config(chain_id: u32) -> ChainConfig {
+    read_dictionary(get_dictionary(KEY_CHAIN_CONFIGS), &chain_id.to_string()).unwrap_or_default()
+}
 
-    entry_points.add_entry_point(EntryPoint::new(
-        "send_message",
-        vec![
-            Parameter::new("dst_chain_id", CLType::U32),
-            Parameter::new("receiver", CLType::List(Box::new(CLType::U8))),
-            Parameter::new("payload", CLType::List(Box::new(CLType::U8))),
-        ],
-        CLType::List(Box::new(CLType::U8)),
-        EntryPointAccess::Public,
-        EntryPointType::Called,
-    ));
+fn write_chain_config(chain_id: u32, config: ChainConfig) {
+    let dict = get_dictionary(KEY_CHAIN_CONFIGS);
+    storage::dictionary_put(dict, &chain_id.to_string(), config);
+}
 
-    entry_points.add_entry_point(EntryPoint::new(
-        "execute_message",
-        vec![
-            Parameter::new("src_chain_id", CLType::U32),
-            Parameter::new("src_gateway", CLType::List(Box::new(CLType::U8))),
-            Parameter::new("receiver", CLType::List(Box::new(CLType::U8))),
-            Parameter::new("nonce", CLType::U64),
-            Parameter::new("payload", CLType::List(Box::new(CLType::U8))),
-            Parameter::new("signature", CLType::List(Box::new(CLType::U8))),
-        ],
-        CLType::Unit,
-        EntryPointAccess::Public,
-        EntryPointType::Called,
-    ));
+fn read_trusted_gateway(src_chain_id: u32) -> Option<Bytes> {
+    read_dictionary(
+        get_dictionary(KEY_TRUSTED_GATEWAYS),
+        &src_chain_id.to_string(),
+    )
+}
 
-    entry_points.add_entry_point(EntryPoint::new(
-        "set_supported_chain",
-        vec![
-            Parameter::new("chain_id", CLType::U32),
-            Parameter::new("supported", CLType::Bool),
-        ],
-        CLType::Unit,
-        EntryPointAccess::Public,
-        EntryPointType::Called,
-    ));
+/// `(major, minor, patch)` of a `major.minor.patch` version string.
+fn parse_version(version: &str) -> Result<(u32, u32, u32), Error> {
+    let mut parts = version.split('.').map(|part| part.parse::<u32>());
 
-    // Deploy contract
-    let (contract_hash, _) =
-        storage::new_contract(entry_points.into(), Some(named_keys), None, None, None);
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(major)), Some(Ok(minor)), Some(Ok(patch)), None) => Ok((major, minor, patch)),
+        _ => Err(Error::InvalidVersion),
+    }
+}
+
+/// Hash of this contract version, from the top of the call stack.
+fn self_contract_hash() -> ContractHash {
+    let frame = runtime::get_call_stack().pop().unwrap_or_revert();
+
+    let contract_hash: Option<ContractHash> = frame
+        .get_field_by_index(CALLER_FIELD_CONTRACT)
+        .unwrap_or_revert()
+        .clone()
+        .into_t()
+        .unwrap_or_revert();
 
-    runtime::put_key("knotx_gateway", contract_hash.into());
+    contract_hash.unwrap_or_revert()
 }
 
-/// ------------------------------------------------
-/// send_message
-/// ------------------------------------------------
+/// Package hash of this contract, from the top of the call stack.
+fn self_package_hash() -> ContractPackageHash {
+    let frame = runtime::get_call_stack().pop().unwrap_or_revert();
 
-#[no_mangle]
-pub extern "C" fn send_message() {
-    let dst_chain_id: u32 = runtime::get_named_arg("dst_chain_id");
-    let receiver: Bytes = runtime::get_named_arg("receiver");
-    let payload: Bytes = runtime::get_named_arg("payload");
+    let package_hash: Option<ContractPackageHash> = frame
+        .get_field_by_index(CALLER_FIELD_CONTRACT_PACKAGE)
+        .unwrap_or_revert()
+        .clone()
+        .into_t()
+        .unwrap_or_revert();
 
-    let supported = get_dictionary(KEY_SUPPORTED_CHAINS);
-    let allowed: Option<bool> =
-        storage::dictionary_get(supported, &dst_chain_id.to_string()).unwrap_or_revert();
+    package_hash.unwrap_or_revert()
+}
 
-    if allowed != Some(true) {
-        runtime::revert(Error::UnsupportedChain);
-    }
+fn read_chain_count(name: &str, chain_id: u32) -> u64 {
+    read_dictionary(get_dictionary(name), &chain_id.to_string()).unwrap_or(0)
+}
 
-    let nonce_ref = get_uref(KEY_NONCE);
-    let nonce: u64 = storage::read(nonce_ref).unwrap_or_revert().unwrap_or(0);
-    
-    let message_bytes = build_message_bytes(
-        CASPER_CHAIN_ID,
-        dst_chain_id,
-        &runtime::get_caller().to_bytes().unwrap_or_default(),
-        receiver.as_ref(),
-        nonce,
-        payload.as_ref(),
+fn bump_chain_count(name: &str, chain_id: u32) {
+    let count = read_chain_count(name, chain_id);
+    storage::dictionary_put(get_dictionary(name), &chain_id.to_string(), count + 1);
+}
+
+fn drop_chain_count(name: &str, chain_id: u32) {
+    let count = read_chain_count(name, chain_id);
+    storage::dictionary_put(
+        get_dictionary(name),
+        &chain_id.to_string(),
+        count.saturating_sub(1),
     );
+}
 
-    let messages = get_dictionary(KEY_MESSAGES);
+fn read_pending_count(chain_id: u32) -> u32 {
+    read_dictionary(get_dictionary(KEY_PENDING_COUNTS), &chain_id.to_string()).unwrap_or(0)
+}
 
+fn write_pending_count(chain_id: u32, count: u32) {
     storage::dictionary_put(
-        messages,
-        &nonce.to_string(),
-        Bytes::from(message_bytes.clone()),
+        get_dictionary(KEY_PENDING_COUNTS),
+        &chain_id.to_string(),
+        count,
     );
+}
 
-    storage::write(nonce_ref, nonce + 1);
+/// Drop an executed message's confirmations, freeing its pending slot.
+fn settle_confirmations(chain_id: u32, message_id: &str) {
+    let confirmations = get_dictionary(KEY_CONFIRMATIONS);
+    let confirmed: Vec<PublicKey> = read_dictionary(confirmations, message_id).unwrap_or_default();
 
-    runtime::ret(CLValue::from_t(Bytes::from(message_bytes)).unwrap_or_revert());
+    if confirmed.is_empty() {
+        return;
+    }
+
+    storage::dictionary_put(confirmations, message_id, Vec::<PublicKey>::new());
+    write_pending_count(chain_id, read_pending_count(chain_id).saturating_sub(1));
 }
 
-/// execute_message
-#[no_mangle]
-pub extern "C" fn execute_message() {
-    let src_chain_id: u32 = runtime::get_named_arg("src_chain_id");
-    let src_gateway: Bytes = runtime::get_named_arg("src_gateway");
-    let receiver: Bytes = runtime::get_named_arg("receiver");
-    let nonce: u64 = runtime::get_named_arg("nonce");
-    let payload: Bytes = runtime::get_named_arg("payload");
-    let signature: Bytes = runtime::get_named_arg("signature");
+/// What relayers sign for an inbound message: its bytes, then the signed
+/// delivery args that are present, each checked first.
+fn inbound_signed_bytes(message_bytes: Vec<u8>, message_key: &str) -> Vec<u8> {
+    // With `max_sig_age_ms` set, relayers sign the message bytes followed by
+    // `sig_timestamp` (u64 BE, ms), so a signature is only good near the time
+    // it was made. The message id still covers the message bytes alone.
+    let max_sig_age_ms: u64 = storage::read(get_uref(KEY_MAX_SIG_AGE_MS))
+        .unwrap_or_revert()
+        .unwrap_or(0);
 
-    let message_bytes = build_message_bytes(
-        src_chain_id,
-        CASPER_CHAIN_ID,
-        src_gateway.as_ref(),
-        receiver.as_ref(),
-        nonce,
-        payload.as_ref(),
-    );
+    let mut signed_bytes = if max_sig_age_ms > 0 {
+        let sig_timestamp: u64 = runtime::get_named_arg("sig_timestamp");
 
-    verify_relayer_signature(&message_bytes, signature.as_ref());
+        if u64::from(runtime::get_blocktime()).abs_diff(sig_timestamp) > max_sig_age_ms {
+            fail(Error::SignatureTooOld, message_key);
+        }
 
-    let message_key = message_key(&message_bytes);
-    let executed = get_dictionary(KEY_EXECUTED_MESSAGES);
+        let mut signed_bytes = message_bytes;
+        signed_bytes.extend_from_slice(&sig_timestamp.to_be_bytes());
+        signed_bytes
+    } else {
+        message_bytes
+    };
 
-    let seen: Option<bool> = storage::dictionary_get(executed, &message_key).unwrap_or_revert();
+    // A message sent with a `ttl_ms` carries the deadline its source gateway
+    // computed. Relayers sign it after everything above; 0 never expires.
+    let deadline: u64 = runtime::try_get_named_arg("deadline").unwrap_or(0);
 
-    if seen == Some(true) {
-        runtime::revert(Error::AlreadyExecuted);
+    if deadline > 0 {
+        if u64::from(runtime::get_blocktime()) > deadline {
+            fail(Error::MessageExpired, message_key);
+        }
+
+        signed_bytes.extend_from_slice(&deadline.to_be_bytes());
     }
 
-    storage::dictionary_put(executed, &message_key, true);
+    // The flag is signed last, so nobody but the relayers can turn a push
+    // delivery into a store-only one.
+    let store_only: bool = runtime::try_get_named_arg("store_only").unwrap_or(false);
 
-    if receiver.len() != 32 {
-        runtime::revert(Error::InvalidReceiver);
+    if store_only {
+        signed_bytes.push(1);
     }
 
-    let mut arr = [0u8; 32];
-    arr.copy_from_slice(receiver.as_ref());
-    let receiver_hash = ContractHash::new(arr);
+    signed_bytes
+}
 
-    runtime::call_contract::<()>(
-        receiver_hash,
-        "on_call",
-        runtime_args! {
-            "src_chain_id" => src_chain_id,
-            "src_gateway" => src_gateway,
-            "payload" => payload,
-        },
-    );
+/// Destination gateway field of an inbound message from canonical
+/// `chain_id`. A loopback is rebuilt exactly as `send_message` built it,
+/// with the gateway configured for this chain; other chains leave it empty.
+fn inbound_dst_gateway(chain_id: u32) -> Bytes {
+    if chain_id == CASPER_CHAIN_ID {
+        read_chain_config(CASPER_CHAIN_ID)
+            .dst_gateway
+            .unwrap_or_default()
+    } else {
+        Bytes::new()
+    }
 }
 
-/// Admin
-#[no_mangle]
-pub extern "C" fn set_supported_chain() {
-    let chain_id: u32 = runtime::get_named_arg("chain_id");
-    let supported: bool = runtime::get_named_arg("supported");
+fn read_chain_relayer(src_chain_id: u32) -> Option<PublicKey> {
+    read_dictionary::<Option<PublicKey>>(
+        get_dictionary(KEY_CHAIN_RELAYERS),
+        &src_chain_id.to_string(),
+    )
+    .flatten()
+}
+
+/// Store a chain's support flag, keeping the supported-chain count and
+/// list in step. Re-enabling an enabled chain changes neither, and a
+/// deprecated chain stays deprecated until `set_chain_status` moves it.
+/// A chain is only enabled with a threshold override the committee can meet.
+fn write_chain_supported(chain_id: u32, supported: bool) {
+    let was_supported = is_chain_supported(chain_id);
+
+    let committee_size = read_relayers().len();
+    if supported && committee_size > 0 {
+        check_chain_threshold(chain_id, committee_size);
+    }
 
     let dict = get_dictionary(KEY_SUPPORTED_CHAINS);
     storage::dictionary_put(dict, &chain_id.to_string(), supported);
+
+    let mut config = read_chain_config(chain_id);
+    config.status = if supported {
+        if config.status == CHAIN_STATUS_DEPRECATED {
+            CHAIN_STATUS_DEPRECATED
+        } else {
+            CHAIN_STATUS_ACTIVE
+        }
+    } else {
+        CHAIN_STATUS_DISABLED
+    };
+    write_chain_config(chain_id, config);
+
+    if was_supported == supported {
+        return;
+    }
+
+    let count_ref = get_uref(KEY_SUPPORTED_CHAIN_COUNT);
+    let count: u32 = storage::read(count_ref).unwrap_or_revert().unwrap_or(0);
+
+    storage::write(
+        count_ref,
+        if supported {
+            count + 1
+        } else {
+            count.saturating_sub(1)
+        },
+    );
+
+    let list_ref = get_uref(KEY_SUPPORTED_CHAIN_LIST);
+    let mut chains: Vec<u32> = storage::read(list_ref)
+        .unwrap_or_revert()
+        .unwrap_or_default();
+
+    if supported {
+        chains.push(chain_id);
+    } else {
+        chains.retain(|chain| *chain != chain_id);
+    }
+
+    storage::write(list_ref, chains);
 }
 
-/// Signature verification
-fn verify_relayer_signature(message: &[u8], signature: &[u8]) {
-    let pubkey_bytes: Bytes = storage::read(get_uref(KEY_RELAYER_PUBKEY))
+/// Canonical id for `chain_id`; itself unless aliased.
+fn resolve_chain_id(chain_id: u32) -> u32 {
+    read_dictionary(get_dictionary(KEY_CHAIN_ALIASES), &chain_id.to_string()).unwrap_or(chain_id)
+}
+
+/// A revoked execution reads as never executed.
+fn read_execution_record(message_id: &str) -> Option<ExecutionRecord> {
+    read_dictionary::<Option<ExecutionRecord>>(get_dictionary(KEY_EXECUTED_MESSAGES), message_id)
+        .flatten()
+}
+
+fn is_message_executed(message_id: &str) -> bool {
+    read_execution_record(message_id).is_some()
+}
+
+fn is_chain_supported(chain_id: u32) -> bool {
+    let supported = get_dictionary(KEY_SUPPORTED_CHAINS);
+    let allowed: Option<bool> = read_dictionary(supported, &chain_id.to_string());
+    allowed == Some(true)
+}
+
+/// Outbound support: the chain registry decides when one is set, otherwise
+/// the local dictionary.
+fn is_destination_supported(chain_id: u32) -> bool {
+    let registry: Option<ContractHash> = storage::read(get_uref(KEY_CHAIN_REGISTRY))
         .unwrap_or_revert()
-        .unwrap_or_revert();
+        .flatten();
+
+    match registry {
+        Some(registry) => runtime::call_contract(
+            registry,
+            "is_chain_supported",
+            runtime_args! { "chain_id" => chain_id },
+        ),
+        None => is_chain_supported(chain_id),
+    }
+}
+
+/// Whether `send_message` takes `chain_id` as a destination: supported,
+/// and not deprecated.
+fn is_destination_sendable(chain_id: u32) -> bool {
+    is_destination_supported(chain_id)
+        && read_chain_config(chain_id).status != CHAIN_STATUS_DEPRECATED
+}
+
+/// Interpret receiver bytes as a 32-byte hash address.
+fn receiver_to_hash_addr(receiver: &[u8]) -> Result<[u8; 32], Error> {
+    receiver.try_into().map_err(|_| Error::InvalidReceiver)
+}
+
+/// Interpret receiver bytes as a contract hash.
+fn receiver_to_contract_hash(receiver: &[u8]) -> Result<ContractHash, Error> {
+    receiver_to_hash_addr(receiver).map(ContractHash::new)
+}
+
+fn pinned_receiver_version(receiver: &[u8]) -> Option<ContractVersion> {
+    let version: Option<ContractVersion> =
+        read_dictionary(get_dictionary(KEY_RECEIVER_VERSIONS), &to_hex(receiver));
+    version.filter(|version| *version != 0)
+}
+
+fn is_replayable(receiver: &[u8]) -> bool {
+    let allowed: Option<bool> =
+        read_dictionary(get_dictionary(KEY_REPLAYABLE_RECEIVERS), &to_hex(receiver));
+
+    allowed == Some(true)
+}
+
+/// Per-receiver pause and payload rules, checked before a delivery is recorded.
+fn check_delivery_rules(receiver: &[u8], msg_type: u8, payload: &[u8], context: &str) {
+    if receiver_entry_point(receiver, msg_type).is_none() {
+        fail(Error::UnmappedMessageType, context);
+    }
 
-    let verifying_key = match pubkey_bytes.as_ref().try_into() {
-        Ok(key) => key,
-        Err(_) => runtime::revert(Error::InvalidSignature),
+    let paused: Option<bool> =
+        read_dictionary(get_dictionary(KEY_PAUSED_RECEIVERS), &to_hex(receiver));
+
+    if paused == Some(true) {
+        fail(Error::ReceiverPaused, context);
+    }
+
+    let check_receivers: bool = storage::read(get_uref(KEY_CHECK_RECEIVERS))
+        .unwrap_or_revert()
+        .unwrap_or(false);
+
+    if check_receivers && !receiver_exists(receiver) {
+        fail(Error::ReceiverNotFound, context);
+    }
+
+    check_payload_version(receiver, payload, context);
+    check_required_prefix(receiver, payload, context);
+}
+
+/// Whether a pinned receiver's package holds its pinned version, enabled.
+///
+/// The host traps when a contract hash is read as a value, so only package
+/// hashes can be probed; unpinned receivers count as present.
+fn receiver_exists(receiver: &[u8]) -> bool {
+    let version = match pinned_receiver_version(receiver) {
+        Some(version) => version,
+        None => return true,
     };
 
-    let pubkey = PublicKey::Secp256k1(verifying_key);
+    let package: Option<ContractPackage> = storage::read_from_key(Key::Hash(
+        receiver_to_hash_addr(receiver).unwrap_or_revert(),
+    ))
+    .unwrap_or_revert_with(Error::CorruptState);
+
+    package.is_some_and(|package| {
+        package
+            .versions()
+            .keys()
+            .any(|key| key.contract_version() == version && package.is_version_enabled(*key))
+    })
+}
 
-    let sig = Signature::Secp256k1(signature.try_into().unwrap());
+fn check_required_prefix(receiver: &[u8], payload: &[u8], context: &str) {
+    let prefix: Option<Bytes> =
+        read_dictionary(get_dictionary(KEY_REQUIRED_PREFIXES), &to_hex(receiver));
 
-    verify_signature(message, &sig, &pubkey).unwrap_or_revert_with(Error::InvalidSignature);
+    if let Some(prefix) = prefix {
+        if !payload.starts_with(prefix.as_ref()) {
+            fail(Error::PrefixMismatch, context);
+        }
+    }
 }
 
-/// Helpers
-fn build_message_bytes(
-    src_chain_id: u32,
-    dst_chain_id: u32,
-    src_gateway: &[u8],
-    receiver: &[u8],
-    nonce: u64,
-    payload: &[u8],
-) -> Vec<u8> {
-    let mut out = Vec::new();
-    out.extend_from_slice(&src_chain_id.to_be_bytes());
-    out.extend_from_slice(&dst_chain_id.to_be_bytes());
-    out.extend_from_slice(src_gateway);
-    out.extend_from_slice(receiver);
-    out.extend_from_slice(&nonce.to_be_bytes());
-    out.extend_from_slice(payload);
-    out
+/// Revert unless `receiver` accepts the payload's version byte.
+fn check_payload_version(receiver: &[u8], payload: &[u8], context: &str) {
+    let allowed: Option<Bytes> = read_dictionary(
+        get_dictionary(KEY_ALLOWED_PAYLOAD_VERSIONS),
+        &to_hex(receiver),
+    );
+
+    match allowed {
+        Some(versions) if !versions.is_empty() => match payload.first() {
+            Some(version) if versions.contains(version) => {}
+            _ => fail(Error::UnsupportedPayloadVersion, context),
+        },
+        _ => {}
+    }
 }
 
 fn get_uref(name: &str) -> URef {
@@ -283,6 +5529,12 @@ fn get_uref(name: &str) -> URef {
         .unwrap_or_revert()
 }
 
+/// Read a dictionary item. A stored value that does not decode as `T`
+/// (say, after a faulty upgrade) reverts with `CorruptState`.
+fn read_dictionary<T: CLTyped + FromBytes>(dictionary: URef, key: &str) -> Option<T> {
+    storage::dictionary_get(dictionary, key).unwrap_or_revert_with(Error::CorruptState)
+}
+
 fn get_dictionary(name: &str) -> URef {
     runtime::get_key(name)
         .unwrap_or_revert_with(Error::MissingKey)
@@ -300,7 +5552,59 @@ fn to_hex(bytes: &[u8]) -> String {
     s
 }
 
+/// Value the signed `receiver` field takes for a fan-out delivery:
+/// blake2b over the concatenated receivers, in delivery order.
+fn fanout_commitment(receivers: &[Bytes]) -> [u8; 32] {
+    let mut input = Vec::with_capacity(receivers.len() * 32);
+    for receiver in receivers {
+        input.extend_from_slice(receiver.as_ref());
+    }
+    blake2b(input)
+}
+
+/// Message id: hex blake2b of the message bytes, preceded by the
+/// length-prefixed gateway namespace when one is set.
+///
+/// Hashing is the costly part, so each entry point computes the id once and
+/// passes it to every use (dictionary keys, recent ids, error events).
 fn message_key(message: &[u8]) -> String {
-    let digest = blake2b(message);
-    to_hex(&digest)
+    let namespace: String = storage::read(get_uref(KEY_GATEWAY_NAMESPACE))
+        .unwrap_or_revert()
+        .unwrap_or_default();
+
+    if namespace.is_empty() {
+        return to_hex(&blake2b(message));
+    }
+
+    let mut input = Vec::with_capacity(4 + namespace.len() + message.len());
+    input.extend_from_slice(&(namespace.len() as u32).to_be_bytes());
+    input.extend_from_slice(namespace.as_bytes());
+    input.extend_from_slice(message);
+    to_hex(&blake2b(input))
+}
+
+/// Key for the nonce sequence of one `(src chain, src gateway)` pair.
+fn stream_key(src_chain_id: u32, src_gateway: &[u8]) -> String {
+    let mut input = Vec::with_capacity(4 + src_gateway.len());
+    input.extend_from_slice(&src_chain_id.to_be_bytes());
+    input.extend_from_slice(src_gateway);
+    to_hex(&blake2b(input))
+}
+
+/// `nonce_to_id` key for the outbound message to `dst_chain_id` at `nonce`.
+fn nonce_key(dst_chain_id: u32, nonce: u64) -> String {
+    let mut input = Vec::with_capacity(12);
+    input.extend_from_slice(&dst_chain_id.to_be_bytes());
+    input.extend_from_slice(&nonce.to_be_bytes());
+    to_hex(&input)
+}
+
+/// Replay mark for `(namespace version, src chain, src gateway, nonce)`.
+fn replay_key(src_chain_id: u32, src_gateway: &[u8], nonce: u64) -> String {
+    let mut input = Vec::with_capacity(16 + src_gateway.len());
+    input.extend_from_slice(&REPLAY_NAMESPACE_VERSION.to_be_bytes());
+    input.extend_from_slice(&src_chain_id.to_be_bytes());
+    input.extend_from_slice(&nonce.to_be_bytes());
+    input.extend_from_slice(src_gateway);
+    to_hex(&blake2b(input))
 }
@@ -0,0 +1,34 @@
+#![no_std]
+#![no_main]
+
+#[cfg(not(target_arch = "wasm32"))]
+compile_error!("compile with '--target wasm32-unknown-unknown'");
+
+extern crate alloc;
+
+use casper_contract::{
+    contract_api::{account, runtime, system},
+    unwrap_or_revert::UnwrapOrRevert,
+};
+use casper_types::{contracts::ContractHash, runtime_args, U512};
+
+/// Session code for `fund_rewards`: moves `amount` from the caller's main
+/// purse into a fresh purse the gateway can draw from.
+#[no_mangle]
+pub extern "C" fn call() {
+    let gateway: ContractHash = runtime::get_named_arg("gateway");
+    let amount: U512 = runtime::get_named_arg("amount");
+
+    let payment_purse = system::create_purse();
+    system::transfer_from_purse_to_purse(account::get_main_purse(), payment_purse, amount, None)
+        .unwrap_or_revert();
+
+    runtime::call_contract::<()>(
+        gateway,
+        "fund_rewards",
+        runtime_args! {
+            "payment_purse" => payment_purse,
+            "amount" => amount,
+        },
+    );
+}
@@ -0,0 +1,188 @@
+#![no_std]
+#![no_main]
+
+#[cfg(not(target_arch = "wasm32"))]
+compile_error!("compile with '--target wasm32-unknown-unknown'");
+
+extern crate alloc;
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+};
+
+use casper_contract::{
+    contract_api::{runtime, storage},
+    unwrap_or_revert::UnwrapOrRevert,
+};
+use casper_types::{
+    account::AccountHash,
+    contracts::{ContractPackageHash, EntryPoint, EntryPoints},
+    ApiError, CLType, CLTyped, CLValue, EntryPointAccess, EntryPointType, Key, NamedKeys,
+    Parameter, U256,
+};
+
+/// Token balances, keyed by hex-encoded holder hash
+const KEY_BALANCES: &str = "balances";
+
+/// `CallerInfo` fields holding the account hash and contract package hash
+const CALLER_FIELD_ACCOUNT: u8 = 0;
+const CALLER_FIELD_CONTRACT_PACKAGE: u8 = 2;
+
+const DEFAULT_CONTRACT_KEY_NAME: &str = "mock_token";
+
+/// Minimal CEP-18 stand-in: `transfer` and `balance_of` with the standard
+/// argument names, plus an open `mint` for tests to fund holders.
+#[no_mangle]
+pub extern "C" fn call() {
+    let contract_key_name: String = runtime::try_get_named_arg("contract_key_name")
+        .unwrap_or_else(|| DEFAULT_CONTRACT_KEY_NAME.to_string());
+
+    let balances = storage::new_dictionary(KEY_BALANCES).unwrap_or_revert();
+    runtime::remove_key(KEY_BALANCES);
+
+    let mut named_keys = NamedKeys::new();
+    named_keys.insert(KEY_BALANCES.to_string(), balances.into());
+
+    let mut entry_points = EntryPoints::new();
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "transfer",
+        vec![
+            Parameter::new("recipient", Key::cl_type()),
+            Parameter::new("amount", CLType::U256),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "balance_of",
+        vec![Parameter::new("address", Key::cl_type())],
+        CLType::U256,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "mint",
+        vec![
+            Parameter::new("owner", Key::cl_type()),
+            Parameter::new("amount", CLType::U256),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    let (contract_hash, _) = storage::new_contract(
+        entry_points.into(),
+        Some(named_keys),
+        Some(format!("{}_package", contract_key_name)),
+        Some(format!("{}_access", contract_key_name)),
+        None,
+    );
+
+    runtime::put_key(&contract_key_name, contract_hash.into());
+}
+
+/// Move `amount` from the immediate caller to `recipient`.
+#[no_mangle]
+pub extern "C" fn transfer() {
+    let recipient: Key = runtime::get_named_arg("recipient");
+    let amount: U256 = runtime::get_named_arg("amount");
+
+    let sender = immediate_caller();
+    let sender_balance = read_balance(&sender);
+
+    if sender_balance < amount {
+        // CEP-18 `InsufficientBalance`
+        runtime::revert(ApiError::User(60001));
+    }
+
+    write_balance(&sender, sender_balance - amount);
+    write_balance(&recipient, read_balance(&recipient) + amount);
+}
+
+#[no_mangle]
+pub extern "C" fn balance_of() {
+    let address: Key = runtime::get_named_arg("address");
+
+    runtime::ret(CLValue::from_t(read_balance(&address)).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn mint() {
+    let owner: Key = runtime::get_named_arg("owner");
+    let amount: U256 = runtime::get_named_arg("amount");
+
+    write_balance(&owner, read_balance(&owner) + amount);
+}
+
+/// The caller as CEP-18 sees it: a contract by its package hash, otherwise
+/// the account.
+fn immediate_caller() -> Key {
+    let mut call_stack = runtime::get_call_stack();
+    call_stack.pop();
+    let frame = call_stack.pop().unwrap_or_revert();
+
+    let package_hash: Option<ContractPackageHash> = frame
+        .get_field_by_index(CALLER_FIELD_CONTRACT_PACKAGE)
+        .unwrap_or_revert()
+        .clone()
+        .into_t()
+        .unwrap_or_revert();
+
+    if let Some(package_hash) = package_hash {
+        return Key::Hash(package_hash.value());
+    }
+
+    let account_hash: Option<AccountHash> = frame
+        .get_field_by_index(CALLER_FIELD_ACCOUNT)
+        .unwrap_or_revert()
+        .clone()
+        .into_t()
+        .unwrap_or_revert();
+
+    Key::Account(account_hash.unwrap_or_revert())
+}
+
+fn read_balance(holder: &Key) -> U256 {
+    let balances = runtime::get_key(KEY_BALANCES)
+        .unwrap_or_revert()
+        .into_uref()
+        .unwrap_or_revert();
+
+    storage::dictionary_get(balances, &balance_key(holder))
+        .unwrap_or_revert()
+        .unwrap_or_default()
+}
+
+fn write_balance(holder: &Key, amount: U256) {
+    let balances = runtime::get_key(KEY_BALANCES)
+        .unwrap_or_revert()
+        .into_uref()
+        .unwrap_or_revert();
+
+    storage::dictionary_put(balances, &balance_key(holder), amount);
+}
+
+/// Dictionary item keys are capped at 64 bytes, so key the hash part only;
+/// an account and a package never share one.
+fn balance_key(holder: &Key) -> String {
+    let bytes: &[u8] = match holder {
+        Key::Account(account_hash) => account_hash.as_bytes(),
+        Key::Hash(hash) => hash,
+        _ => runtime::revert(ApiError::InvalidArgument),
+    };
+
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    let mut key = String::with_capacity(bytes.len() * 2);
+    for &b in bytes {
+        key.push(HEX[(b >> 4) as usize] as char);
+        key.push(HEX[(b & 0xf) as usize] as char);
+    }
+    key
+}
@@ -7,56 +7,340 @@ compile_error!("compile with '--target wasm32-unknown-unknown'");
 extern crate alloc;
 
 use alloc::{
-    boxed::Box,
+    collections::BTreeMap,
+    format,
     string::{String, ToString},
     vec,
     vec::Vec,
 };
 
 use casper_contract::{
-    contract_api::{runtime, storage},
+    contract_api::{runtime, storage, system},
     unwrap_or_revert::UnwrapOrRevert,
 };
 use casper_types::{
-    contracts::{EntryPoint, EntryPoints},
-    CLType, EntryPointAccess, EntryPointType, NamedKeys
+    contracts::{ContractHash, ContractPackageHash, EntryPoint, EntryPoints},
+    runtime_args, ApiError, CLType, CLValue, EntryPointAccess, EntryPointType, Key, NamedKeys,
+    Parameter,
 };
 
+/// Deliveries across all versions (named keys carry over on upgrade)
 const KEY_COUNT: &str = "count";
+/// Deliveries per contract version, keyed by formatted contract hash
+const KEY_VERSION_COUNTS: &str = "version_counts";
+/// Optional shared contract handing out a global call sequence
+const KEY_SEQUENCER: &str = "sequencer";
+/// Sequence number drawn from the sequencer on the latest call
+const KEY_LAST_SEQUENCE: &str = "last_sequence";
+/// Gateway that `on_call` tries to reconfigure, to exercise its delivery guard
+const KEY_HOSTILE_GATEWAY: &str = "hostile_gateway";
+/// Purse that value-carrying deliveries are paid into
+const KEY_DEPOSIT_PURSE: &str = "deposit_purse";
+/// Present when `on_call` should always revert
+const KEY_REVERT_ON_CALL: &str = "revert_on_call";
+/// Gateway that `on_call` asks whether the message it got is already executed
+const KEY_CHECKED_GATEWAY: &str = "checked_gateway";
+/// Name of the typed handler (`on_transfer` / `on_message`) that ran last
+const KEY_LAST_HANDLER: &str = "last_handler";
+/// Chains `is_chain_supported` approves when acting as a chain registry
+const KEY_APPROVED_CHAINS: &str = "approved_chains";
+/// Type `on_call` returns: `"bool"` (true), `"u64"` (the version count) or nothing
+const KEY_RETURNS: &str = "returns";
+
+/// `CallerInfo` field holding the contract hash
+const CALLER_FIELD_CONTRACT: u8 = 4;
+
+const DEFAULT_CONTRACT_KEY_NAME: &str = "mock_receiver";
 
 #[no_mangle]
 pub extern "C" fn call() {
+    let contract_key_name: String = runtime::try_get_named_arg("contract_key_name")
+        .unwrap_or_else(|| DEFAULT_CONTRACT_KEY_NAME.to_string());
+
     // initialize counter
     let count_uref = storage::new_uref(0u64);
 
+    let version_counts = storage::new_dictionary(KEY_VERSION_COUNTS).unwrap_or_revert();
+    runtime::remove_key(KEY_VERSION_COUNTS);
+
     let mut named_keys = NamedKeys::new();
     named_keys.insert(KEY_COUNT.to_string(), count_uref.into());
+    named_keys.insert(KEY_VERSION_COUNTS.to_string(), version_counts.into());
+    named_keys.insert(KEY_DEPOSIT_PURSE.to_string(), system::create_purse().into());
+    named_keys.insert(
+        KEY_LAST_HANDLER.to_string(),
+        storage::new_uref(String::new()).into(),
+    );
+    let approved_chains: Vec<u32> =
+        runtime::try_get_named_arg("approved_chains").unwrap_or_default();
+    named_keys.insert(
+        KEY_APPROVED_CHAINS.to_string(),
+        storage::new_uref(approved_chains).into(),
+    );
+
+    if let Some(sequencer) = runtime::try_get_named_arg::<ContractHash>("sequencer") {
+        named_keys.insert(KEY_SEQUENCER.to_string(), Key::Hash(sequencer.value()));
+        named_keys.insert(
+            KEY_LAST_SEQUENCE.to_string(),
+            storage::new_uref(0u64).into(),
+        );
+    }
+
+    if let Some(gateway) = runtime::try_get_named_arg::<ContractHash>("hostile_gateway") {
+        named_keys.insert(KEY_HOSTILE_GATEWAY.to_string(), Key::Hash(gateway.value()));
+    }
+
+    if let Some(gateway) = runtime::try_get_named_arg::<ContractHash>("checked_gateway") {
+        named_keys.insert(KEY_CHECKED_GATEWAY.to_string(), Key::Hash(gateway.value()));
+    }
+
+    if let Some(returns) = runtime::try_get_named_arg::<String>("returns") {
+        named_keys.insert(KEY_RETURNS.to_string(), storage::new_uref(returns).into());
+    }
+
+    if runtime::try_get_named_arg::<bool>("revert_on_call").unwrap_or(false) {
+        named_keys.insert(
+            KEY_REVERT_ON_CALL.to_string(),
+            storage::new_uref(true).into(),
+        );
+    }
 
     let mut entry_points = EntryPoints::new();
 
     entry_points.add_entry_point(EntryPoint::new(
         "on_call",
-        vec![],            // no required args
+        vec![], // no required args
         CLType::Unit,
         EntryPointAccess::Public,
         EntryPointType::Called,
     ));
 
-    let (contract_hash, _) = storage::new_contract(entry_points.into(), Some(named_keys), None, None, None);
+    entry_points.add_entry_point(EntryPoint::new(
+        "on_transfer",
+        vec![],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "on_message",
+        vec![],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "deposit_purse",
+        vec![],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
 
-    runtime::put_key("mock_receiver", contract_hash.into());
+    entry_points.add_entry_point(EntryPoint::new(
+        "next_sequence",
+        vec![],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    entry_points.add_entry_point(EntryPoint::new(
+        "is_chain_supported",
+        vec![Parameter::new("chain_id", CLType::U32)],
+        CLType::Bool,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    ));
+
+    // Re-running the installer from the same account adds a new version to
+    // the existing package; each version also counts its own calls.
+    let package_key_name = format!("{}_package", contract_key_name);
+    let access_key_name = format!("{}_access", contract_key_name);
+
+    let contract_hash = match runtime::get_key(&package_key_name) {
+        Some(package_key) => {
+            let package_hash =
+                ContractPackageHash::new(package_key.into_hash_addr().unwrap_or_revert());
+            let (contract_hash, _) = storage::add_contract_version(
+                package_hash,
+                entry_points.into(),
+                named_keys,
+                BTreeMap::new(),
+            );
+            contract_hash
+        }
+        None => {
+            let (contract_hash, _) = storage::new_contract(
+                entry_points.into(),
+                Some(named_keys),
+                Some(package_key_name),
+                Some(access_key_name),
+                None,
+            );
+            contract_hash
+        }
+    };
+
+    runtime::put_key(&contract_key_name, contract_hash.into());
 }
 
 #[no_mangle]
 pub extern "C" fn on_call() {
+    if runtime::has_key(KEY_REVERT_ON_CALL) {
+        runtime::revert(ApiError::User(1));
+    }
+
+    // The gateway must have marked the message before handing it over.
+    if let Some(gateway) = runtime::get_key(KEY_CHECKED_GATEWAY) {
+        let gateway = ContractHash::new(gateway.into_hash_addr().unwrap_or_revert());
+        let message_id: String = runtime::get_named_arg("message_id");
+        let executed: bool = runtime::call_contract(
+            gateway,
+            "is_executed",
+            runtime_args! { "message_id" => message_id },
+        );
+
+        if !executed {
+            runtime::revert(ApiError::User(2));
+        }
+    }
+
     let count_uref = runtime::get_key(KEY_COUNT)
         .unwrap_or_revert()
         .into_uref()
         .unwrap_or_revert();
 
-    let current: u64 = storage::read(count_uref)
+    let current: u64 = storage::read(count_uref).unwrap_or_revert().unwrap_or(0);
+
+    storage::write(count_uref, current + 1);
+
+    let version_counts = runtime::get_key(KEY_VERSION_COUNTS)
+        .unwrap_or_revert()
+        .into_uref()
+        .unwrap_or_revert();
+    let version_key = current_contract_hash().to_formatted_string();
+
+    let current: u64 = storage::dictionary_get(version_counts, &version_key)
         .unwrap_or_revert()
         .unwrap_or(0);
 
-    storage::write(count_uref, current + 1);
+    storage::dictionary_put(version_counts, &version_key, current + 1);
+
+    // Record where this call landed in the sequencer's global order.
+    if let Some(sequencer) = runtime::get_key(KEY_SEQUENCER) {
+        let sequencer = ContractHash::new(sequencer.into_hash_addr().unwrap_or_revert());
+        let sequence: u64 = runtime::call_contract(sequencer, "next_sequence", runtime_args! {});
+
+        let last_sequence = runtime::get_key(KEY_LAST_SEQUENCE)
+            .unwrap_or_revert()
+            .into_uref()
+            .unwrap_or_revert();
+        storage::write(last_sequence, sequence);
+    }
+
+    // Misbehave: try to enable a chain on the gateway delivering to us.
+    if let Some(gateway) = runtime::get_key(KEY_HOSTILE_GATEWAY) {
+        let gateway = ContractHash::new(gateway.into_hash_addr().unwrap_or_revert());
+        runtime::call_contract::<()>(
+            gateway,
+            "set_supported_chain",
+            runtime_args! { "chain_id" => 99u32, "supported" => true },
+        );
+    }
+
+    if let Some(returns) = runtime::get_key(KEY_RETURNS) {
+        let returns: String = storage::read(returns.into_uref().unwrap_or_revert())
+            .unwrap_or_revert()
+            .unwrap_or_default();
+
+        match returns.as_str() {
+            "bool" => runtime::ret(CLValue::from_t(true).unwrap_or_revert()),
+            "u64" => runtime::ret(CLValue::from_t(current + 1).unwrap_or_revert()),
+            _ => {}
+        }
+    }
+}
+
+/// Typed handler a gateway can map a message type to.
+#[no_mangle]
+pub extern "C" fn on_transfer() {
+    record_handler("on_transfer");
+}
+
+/// Typed handler a gateway can map a message type to.
+#[no_mangle]
+pub extern "C" fn on_message() {
+    record_handler("on_message");
+}
+
+fn record_handler(name: &str) {
+    let last_handler = runtime::get_key(KEY_LAST_HANDLER)
+        .unwrap_or_revert()
+        .into_uref()
+        .unwrap_or_revert();
+
+    storage::write(last_handler, name.to_string());
+}
+
+/// Hand out the deposit purse with add-only access, so senders can pay in
+/// but never withdraw.
+#[no_mangle]
+pub extern "C" fn deposit_purse() {
+    let purse = runtime::get_key(KEY_DEPOSIT_PURSE)
+        .unwrap_or_revert()
+        .into_uref()
+        .unwrap_or_revert();
+
+    runtime::ret(CLValue::from_t(purse.into_add()).unwrap_or_revert());
+}
+
+/// Bump `count` and return the new value.
+#[no_mangle]
+pub extern "C" fn next_sequence() {
+    let count_uref = runtime::get_key(KEY_COUNT)
+        .unwrap_or_revert()
+        .into_uref()
+        .unwrap_or_revert();
+
+    let next: u64 = storage::read::<u64>(count_uref)
+        .unwrap_or_revert()
+        .unwrap_or(0)
+        + 1;
+
+    storage::write(count_uref, next);
+
+    runtime::ret(CLValue::from_t(next).unwrap_or_revert());
+}
+
+/// Chain registry check: true for chains passed as `approved_chains`.
+#[no_mangle]
+pub extern "C" fn is_chain_supported() {
+    let chain_id: u32 = runtime::get_named_arg("chain_id");
+
+    let approved_chains = runtime::get_key(KEY_APPROVED_CHAINS)
+        .unwrap_or_revert()
+        .into_uref()
+        .unwrap_or_revert();
+    let approved_chains: Vec<u32> = storage::read(approved_chains)
+        .unwrap_or_revert()
+        .unwrap_or_default();
+
+    runtime::ret(CLValue::from_t(approved_chains.contains(&chain_id)).unwrap_or_revert());
+}
+
+/// Hash of the contract version currently executing.
+fn current_contract_hash() -> ContractHash {
+    let frame = runtime::get_call_stack().pop().unwrap_or_revert();
+
+    let contract_hash: Option<ContractHash> = frame
+        .get_field_by_index(CALLER_FIELD_CONTRACT)
+        .unwrap_or_revert()
+        .clone()
+        .into_t()
+        .unwrap_or_revert();
+
+    contract_hash.unwrap_or_revert()
 }
@@ -0,0 +1,50 @@
+#![no_std]
+#![no_main]
+
+#[cfg(not(target_arch = "wasm32"))]
+compile_error!("compile with '--target wasm32-unknown-unknown'");
+
+extern crate alloc;
+
+use casper_contract::{
+    contract_api::{account, runtime, system},
+    unwrap_or_revert::UnwrapOrRevert,
+};
+use casper_types::{bytesrepr::Bytes, contracts::ContractHash, runtime_args, U512};
+
+/// Session code for `send_message` on a gateway that charges a fee.
+///
+/// A stored contract cannot draw from the caller's main purse, so the fee is
+/// moved into a fresh purse here and handed to the gateway as `payment_purse`.
+#[no_mangle]
+pub extern "C" fn call() {
+    let gateway: ContractHash = runtime::get_named_arg("gateway");
+    let amount: U512 = runtime::get_named_arg("amount");
+    let dst_chain_id: u32 = runtime::get_named_arg("dst_chain_id");
+    let receiver: Bytes = runtime::get_named_arg("receiver");
+    let payload: Bytes = runtime::get_named_arg("payload");
+    let ttl_ms: Option<u64> = runtime::try_get_named_arg("ttl_ms");
+    // CSPR to carry with the message; `amount` must cover it plus the fee.
+    let value: Option<U512> = runtime::try_get_named_arg("value");
+
+    let payment_purse = system::create_purse();
+    system::transfer_from_purse_to_purse(account::get_main_purse(), payment_purse, amount, None)
+        .unwrap_or_revert();
+
+    let mut args = runtime_args! {
+        "dst_chain_id" => dst_chain_id,
+        "receiver" => receiver,
+        "payload" => payload,
+        "payment_purse" => payment_purse,
+    };
+
+    if let Some(ttl_ms) = ttl_ms {
+        args.insert("ttl_ms", ttl_ms).unwrap_or_revert();
+    }
+
+    if let Some(value) = value {
+        args.insert("amount", value).unwrap_or_revert();
+    }
+
+    runtime::call_contract::<Bytes>(gateway, "send_message", args);
+}
@@ -5,7 +5,8 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use casper_engine_test_support::{
-        ExecuteRequestBuilder, LmdbWasmTestBuilder, DEFAULT_ACCOUNT_ADDR, LOCAL_GENESIS_REQUEST,
+        ExecuteRequest, ExecuteRequestBuilder, LmdbWasmTestBuilder, TransferRequestBuilder,
+        DEFAULT_ACCOUNT_ADDR, LOCAL_GENESIS_REQUEST, MINIMUM_ACCOUNT_CREATION_BALANCE,
     };
 
     use casper_execution_engine::engine_state::Error;
@@ -13,8 +14,12 @@ mod tests {
 
     use casper_execution_engine::runtime::cryptography::blake2b;
     use casper_types::contracts::ContractHash;
-    use casper_types::URef;
-    use casper_types::{bytesrepr::Bytes, runtime_args, ApiError, Key, StoredValue};
+    use casper_types::{
+        account::AccountHash,
+        bytesrepr::{self, Bytes, FromBytes, ToBytes},
+        runtime_args, ApiError, CLType, CLTyped, CLValue, CLValueDictionary, Key, PublicKey,
+        RuntimeArgs, SecretKey, StoredValue, URef, U256, U512,
+    };
 
     use k256::ecdsa::signature::Signer;
     use k256::ecdsa::{Signature as K256Signature, SigningKey};
@@ -22,69 +27,318 @@ mod tests {
 
     const CONTRACT_WASM: &str = "contract.wasm";
     const RECEIVER_WASM: &str = "receiver.wasm";
+    const SEND_WITH_FEE_WASM: &str = "send_with_fee.wasm";
+    const FUND_REWARDS_WASM: &str = "fund_rewards.wasm";
+    const MOCK_TOKEN_WASM: &str = "mock_token.wasm";
+
+    const GATEWAY_KEY: &str = "knotx_gateway";
+    const RECEIVER_KEY: &str = "mock_receiver";
 
     const KEY_NONCE: &str = "nonce";
+    const KEY_CHAIN_NONCES: &str = "chain_nonces";
     const KEY_MESSAGES: &str = "messages";
     const KEY_EXECUTED_MESSAGES: &str = "executed_messages";
 
     const CASPER_CHAIN_ID: u32 = 3;
+    const CONTRACT_VERSION: &str = "0.1.0";
     const DST_CHAIN_ID: u32 = 1;
 
-    // ------------------------------------------------
-    // Helpers
-    // ------------------------------------------------
+    /// Mirrors the contract's `BuildInfo`
+    #[derive(Debug)]
+    struct BuildInfo {
+        version: String,
+        git_commit: String,
+    }
 
-    fn install(builder: &mut LmdbWasmTestBuilder) -> ContractHash {
-        let install = ExecuteRequestBuilder::standard(
-            *DEFAULT_ACCOUNT_ADDR,
-            CONTRACT_WASM,
-            runtime_args! {
-                "relayer_pubkey" => Bytes::from(vec![1u8; 33]),
-            },
-        )
-        .build();
+    impl FromBytes for BuildInfo {
+        fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+            let (version, rem) = String::from_bytes(bytes)?;
+            let (git_commit, rem) = String::from_bytes(rem)?;
+            Ok((
+                BuildInfo {
+                    version,
+                    git_commit,
+                },
+                rem,
+            ))
+        }
+    }
 
-        builder.exec(install).commit().expect_success();
+    impl CLTyped for BuildInfo {
+        fn cl_type() -> CLType {
+            CLType::Any
+        }
+    }
 
-        let account = builder
-            .get_account(*DEFAULT_ACCOUNT_ADDR)
-            .expect("account exists");
+    /// Mirrors the contract's `Stats`
+    #[derive(Debug, PartialEq)]
+    struct Stats {
+        sent: u64,
+        executed: u64,
+        nonce: u64,
+        supported_chains: u32,
+        paused: bool,
+    }
 
-        for (_name, key) in account.named_keys().iter() {
-            if let Key::Hash(hash) = key {
-                return ContractHash::new(*hash);
-            }
+    impl FromBytes for Stats {
+        fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+            let (sent, rem) = u64::from_bytes(bytes)?;
+            let (executed, rem) = u64::from_bytes(rem)?;
+            let (nonce, rem) = u64::from_bytes(rem)?;
+            let (supported_chains, rem) = u32::from_bytes(rem)?;
+            let (paused, rem) = bool::from_bytes(rem)?;
+            Ok((
+                Stats {
+                    sent,
+                    executed,
+                    nonce,
+                    supported_chains,
+                    paused,
+                },
+                rem,
+            ))
+        }
+    }
+
+    /// Mirrors the contract's `StorageStats`
+    #[derive(Debug, PartialEq)]
+    struct StorageStats {
+        messages: u64,
+        executed_messages: u64,
+        error_events: u64,
+    }
+
+    impl FromBytes for StorageStats {
+        fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+            let (messages, rem) = u64::from_bytes(bytes)?;
+            let (executed_messages, rem) = u64::from_bytes(rem)?;
+            let (error_events, rem) = u64::from_bytes(rem)?;
+            Ok((
+                StorageStats {
+                    messages,
+                    executed_messages,
+                    error_events,
+                },
+                rem,
+            ))
+        }
+    }
+
+    impl CLTyped for StorageStats {
+        fn cl_type() -> CLType {
+            CLType::Any
+        }
+    }
+
+    /// Mirrors the contract's `ExecutionRecord`
+    #[derive(Debug, PartialEq)]
+    struct ExecutionRecord {
+        src_chain_id: u32,
+        src_gateway: Bytes,
+        nonce: u64,
+        executed_at: u64,
+    }
+
+    impl FromBytes for ExecutionRecord {
+        fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+            let (src_chain_id, rem) = u32::from_bytes(bytes)?;
+            let (src_gateway, rem) = Bytes::from_bytes(rem)?;
+            let (nonce, rem) = u64::from_bytes(rem)?;
+            let (executed_at, rem) = u64::from_bytes(rem)?;
+            Ok((
+                ExecutionRecord {
+                    src_chain_id,
+                    src_gateway,
+                    nonce,
+                    executed_at,
+                },
+                rem,
+            ))
+        }
+    }
+
+    impl CLTyped for ExecutionRecord {
+        fn cl_type() -> CLType {
+            CLType::Any
+        }
+    }
+
+    /// Mirrors the contract's `MessageMeta`
+    #[derive(Debug, PartialEq)]
+    struct MessageMeta {
+        sender: AccountHash,
+        dst_chain_id: u32,
+        nonce: u64,
+        timestamp: u64,
+        deadline: u64,
+    }
+
+    impl FromBytes for MessageMeta {
+        fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+            let (sender, rem) = AccountHash::from_bytes(bytes)?;
+            let (dst_chain_id, rem) = u32::from_bytes(rem)?;
+            let (nonce, rem) = u64::from_bytes(rem)?;
+            let (timestamp, rem) = u64::from_bytes(rem)?;
+            let (deadline, rem) = u64::from_bytes(rem)?;
+            Ok((
+                MessageMeta {
+                    sender,
+                    dst_chain_id,
+                    nonce,
+                    timestamp,
+                    deadline,
+                },
+                rem,
+            ))
+        }
+    }
+
+    impl CLTyped for MessageMeta {
+        fn cl_type() -> CLType {
+            CLType::Any
+        }
+    }
+
+    /// Mirrors the contract's `GatewayEvent`
+    #[derive(Debug, PartialEq)]
+    struct GatewayEvent {
+        global_seq: u64,
+        kind: String,
+        subject: String,
+        timestamp: u64,
+    }
+
+    impl FromBytes for GatewayEvent {
+        fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+            let (global_seq, rem) = u64::from_bytes(bytes)?;
+            let (kind, rem) = String::from_bytes(rem)?;
+            let (subject, rem) = String::from_bytes(rem)?;
+            let (timestamp, rem) = u64::from_bytes(rem)?;
+            Ok((
+                GatewayEvent {
+                    global_seq,
+                    kind,
+                    subject,
+                    timestamp,
+                },
+                rem,
+            ))
+        }
+    }
+
+    impl CLTyped for GatewayEvent {
+        fn cl_type() -> CLType {
+            CLType::Any
+        }
+    }
+
+    /// Mirrors the contract's `ChainConfig`
+    #[derive(Debug, PartialEq)]
+    struct ChainConfig {
+        status: u8,
+        ordered: bool,
+        threshold: Option<u8>,
+        dst_gateway: Option<Bytes>,
+        required_sig_scheme: Option<u8>,
+        addr_len: Option<u8>,
+    }
+
+    impl FromBytes for ChainConfig {
+        fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+            let (status, rem) = u8::from_bytes(bytes)?;
+            let (ordered, rem) = bool::from_bytes(rem)?;
+            let (threshold, rem) = Option::<u8>::from_bytes(rem)?;
+            let (dst_gateway, rem) = Option::<Bytes>::from_bytes(rem)?;
+            let (required_sig_scheme, rem) = Option::<u8>::from_bytes(rem)?;
+            let (addr_len, rem) = Option::<u8>::from_bytes(rem)?;
+            Ok((
+                ChainConfig {
+                    status,
+                    ordered,
+                    threshold,
+                    dst_gateway,
+                    required_sig_scheme,
+                    addr_len,
+                },
+                rem,
+            ))
+        }
+    }
+
+    impl CLTyped for ChainConfig {
+        fn cl_type() -> CLType {
+            CLType::Any
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct DeliveryCall {
+        receiver: [u8; 32],
+        version: Option<u32>,
+        entry_point: String,
+        payload_len: u32,
+        amount: U512,
+    }
+
+    impl FromBytes for DeliveryCall {
+        fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+            let (receiver, rem) = <[u8; 32]>::from_bytes(bytes)?;
+            let (version, rem) = Option::<u32>::from_bytes(rem)?;
+            let (entry_point, rem) = String::from_bytes(rem)?;
+            let (payload_len, rem) = u32::from_bytes(rem)?;
+            let (amount, rem) = U512::from_bytes(rem)?;
+            Ok((
+                DeliveryCall {
+                    receiver,
+                    version,
+                    entry_point,
+                    payload_len,
+                    amount,
+                },
+                rem,
+            ))
+        }
+    }
+
+    impl CLTyped for DeliveryCall {
+        fn cl_type() -> CLType {
+            CLType::Any
+        }
+    }
+
+    impl CLTyped for Stats {
+        fn cl_type() -> CLType {
+            CLType::Any
         }
+    }
+
+    // ------------------------------------------------
+    // Helpers
+    // ------------------------------------------------
 
-        panic!("contract hash not found");
+    fn install(builder: &mut LmdbWasmTestBuilder) -> ContractHash {
+        install_with_pubkey(builder, unused_relayer_key())
     }
 
     fn install_with_pubkey(
         builder: &mut LmdbWasmTestBuilder,
-        relayer_pubkey: Bytes,
+        relayer_pubkey: PublicKey,
     ) -> ContractHash {
-        let install = ExecuteRequestBuilder::standard(
-            *DEFAULT_ACCOUNT_ADDR,
-            CONTRACT_WASM,
+        install_with_args(
+            builder,
             runtime_args! {
                 "relayer_pubkey" => relayer_pubkey,
             },
         )
-        .build();
-
-        builder.exec(install).commit().expect_success();
+    }
 
-        let account = builder
-            .get_account(*DEFAULT_ACCOUNT_ADDR)
-            .expect("account exists");
+    fn install_with_args(builder: &mut LmdbWasmTestBuilder, args: RuntimeArgs) -> ContractHash {
+        let install =
+            ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, CONTRACT_WASM, args).build();
 
-        for (_name, key) in account.named_keys().iter() {
-            if let Key::Hash(hash) = key {
-                return ContractHash::new(*hash);
-            }
-        }
+        builder.exec(install).commit().expect_success();
 
-        panic!("contract hash not found");
+        named_contract(builder, GATEWAY_KEY)
     }
 
     fn install_receiver(builder: &mut LmdbWasmTestBuilder) -> ContractHash {
@@ -94,15 +348,18 @@ mod tests {
 
         builder.exec(install).commit().expect_success();
 
-        let account = builder.get_account(*DEFAULT_ACCOUNT_ADDR).expect("account");
+        named_contract(builder, RECEIVER_KEY)
+    }
 
-        for (_name, key) in account.named_keys().iter() {
-            if let Key::Hash(hash) = key {
-                return ContractHash::new(*hash);
-            }
-        }
+    fn named_contract(builder: &LmdbWasmTestBuilder, name: &str) -> ContractHash {
+        let account = builder
+            .get_account(*DEFAULT_ACCOUNT_ADDR)
+            .expect("account exists");
 
-        panic!("contract hash not found");
+        match account.named_keys().get(name) {
+            Some(Key::Hash(hash)) => ContractHash::new(*hash),
+            _ => panic!("contract hash not found"),
+        }
     }
 
     fn set_supported_chain(
@@ -155,37 +412,241 @@ mod tests {
         nonce: u64,
         payload: &[u8],
     ) -> Vec<u8> {
+        build_message_bytes_with_amount(
+            src_chain_id,
+            dst_chain_id,
+            src_gateway,
+            receiver,
+            nonce,
+            U512::zero(),
+            payload,
+        )
+    }
+
+    fn build_message_bytes_with_amount(
+        src_chain_id: u32,
+        dst_chain_id: u32,
+        src_gateway: &[u8],
+        receiver: &[u8],
+        nonce: u64,
+        amount: U512,
+        payload: &[u8],
+    ) -> Vec<u8> {
+        build_typed_message_bytes(
+            src_chain_id,
+            dst_chain_id,
+            src_gateway,
+            receiver,
+            nonce,
+            amount,
+            0,
+            0,
+            payload,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn build_typed_message_bytes(
+        src_chain_id: u32,
+        dst_chain_id: u32,
+        src_gateway: &[u8],
+        receiver: &[u8],
+        nonce: u64,
+        amount: U512,
+        msg_type: u8,
+        sig_scheme: u8,
+        payload: &[u8],
+    ) -> Vec<u8> {
+        let mut wide_amount = [0u8; 64];
+        amount.to_big_endian(&mut wide_amount);
+
         let mut out = Vec::new();
         out.extend_from_slice(&src_chain_id.to_be_bytes());
         out.extend_from_slice(&dst_chain_id.to_be_bytes());
         out.extend_from_slice(src_gateway);
         out.extend_from_slice(receiver);
         out.extend_from_slice(&nonce.to_be_bytes());
+        out.extend_from_slice(&wide_amount[32..]);
+        out.push(msg_type);
+        out.push(sig_scheme);
         out.extend_from_slice(payload);
         out
     }
 
-    fn hex(bytes: &[u8]) -> String {
-        const HEX: &[u8; 16] = b"0123456789abcdef";
-        let mut s = String::with_capacity(bytes.len() * 2);
-        for &b in bytes {
-            s.push(HEX[(b >> 4) as usize] as char);
-            s.push(HEX[(b & 0xf) as usize] as char);
-        }
-        s
+    fn sign_message(message: &[u8]) -> (PublicKey, Bytes) {
+        let signing_key = SigningKey::random(&mut OsRng);
+
+        (relayer_key(&signing_key), sign_with(&signing_key, message))
     }
 
-    fn sign_message(message: &[u8]) -> (Bytes, Bytes) {
-        let signing_key = SigningKey::random(&mut OsRng);
-        let verify_key = signing_key.verifying_key();
+    fn relayer_key(signing_key: &SigningKey) -> PublicKey {
+        let secret_key =
+            SecretKey::secp256k1_from_bytes(signing_key.to_bytes()).expect("secp256k1 key");
+        PublicKey::from(&secret_key)
+    }
+
+    /// A relayer key nobody in the test signs with
+    fn unused_relayer_key() -> PublicKey {
+        relayer_key(&SigningKey::random(&mut OsRng))
+    }
 
+    fn sign_with(signing_key: &SigningKey, message: &[u8]) -> Bytes {
         let sig: K256Signature = signing_key.sign(message);
+        Bytes::from(sig.to_bytes().to_vec())
+    }
+
+    fn set_committee(
+        builder: &mut LmdbWasmTestBuilder,
+        contract: ContractHash,
+        committee: &[SigningKey],
+        threshold: u8,
+    ) {
+        let relayers: Vec<PublicKey> = committee.iter().map(relayer_key).collect();
+        let call = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "set_committee",
+            runtime_args! {
+                "relayers" => relayers,
+                "threshold" => threshold,
+            },
+        )
+        .build();
+
+        builder.exec(call).commit().expect_success();
+    }
+
+    /// One entry per committee member, empty for those not in `signers`.
+    fn committee_signatures(
+        committee: &[SigningKey],
+        signers: &[usize],
+        message: &[u8],
+    ) -> Vec<Bytes> {
+        committee
+            .iter()
+            .enumerate()
+            .map(|(i, key)| {
+                if signers.contains(&i) {
+                    sign_with(key, message)
+                } else {
+                    Bytes::new()
+                }
+            })
+            .collect()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn execute_message_request(
+        contract: ContractHash,
+        src_chain_id: u32,
+        src_gateway: Bytes,
+        receiver: Bytes,
+        nonce: u64,
+        payload: Bytes,
+        signature: Bytes,
+    ) -> ExecuteRequest {
+        ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "execute_message",
+            runtime_args! {
+                "src_chain_id" => src_chain_id,
+                "src_gateway" => src_gateway,
+                "receiver" => receiver,
+                "nonce" => nonce,
+                "payload" => payload,
+                "signature" => signature,
+            },
+        )
+        .build()
+    }
+
+    /// Calls a view entry point and decodes the value it returned.
+    fn call_getter<T: CLTyped + FromBytes>(
+        builder: &mut LmdbWasmTestBuilder,
+        contract: ContractHash,
+        entry_point: &str,
+        args: RuntimeArgs,
+    ) -> T {
+        let call = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            entry_point,
+            args,
+        )
+        .build();
+
+        builder.exec(call).commit().expect_success();
+
+        builder
+            .get_last_exec_result()
+            .expect("exec result")
+            .ret()
+            .expect("return value")
+            .clone()
+            .into_t()
+            .expect("decode return value")
+    }
+
+    /// Reads an item from one of the contract's dictionaries, `None` if absent.
+    fn dictionary_item<T: CLTyped + FromBytes>(
+        builder: &LmdbWasmTestBuilder,
+        contract: ContractHash,
+        dictionary: &str,
+        key: &str,
+    ) -> Option<T> {
+        let dictionary_uref = builder
+            .get_contract(contract)
+            .expect("contract")
+            .named_keys()
+            .get(dictionary)
+            .expect("dictionary named key")
+            .into_uref()
+            .expect("dictionary should be URef");
+
+        builder
+            .query_dictionary_item(None, dictionary_uref, key)
+            .ok()
+            .map(|value| {
+                value
+                    .as_cl_value()
+                    .expect("cl value")
+                    .clone()
+                    .into_t()
+                    .expect("decode dictionary item")
+            })
+    }
+
+    fn receiver_count(builder: &LmdbWasmTestBuilder, receiver: ContractHash) -> u64 {
+        builder
+            .query(None, Key::Hash(receiver.value()), &["count".to_string()])
+            .expect("count named key")
+            .as_cl_value()
+            .expect("cl value")
+            .clone()
+            .into_t()
+            .expect("u64")
+    }
 
-        let pubkey = verify_key.to_encoded_point(false);
-        let pubkey_bytes = Bytes::from(verify_key.to_encoded_point(true).as_bytes().to_vec());
-        let sig_bytes = Bytes::from(sig.to_bytes().to_vec());
+    fn contract_uref(builder: &LmdbWasmTestBuilder, contract: ContractHash, name: &str) -> URef {
+        builder
+            .get_contract(contract)
+            .expect("contract")
+            .named_keys()
+            .get(name)
+            .expect("named key")
+            .into_uref()
+            .expect("named key should be URef")
+    }
 
-        (pubkey_bytes, sig_bytes)
+    fn assert_user_error(builder: &LmdbWasmTestBuilder, code: u16) {
+        let err = builder.get_error().expect("execution error");
+        assert!(
+            matches!(err, Error::Exec(ExecError::Revert(ApiError::User(c))) if c == code),
+            "expected user error {}, got {:?}",
+            code,
+            err
+        );
     }
 
     fn message_key(message: &[u8]) -> String {
@@ -300,8 +761,6 @@ mod tests {
             payload.as_ref(),
         );
 
-        // Dictionary keys must be short → hash
-        let digest = blake2b(&message_bytes);
         let message_id = "0";
 
         let contract = builder.get_contract(contract).expect("contract");
@@ -314,7 +773,7 @@ mod tests {
             .expect("messages should be URef");
 
         let stored = builder
-            .query_dictionary_item(None, messages_uref, &message_id)
+            .query_dictionary_item(None, messages_uref, message_id)
             .expect("dictionary item")
             .as_cl_value()
             .expect("cl value")
@@ -364,14 +823,13 @@ mod tests {
 
         builder.exec(call).commit().expect_success();
 
-        let digest = blake2b(&message);
-        let key = hex(&digest);
+        let key = message_key(&message);
 
         let contract = builder.get_contract(contract).expect("contract");
 
         let messages_uref = contract
             .named_keys()
-            .get("executed_messages")
+            .get(KEY_EXECUTED_MESSAGES)
             .expect("messages named key")
             .into_uref()
             .expect("messages should be URef");
@@ -382,10 +840,10 @@ mod tests {
             .as_cl_value()
             .expect("cl value")
             .clone()
-            .into_t::<bool>()
-            .expect("bool");
+            .into_t::<Option<ExecutionRecord>>()
+            .expect("execution record");
 
-        assert!(executed);
+        assert!(executed.is_some());
     }
 
     #[test]
@@ -444,4 +902,6865 @@ mod tests {
 
         assert_eq!(counter, 1);
     }
+
+    #[test]
+    fn execute_message_reverts_on_local_source_chain() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let receiver_contract = install_receiver(&mut builder);
+        let receiver = Bytes::from(receiver_contract.value().to_vec());
+
+        let payload = Bytes::from(vec![1, 2, 3]);
+
+        let message = build_message_bytes(
+            CASPER_CHAIN_ID,
+            CASPER_CHAIN_ID,
+            &[9u8; 32],
+            receiver.as_ref(),
+            0,
+            payload.as_ref(),
+        );
+
+        let (relayer_pubkey, signature) = sign_message(&message);
+        let gateway = install_with_pubkey(&mut builder, relayer_pubkey);
+
+        let call = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "execute_message",
+            runtime_args! {
+                "src_chain_id" => CASPER_CHAIN_ID,
+                "src_gateway" => Bytes::from(vec![9u8; 32]),
+                "receiver" => receiver,
+                "nonce" => 0u64,
+                "payload" => payload,
+                "signature" => signature,
+            },
+        )
+        .build();
+
+        builder.exec(call).commit().expect_failure();
+
+        let err = builder.get_error().unwrap();
+        assert!(matches!(
+            err,
+            Error::Exec(ExecError::Revert(ApiError::User(29)))
+        ));
+    }
+
+    #[test]
+    fn execute_message_rejects_high_s_signature() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let receiver_contract = install_receiver(&mut builder);
+        let receiver = Bytes::from(receiver_contract.value().to_vec());
+        let src_gateway = Bytes::from(vec![9u8; 32]);
+        let payload = Bytes::from(vec![1, 2, 3]);
+
+        let message = build_message_bytes(
+            1,
+            CASPER_CHAIN_ID,
+            src_gateway.as_ref(),
+            receiver.as_ref(),
+            0,
+            payload.as_ref(),
+        );
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+
+        // k256 always produces low-s; negate s to get the malleable twin.
+        let low: K256Signature = signing_key.sign(&message);
+        let (r, s) = low.split_scalars();
+        let high = K256Signature::from_scalars(r.to_bytes(), (-*s).to_bytes()).unwrap();
+        assert!(high.normalize_s().is_some());
+
+        let call = execute_message_request(
+            gateway,
+            1,
+            src_gateway.clone(),
+            receiver.clone(),
+            0,
+            payload.clone(),
+            Bytes::from(high.to_bytes().to_vec()),
+        );
+        builder.exec(call).commit().expect_failure();
+        assert_user_error(&builder, 5);
+
+        let call = execute_message_request(
+            gateway,
+            1,
+            src_gateway,
+            receiver,
+            0,
+            payload,
+            Bytes::from(low.to_bytes().to_vec()),
+        );
+        builder.exec(call).commit().expect_success();
+    }
+
+    #[test]
+    fn get_dict_uref_returns_queryable_dictionary() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+        set_supported_chain(&mut builder, contract, DST_CHAIN_ID, true);
+
+        send_message(
+            &mut builder,
+            contract,
+            DST_CHAIN_ID,
+            Bytes::from(vec![9u8; 32]),
+            Bytes::from(vec![1u8]),
+        );
+
+        let messages_uref: URef = call_getter(
+            &mut builder,
+            contract,
+            "get_dict_uref",
+            runtime_args! { "name" => KEY_MESSAGES.to_string() },
+        );
+
+        let stored = builder
+            .query_dictionary_item(None, messages_uref, "0")
+            .expect("dictionary item")
+            .as_cl_value()
+            .expect("cl value")
+            .clone()
+            .into_t::<Bytes>()
+            .expect("bytes");
+
+        assert!(!stored.is_empty());
+
+        let call = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "get_dict_uref",
+            runtime_args! { "name" => KEY_NONCE.to_string() },
+        )
+        .build();
+        builder.exec(call).commit().expect_failure();
+        assert_user_error(&builder, 4);
+    }
+
+    #[test]
+    fn chain_registry_decides_outbound_support() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+
+        let install_registry = ExecuteRequestBuilder::standard(
+            *DEFAULT_ACCOUNT_ADDR,
+            RECEIVER_WASM,
+            runtime_args! {
+                "contract_key_name" => "chain_registry".to_string(),
+                "approved_chains" => vec![7u32],
+            },
+        )
+        .build();
+        builder.exec(install_registry).commit().expect_success();
+        let registry = named_contract(&builder, "chain_registry");
+
+        let set_registry = |builder: &mut LmdbWasmTestBuilder, registry: Option<ContractHash>| {
+            let call = ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                contract.into(),
+                "set_chain_registry",
+                runtime_args! { "registry" => registry },
+            )
+            .build();
+            builder.exec(call).commit().expect_success();
+        };
+        let send_request = |dst_chain_id: u32| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                contract.into(),
+                "send_message",
+                runtime_args! {
+                    "dst_chain_id" => dst_chain_id,
+                    "receiver" => Bytes::from(vec![5u8; 32]),
+                    "payload" => Bytes::from(vec![1u8]),
+                },
+            )
+            .build()
+        };
+
+        set_registry(&mut builder, Some(registry));
+
+        // Approved by the registry, never enabled locally.
+        send_message(
+            &mut builder,
+            contract,
+            7,
+            Bytes::from(vec![5u8; 32]),
+            Bytes::from(vec![1u8]),
+        );
+
+        // The registry overrides the local dictionary.
+        set_supported_chain(&mut builder, contract, DST_CHAIN_ID, true);
+        builder
+            .exec(send_request(DST_CHAIN_ID))
+            .commit()
+            .expect_failure();
+        assert_user_error(&builder, 1);
+
+        let flags: Vec<bool> = call_getter(
+            &mut builder,
+            contract,
+            "are_chains_supported",
+            runtime_args! { "chain_ids" => vec![7u32, DST_CHAIN_ID] },
+        );
+        assert_eq!(flags, vec![true, false]);
+
+        // Unset: back to the dictionary.
+        set_registry(&mut builder, None);
+        builder.exec(send_request(7)).commit().expect_failure();
+        assert_user_error(&builder, 1);
+        builder
+            .exec(send_request(DST_CHAIN_ID))
+            .commit()
+            .expect_success();
+    }
+
+    #[test]
+    fn describe_named_key_reports_known_keys_only() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+
+        let describe = |builder: &mut LmdbWasmTestBuilder, name: &str| -> String {
+            call_getter(
+                builder,
+                contract,
+                "describe_named_key",
+                runtime_args! { "name" => name.to_string() },
+            )
+        };
+
+        assert_eq!(describe(&mut builder, KEY_NONCE), "uref<U64>");
+        assert_eq!(describe(&mut builder, KEY_MESSAGES), "dictionary");
+        assert_eq!(describe(&mut builder, "fee_purse"), "uref");
+
+        let call = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "describe_named_key",
+            runtime_args! { "name" => "not_a_key".to_string() },
+        )
+        .build();
+        builder.exec(call).commit().expect_failure();
+        assert_user_error(&builder, 4);
+    }
+
+    #[test]
+    fn execute_message_to_null_receiver_only_marks_executed() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+
+        let null_receiver = Bytes::from(vec![0u8; 32]);
+        let src_gateway = Bytes::from(vec![9u8; 32]);
+
+        // Empty payload: accepted, nothing is called.
+        let message = build_message_bytes(
+            1,
+            CASPER_CHAIN_ID,
+            src_gateway.as_ref(),
+            null_receiver.as_ref(),
+            0,
+            &[],
+        );
+        let call = execute_message_request(
+            gateway,
+            1,
+            src_gateway.clone(),
+            null_receiver.clone(),
+            0,
+            Bytes::new(),
+            sign_with(&signing_key, &message),
+        );
+        builder.exec(call).commit().expect_success();
+
+        let executed: Option<Option<ExecutionRecord>> = dictionary_item(
+            &builder,
+            gateway,
+            KEY_EXECUTED_MESSAGES,
+            &message_key(&message),
+        );
+        assert!(matches!(executed, Some(Some(_))));
+
+        // Payload without the opt-in flag is rejected.
+        let payload = Bytes::from(vec![1u8]);
+        let message = build_message_bytes(
+            1,
+            CASPER_CHAIN_ID,
+            src_gateway.as_ref(),
+            null_receiver.as_ref(),
+            1,
+            payload.as_ref(),
+        );
+        let call = execute_message_request(
+            gateway,
+            1,
+            src_gateway,
+            null_receiver,
+            1,
+            payload,
+            sign_with(&signing_key, &message),
+        );
+        builder.exec(call).commit().expect_failure();
+        assert_user_error(&builder, 6);
+    }
+
+    #[test]
+    fn execute_message_to_null_receiver_accepts_payload_when_allowed() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => relayer_key(&signing_key),
+                "allow_null_payload" => true,
+            },
+        );
+
+        let null_receiver = Bytes::from(vec![0u8; 32]);
+        let src_gateway = Bytes::from(vec![9u8; 32]);
+        let payload = Bytes::from(vec![1u8, 2, 3]);
+
+        let message = build_message_bytes(
+            1,
+            CASPER_CHAIN_ID,
+            src_gateway.as_ref(),
+            null_receiver.as_ref(),
+            0,
+            payload.as_ref(),
+        );
+        let call = execute_message_request(
+            gateway,
+            1,
+            src_gateway,
+            null_receiver,
+            0,
+            payload,
+            sign_with(&signing_key, &message),
+        );
+        builder.exec(call).commit().expect_success();
+    }
+
+    #[test]
+    fn get_recent_message_ids_keeps_latest_newest_first() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => unused_relayer_key(),
+                "recent_ids_capacity" => 16u32,
+            },
+        );
+        set_supported_chain(&mut builder, contract, DST_CHAIN_ID, true);
+
+        let receiver = Bytes::from(vec![9u8; 32]);
+        let payload = Bytes::from(vec![1u8]);
+
+        let mut sent_ids = Vec::new();
+        for nonce in 0..20u64 {
+            send_message(
+                &mut builder,
+                contract,
+                DST_CHAIN_ID,
+                receiver.clone(),
+                payload.clone(),
+            );
+
+            let message = build_message_bytes(
+                CASPER_CHAIN_ID,
+                DST_CHAIN_ID,
+                &DEFAULT_ACCOUNT_ADDR.value(),
+                receiver.as_ref(),
+                nonce,
+                payload.as_ref(),
+            );
+            sent_ids.push(message_key(&message));
+        }
+
+        let recent: Vec<String> = call_getter(
+            &mut builder,
+            contract,
+            "get_recent_message_ids",
+            runtime_args! {},
+        );
+
+        let expected: Vec<String> = sent_ids.into_iter().rev().take(16).collect();
+        assert_eq!(recent, expected);
+    }
+
+    #[test]
+    fn execute_message_reverts_on_malformed_receiver() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+
+        let receiver = Bytes::from(vec![7u8; 31]);
+        let src_gateway = Bytes::from(vec![9u8; 32]);
+        let payload = Bytes::from(vec![1u8]);
+
+        let message = build_message_bytes(
+            1,
+            CASPER_CHAIN_ID,
+            src_gateway.as_ref(),
+            receiver.as_ref(),
+            0,
+            payload.as_ref(),
+        );
+        let call = execute_message_request(
+            gateway,
+            1,
+            src_gateway,
+            receiver,
+            0,
+            payload,
+            sign_with(&signing_key, &message),
+        );
+        builder.exec(call).commit().expect_failure();
+        assert_user_error(&builder, 3);
+    }
+
+    #[test]
+    fn are_chains_supported_aligns_with_input() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+        set_supported_chain(&mut builder, contract, 1, true);
+        set_supported_chain(&mut builder, contract, 3, true);
+
+        let flags: Vec<bool> = call_getter(
+            &mut builder,
+            contract,
+            "are_chains_supported",
+            runtime_args! { "chain_ids" => vec![1u32, 2, 3, 4] },
+        );
+
+        assert_eq!(flags, vec![true, false, true, false]);
+
+        // Deprecated chains take no new sends, so they read as unsupported.
+        let deprecate = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "set_chain_status",
+            runtime_args! { "chain_id" => 3u32, "status" => 2u8 },
+        )
+        .build();
+        builder.exec(deprecate).commit().expect_success();
+
+        let flags: Vec<bool> = call_getter(
+            &mut builder,
+            contract,
+            "are_chains_supported",
+            runtime_args! { "chain_ids" => vec![1u32, 3] },
+        );
+        assert_eq!(flags, vec![true, false]);
+    }
+
+    #[test]
+    fn install_twice_under_distinct_key_names() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        for name in ["gateway_a", "gateway_b"] {
+            let install = ExecuteRequestBuilder::standard(
+                *DEFAULT_ACCOUNT_ADDR,
+                CONTRACT_WASM,
+                runtime_args! {
+                    "relayer_pubkey" => unused_relayer_key(),
+                    "contract_key_name" => name.to_string(),
+                },
+            )
+            .build();
+            builder.exec(install).commit().expect_success();
+        }
+
+        for name in ["receiver_a", "receiver_b"] {
+            let install = ExecuteRequestBuilder::standard(
+                *DEFAULT_ACCOUNT_ADDR,
+                RECEIVER_WASM,
+                runtime_args! { "contract_key_name" => name.to_string() },
+            )
+            .build();
+            builder.exec(install).commit().expect_success();
+        }
+
+        let gateway_a = named_contract(&builder, "gateway_a");
+        let gateway_b = named_contract(&builder, "gateway_b");
+        assert_ne!(gateway_a, gateway_b);
+
+        let receiver_a = named_contract(&builder, "receiver_a");
+        let receiver_b = named_contract(&builder, "receiver_b");
+        assert_ne!(receiver_a, receiver_b);
+    }
+
+    #[test]
+    fn get_message_sender_returns_caller() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+        set_supported_chain(&mut builder, contract, DST_CHAIN_ID, true);
+
+        let receiver = Bytes::from(vec![9u8; 32]);
+        let payload = Bytes::from(vec![1u8]);
+
+        send_message(
+            &mut builder,
+            contract,
+            DST_CHAIN_ID,
+            receiver.clone(),
+            payload.clone(),
+        );
+
+        let message = build_message_bytes(
+            CASPER_CHAIN_ID,
+            DST_CHAIN_ID,
+            &DEFAULT_ACCOUNT_ADDR.value(),
+            receiver.as_ref(),
+            0,
+            payload.as_ref(),
+        );
+
+        let sender: AccountHash = call_getter(
+            &mut builder,
+            contract,
+            "get_message_sender",
+            runtime_args! { "message_id" => message_key(&message) },
+        );
+        assert_eq!(sender, *DEFAULT_ACCOUNT_ADDR);
+
+        let call = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "get_message_sender",
+            runtime_args! { "message_id" => "missing".to_string() },
+        )
+        .build();
+        builder.exec(call).commit().expect_failure();
+        assert_user_error(&builder, 7);
+    }
+
+    #[test]
+    fn execute_message_strict_mode_requires_supported_source() {
+        for strict in [true, false] {
+            let mut builder = LmdbWasmTestBuilder::default();
+            builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+            let receiver_contract = install_receiver(&mut builder);
+            let receiver = Bytes::from(receiver_contract.value().to_vec());
+            let src_gateway = Bytes::from(vec![9u8; 32]);
+            let payload = Bytes::from(vec![1u8]);
+
+            let signing_key = SigningKey::random(&mut OsRng);
+            let gateway = install_with_args(
+                &mut builder,
+                runtime_args! {
+                    "relayer_pubkey" => relayer_key(&signing_key),
+                    "strict_source_chains" => strict,
+                },
+            );
+
+            let message = build_message_bytes(
+                1,
+                CASPER_CHAIN_ID,
+                src_gateway.as_ref(),
+                receiver.as_ref(),
+                0,
+                payload.as_ref(),
+            );
+            let call = execute_message_request(
+                gateway,
+                1,
+                src_gateway,
+                receiver,
+                0,
+                payload,
+                sign_with(&signing_key, &message),
+            );
+
+            if strict {
+                builder.exec(call).commit().expect_failure();
+                assert_user_error(&builder, 1);
+            } else {
+                builder.exec(call).commit().expect_success();
+            }
+        }
+    }
+
+    #[test]
+    fn read_entry_points_do_not_write_state() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let receiver_contract = install_receiver(&mut builder);
+        let receiver = Bytes::from(receiver_contract.value().to_vec());
+        let src_gateway = Bytes::from(vec![9u8; 32]);
+        let payload = Bytes::from(vec![1u8]);
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let contract = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+        set_supported_chain(&mut builder, contract, DST_CHAIN_ID, true);
+
+        let trust = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "set_trusted_gateway",
+            runtime_args! {
+                "src_chain_id" => 1u32,
+                "gateway" => src_gateway.clone(),
+            },
+        )
+        .build();
+        builder.exec(trust).commit().expect_success();
+
+        // Sanity check: a state-changing call does move the root.
+        let before = builder.get_post_state_hash();
+        send_message(
+            &mut builder,
+            contract,
+            DST_CHAIN_ID,
+            receiver.clone(),
+            payload.clone(),
+        );
+        assert_ne!(before, builder.get_post_state_hash());
+
+        let inbound = build_message_bytes(
+            1,
+            CASPER_CHAIN_ID,
+            src_gateway.as_ref(),
+            receiver.as_ref(),
+            0,
+            payload.as_ref(),
+        );
+        let call = execute_message_request(
+            contract,
+            1,
+            src_gateway,
+            receiver.clone(),
+            0,
+            payload.clone(),
+            sign_with(&signing_key, &inbound),
+        );
+        builder.exec(call).commit().expect_success();
+
+        // Leaves an error event to read back.
+        let soft_failed_send = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "send_message",
+            runtime_args! {
+                "dst_chain_id" => 999u32,
+                "receiver" => receiver.clone(),
+                "payload" => payload.clone(),
+                "soft_fail" => true,
+            },
+        )
+        .build();
+        builder.exec(soft_failed_send).commit().expect_success();
+
+        let outbound = build_message_bytes(
+            CASPER_CHAIN_ID,
+            DST_CHAIN_ID,
+            &DEFAULT_ACCOUNT_ADDR.value(),
+            receiver.as_ref(),
+            0,
+            payload.as_ref(),
+        );
+
+        let reads = [
+            ("get_nonce", runtime_args! {}),
+            (
+                "get_chain_nonce",
+                runtime_args! { "chain_id" => DST_CHAIN_ID },
+            ),
+            (
+                "is_executed",
+                runtime_args! { "message_id" => message_key(&inbound) },
+            ),
+            ("get_message", runtime_args! { "nonce" => 0u64 }),
+            ("get_message_payload", runtime_args! { "nonce" => 0u64 }),
+            (
+                "get_dict_uref",
+                runtime_args! { "name" => KEY_MESSAGES.to_string() },
+            ),
+            (
+                "describe_named_key",
+                runtime_args! { "name" => KEY_NONCE.to_string() },
+            ),
+            (
+                "get_dead_letters",
+                runtime_args! { "offset" => 0u64, "limit" => 10u32 },
+            ),
+            ("get_recent_message_ids", runtime_args! {}),
+            ("get_build_info", runtime_args! {}),
+            ("layout_selftest", runtime_args! {}),
+            ("describe_error", runtime_args! { "code" => 2u32 }),
+            (
+                "preview_delivery",
+                runtime_args! {
+                    "receiver" => Bytes::from(vec![5u8; 32]),
+                    "payload" => Bytes::from(vec![1u8]),
+                },
+            ),
+            ("get_stats", runtime_args! {}),
+            ("get_nonce_range", runtime_args! {}),
+            ("get_chain_traffic", runtime_args! { "chain_id" => 1u32 }),
+            (
+                "get_message_id_by_nonce",
+                runtime_args! { "dst_chain_id" => DST_CHAIN_ID, "nonce" => 0u64 },
+            ),
+            ("get_relayers", runtime_args! {}),
+            ("get_supported_chains", runtime_args! {}),
+            ("get_self_identity", runtime_args! {}),
+            ("get_protocol_version", runtime_args! {}),
+            ("get_storage_stats", runtime_args! {}),
+            (
+                "get_messages_meta",
+                runtime_args! { "message_ids" => vec![message_key(&outbound)] },
+            ),
+            (
+                "build_message",
+                runtime_args! {
+                    "src_chain_id" => 1u32,
+                    "dst_chain_id" => CASPER_CHAIN_ID,
+                    "src_gateway" => Bytes::from(vec![9u8; 32]),
+                    "receiver" => Bytes::from(vec![5u8; 32]),
+                    "nonce" => 0u64,
+                    "payload" => Bytes::new(),
+                },
+            ),
+            (
+                "verify_message_id",
+                runtime_args! {
+                    "message_id" => message_key(&inbound),
+                    "src_chain_id" => 1u32,
+                    "dst_chain_id" => CASPER_CHAIN_ID,
+                    "src_gateway" => Bytes::from(vec![9u8; 32]),
+                    "receiver" => Bytes::from(vec![5u8; 32]),
+                    "nonce" => 0u64,
+                    "payload" => Bytes::new(),
+                },
+            ),
+            ("get_chain_config", runtime_args! { "chain_id" => 1u32 }),
+            ("is_chain_ordered", runtime_args! { "chain_id" => 1u32 }),
+            ("get_threshold", runtime_args! {}),
+            ("get_max_payload_len", runtime_args! {}),
+            ("get_error_event", runtime_args! { "index" => 0u64 }),
+            (
+                "estimate_delivery_weight",
+                runtime_args! { "payload_len" => 64u32, "receiver_count" => 1u32 },
+            ),
+            ("export_chain_configs", runtime_args! {}),
+            ("get_relayer_count", runtime_args! {}),
+            ("get_admin_nonce", runtime_args! {}),
+            ("get_reward_balance", runtime_args! {}),
+            (
+                "get_delivery_attempts",
+                runtime_args! { "message_id" => message_key(&inbound) },
+            ),
+            (
+                "get_trusted_gateway",
+                runtime_args! { "src_chain_id" => 1u32 },
+            ),
+            (
+                "are_chains_supported",
+                runtime_args! { "chain_ids" => vec![DST_CHAIN_ID] },
+            ),
+            (
+                "are_executed",
+                runtime_args! { "message_ids" => vec![message_key(&inbound)] },
+            ),
+            (
+                "get_execution_record",
+                runtime_args! { "message_id" => message_key(&inbound) },
+            ),
+            (
+                "get_message_sender",
+                runtime_args! { "message_id" => message_key(&outbound) },
+            ),
+        ];
+
+        for (entry_point, args) in reads {
+            let before = builder.get_post_state_hash();
+
+            let call = ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                contract.into(),
+                entry_point,
+                args,
+            )
+            .build();
+            builder.exec(call).commit().expect_success();
+
+            assert_eq!(
+                before,
+                builder.get_post_state_hash(),
+                "{} wrote to global state",
+                entry_point
+            );
+        }
+    }
+
+    #[test]
+    fn read_entry_points_return_stored_state() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+        set_supported_chain(&mut builder, contract, DST_CHAIN_ID, true);
+
+        let receiver = Bytes::from(vec![9u8; 32]);
+        let payload = Bytes::from(vec![1u8, 2, 3]);
+
+        send_message(
+            &mut builder,
+            contract,
+            DST_CHAIN_ID,
+            receiver.clone(),
+            payload.clone(),
+        );
+
+        let nonce: u64 = call_getter(&mut builder, contract, "get_nonce", runtime_args! {});
+        assert_eq!(nonce, 1);
+
+        let message: Bytes = call_getter(
+            &mut builder,
+            contract,
+            "get_message",
+            runtime_args! { "nonce" => 0u64 },
+        );
+        let expected = build_message_bytes(
+            CASPER_CHAIN_ID,
+            DST_CHAIN_ID,
+            &DEFAULT_ACCOUNT_ADDR.value(),
+            receiver.as_ref(),
+            0,
+            payload.as_ref(),
+        );
+        assert_eq!(message.as_ref(), expected);
+
+        let executed: bool = call_getter(
+            &mut builder,
+            contract,
+            "is_executed",
+            runtime_args! { "message_id" => message_key(&expected) },
+        );
+        assert!(!executed);
+    }
+
+    #[test]
+    fn send_message_uses_per_chain_nonces() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+        set_supported_chain(&mut builder, contract, 1, true);
+        set_supported_chain(&mut builder, contract, 2, true);
+
+        let receiver = Bytes::from(vec![9u8; 32]);
+        let payload = Bytes::from(vec![1u8]);
+
+        for chain_id in [1u32, 2, 1] {
+            send_message(
+                &mut builder,
+                contract,
+                chain_id,
+                receiver.clone(),
+                payload.clone(),
+            );
+        }
+
+        let chain_1: u64 = call_getter(
+            &mut builder,
+            contract,
+            "get_chain_nonce",
+            runtime_args! { "chain_id" => 1u32 },
+        );
+        let chain_2: u64 = call_getter(
+            &mut builder,
+            contract,
+            "get_chain_nonce",
+            runtime_args! { "chain_id" => 2u32 },
+        );
+        assert_eq!(chain_1, 2);
+        assert_eq!(chain_2, 1);
+
+        let stored_nonce: Option<u64> = dictionary_item(&builder, contract, KEY_CHAIN_NONCES, "1");
+        assert_eq!(stored_nonce, Some(2));
+
+        // Third send overall, second to chain 1: its layout carries nonce 1.
+        let third: Option<Bytes> = dictionary_item(&builder, contract, KEY_MESSAGES, "2");
+        let expected = build_message_bytes(
+            CASPER_CHAIN_ID,
+            1,
+            &DEFAULT_ACCOUNT_ADDR.value(),
+            receiver.as_ref(),
+            1,
+            payload.as_ref(),
+        );
+        assert_eq!(third.as_deref(), Some(expected.as_slice()));
+    }
+
+    #[test]
+    fn get_build_info_reports_version() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+
+        let info: BuildInfo =
+            call_getter(&mut builder, contract, "get_build_info", runtime_args! {});
+
+        assert!(!info.version.is_empty());
+        assert_eq!(info.version, CONTRACT_VERSION);
+        assert!(!info.git_commit.is_empty());
+    }
+
+    #[test]
+    fn execute_message_replay_is_keyed_by_source_not_layout() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let receiver_contract = install_receiver(&mut builder);
+        let receiver = Bytes::from(receiver_contract.value().to_vec());
+        let src_gateway = Bytes::from(vec![9u8; 32]);
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+
+        // Same (src chain, gateway, nonce), different bytes and therefore a
+        // different message id: what a layout change would produce.
+        let mut ids = Vec::new();
+        for (payload, expect_success) in [(vec![1u8], true), (vec![1u8, 0], false)] {
+            let payload = Bytes::from(payload);
+            let message = build_message_bytes(
+                1,
+                CASPER_CHAIN_ID,
+                src_gateway.as_ref(),
+                receiver.as_ref(),
+                0,
+                payload.as_ref(),
+            );
+            ids.push(message_key(&message));
+
+            let call = execute_message_request(
+                gateway,
+                1,
+                src_gateway.clone(),
+                receiver.clone(),
+                0,
+                payload,
+                sign_with(&signing_key, &message),
+            );
+            if expect_success {
+                builder.exec(call).commit().expect_success();
+            } else {
+                builder.exec(call).commit().expect_failure();
+                assert_user_error(&builder, 2);
+            }
+        }
+        assert_ne!(ids[0], ids[1]);
+    }
+
+    #[test]
+    fn execute_message_calls_pinned_receiver_version() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        // Installing twice adds version 2 to the same package.
+        let version_1 = install_receiver(&mut builder);
+        let version_2 = install_receiver(&mut builder);
+        assert_ne!(version_1, version_2);
+
+        let package = named_contract(&builder, "mock_receiver_package");
+        let receiver = Bytes::from(package.value().to_vec());
+        let src_gateway = Bytes::from(vec![9u8; 32]);
+        let payload = Bytes::from(vec![1u8]);
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+
+        let deliver = |builder: &mut LmdbWasmTestBuilder, version: u32, nonce: u64| {
+            let pin = ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "set_receiver_version",
+                runtime_args! {
+                    "receiver" => receiver.clone(),
+                    "version" => version,
+                },
+            )
+            .build();
+            builder.exec(pin).commit().expect_success();
+
+            let message = build_message_bytes(
+                1,
+                CASPER_CHAIN_ID,
+                src_gateway.as_ref(),
+                receiver.as_ref(),
+                nonce,
+                payload.as_ref(),
+            );
+            let call = execute_message_request(
+                gateway,
+                1,
+                src_gateway.clone(),
+                receiver.clone(),
+                nonce,
+                payload.clone(),
+                sign_with(&signing_key, &message),
+            );
+            builder.exec(call).commit();
+        };
+
+        deliver(&mut builder, 1, 0);
+        builder.expect_success();
+
+        let version_count = |builder: &LmdbWasmTestBuilder, version: ContractHash| -> Option<u64> {
+            dictionary_item(
+                builder,
+                version_1,
+                "version_counts",
+                &version.to_formatted_string(),
+            )
+        };
+        assert_eq!(version_count(&builder, version_1), Some(1));
+        assert_eq!(version_count(&builder, version_2), None);
+
+        // A version the package does not have cannot be delivered to.
+        deliver(&mut builder, 7, 1);
+        builder.expect_failure();
+    }
+
+    #[test]
+    fn rotate_fee_purse_sweeps_balance_and_redirects_fees() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let fee = U512::from(1_000u64);
+        let contract = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => unused_relayer_key(),
+                "message_fee" => fee,
+            },
+        );
+        set_supported_chain(&mut builder, contract, DST_CHAIN_ID, true);
+
+        let paid_send = |builder: &mut LmdbWasmTestBuilder| {
+            let session = ExecuteRequestBuilder::standard(
+                *DEFAULT_ACCOUNT_ADDR,
+                SEND_WITH_FEE_WASM,
+                runtime_args! {
+                    "gateway" => contract,
+                    "amount" => fee,
+                    "dst_chain_id" => DST_CHAIN_ID,
+                    "receiver" => Bytes::from(vec![2u8; 32]),
+                    "payload" => Bytes::from(vec![]),
+                },
+            )
+            .build();
+            builder.exec(session).commit().expect_success();
+        };
+
+        // Without a purse to pay from, the send is refused.
+        let unpaid = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "send_message",
+            runtime_args! {
+                "dst_chain_id" => DST_CHAIN_ID,
+                "receiver" => Bytes::from(vec![2u8; 32]),
+                "payload" => Bytes::from(vec![]),
+            },
+        )
+        .build();
+        builder.exec(unpaid).commit().expect_failure();
+        assert_user_error(&builder, 9);
+
+        paid_send(&mut builder);
+
+        let old_purse = contract_uref(&builder, contract, "fee_purse");
+        assert_eq!(builder.get_purse_balance(old_purse), fee);
+
+        let new_purse: URef = call_getter(
+            &mut builder,
+            contract,
+            "rotate_fee_purse",
+            runtime_args! { "sweep" => true },
+        );
+        assert_ne!(new_purse.addr(), old_purse.addr());
+        assert_eq!(
+            contract_uref(&builder, contract, "fee_purse").addr(),
+            new_purse.addr()
+        );
+        assert_eq!(builder.get_purse_balance(old_purse), U512::zero());
+        assert_eq!(builder.get_purse_balance(new_purse), fee);
+
+        paid_send(&mut builder);
+        assert_eq!(builder.get_purse_balance(old_purse), U512::zero());
+        assert_eq!(builder.get_purse_balance(new_purse), fee * 2);
+    }
+
+    #[test]
+    fn rotate_fee_purse_is_owner_only() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+
+        let other = AccountHash::new([7u8; 32]);
+        let fund = TransferRequestBuilder::new(MINIMUM_ACCOUNT_CREATION_BALANCE, other).build();
+        builder.transfer_and_commit(fund).expect_success();
+
+        let call = ExecuteRequestBuilder::contract_call_by_hash(
+            other,
+            contract.into(),
+            "rotate_fee_purse",
+            runtime_args! { "sweep" => false },
+        )
+        .build();
+        builder.exec(call).commit().expect_failure();
+        assert_user_error(&builder, 8);
+    }
+
+    #[test]
+    fn execute_message_fanout_over_cap_reverts_before_delivery() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let receivers: Vec<ContractHash> = (0..4)
+            .map(|i| {
+                let name = format!("receiver_{}", i);
+                let install = ExecuteRequestBuilder::standard(
+                    *DEFAULT_ACCOUNT_ADDR,
+                    RECEIVER_WASM,
+                    runtime_args! { "contract_key_name" => name.clone() },
+                )
+                .build();
+                builder.exec(install).commit().expect_success();
+                named_contract(&builder, &name)
+            })
+            .collect();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => relayer_key(&signing_key),
+                "max_fanout" => 3u32,
+            },
+        );
+
+        let fan_out = |builder: &mut LmdbWasmTestBuilder, targets: &[ContractHash], nonce: u64| {
+            let addrs: Vec<[u8; 32]> = targets.iter().map(|hash| hash.value()).collect();
+            let commitment = Bytes::from(blake2b(addrs.concat()).to_vec());
+            let list: Vec<Bytes> = addrs
+                .iter()
+                .map(|addr| Bytes::from(addr.to_vec()))
+                .collect();
+
+            let message = build_message_bytes(
+                1,
+                CASPER_CHAIN_ID,
+                &[9u8; 32],
+                commitment.as_ref(),
+                nonce,
+                &[],
+            );
+
+            let call = ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "execute_message",
+                runtime_args! {
+                    "src_chain_id" => 1u32,
+                    "src_gateway" => Bytes::from(vec![9u8; 32]),
+                    "receiver" => commitment,
+                    "nonce" => nonce,
+                    "payload" => Bytes::from(vec![]),
+                    "signature" => sign_with(&signing_key, &message),
+                    "receivers" => list,
+                },
+            )
+            .build();
+            builder.exec(call).commit();
+        };
+
+        fan_out(&mut builder, &receivers, 0);
+        builder.expect_failure();
+        assert_user_error(&builder, 30);
+        for receiver in &receivers {
+            assert_eq!(receiver_count(&builder, *receiver), 0);
+        }
+
+        // Within the cap every receiver is called once.
+        fan_out(&mut builder, &receivers[..3], 0);
+        builder.expect_success();
+        for receiver in &receivers[..3] {
+            assert_eq!(receiver_count(&builder, *receiver), 1);
+        }
+        assert_eq!(receiver_count(&builder, receivers[3]), 0);
+    }
+
+    #[test]
+    fn set_supported_chain_cas_requires_expected_value() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+        set_supported_chain(&mut builder, contract, DST_CHAIN_ID, true);
+
+        let cas = |expected: bool| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                contract.into(),
+                "set_supported_chain_cas",
+                runtime_args! {
+                    "chain_id" => DST_CHAIN_ID,
+                    "expected" => expected,
+                    "supported" => false,
+                },
+            )
+            .build()
+        };
+
+        builder.exec(cas(false)).commit().expect_failure();
+        assert_user_error(&builder, 31);
+        assert_eq!(
+            dictionary_item::<bool>(&builder, contract, "supported_chains", "1"),
+            Some(true)
+        );
+
+        builder.exec(cas(true)).commit().expect_success();
+        assert_eq!(
+            dictionary_item::<bool>(&builder, contract, "supported_chains", "1"),
+            Some(false)
+        );
+
+        // A matching expectation is still not enough for anyone but the owner.
+        let other = AccountHash::new([7u8; 32]);
+        let fund = TransferRequestBuilder::new(MINIMUM_ACCOUNT_CREATION_BALANCE, other).build();
+        builder.transfer_and_commit(fund).expect_success();
+
+        let call = ExecuteRequestBuilder::contract_call_by_hash(
+            other,
+            contract.into(),
+            "set_supported_chain_cas",
+            runtime_args! {
+                "chain_id" => DST_CHAIN_ID,
+                "expected" => false,
+                "supported" => true,
+            },
+        )
+        .build();
+        builder.exec(call).commit().expect_failure();
+        assert_user_error(&builder, 8);
+    }
+
+    #[test]
+    fn request_refund_returns_fee_for_expired_unacked_message() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let fee = U512::from(1_000u64);
+        let contract = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => unused_relayer_key(),
+                "message_fee" => fee,
+            },
+        );
+        set_supported_chain(&mut builder, contract, DST_CHAIN_ID, true);
+
+        let deadline = 10_000u64;
+        let session = ExecuteRequestBuilder::standard(
+            *DEFAULT_ACCOUNT_ADDR,
+            SEND_WITH_FEE_WASM,
+            runtime_args! {
+                "gateway" => contract,
+                "amount" => fee,
+                "dst_chain_id" => DST_CHAIN_ID,
+                "receiver" => Bytes::from(vec![2u8; 32]),
+                "payload" => Bytes::from(vec![]),
+                "ttl_ms" => deadline,
+            },
+        )
+        .build();
+        builder.exec(session).commit().expect_success();
+
+        let ids: Vec<String> = call_getter(
+            &mut builder,
+            contract,
+            "get_recent_message_ids",
+            runtime_args! {},
+        );
+        let message_id = ids[0].clone();
+
+        let refund = |block_time: u64| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                contract.into(),
+                "request_refund",
+                runtime_args! { "message_id" => message_id.clone() },
+            )
+            .with_block_time(block_time)
+            .build()
+        };
+
+        // Still within the deadline.
+        builder.exec(refund(deadline)).commit().expect_failure();
+        assert_user_error(&builder, 10);
+
+        let fee_purse = contract_uref(&builder, contract, "fee_purse");
+        assert_eq!(builder.get_purse_balance(fee_purse), fee);
+
+        builder.exec(refund(deadline + 1)).commit().expect_success();
+        let refunded: U512 = builder
+            .get_last_exec_result()
+            .expect("exec result")
+            .ret()
+            .expect("return value")
+            .clone()
+            .into_t()
+            .expect("U512");
+        assert_eq!(refunded, fee);
+        assert_eq!(builder.get_purse_balance(fee_purse), U512::zero());
+
+        // A fee is refunded once.
+        builder.exec(refund(deadline + 2)).commit().expect_failure();
+        assert_user_error(&builder, 10);
+    }
+
+    #[test]
+    fn request_refund_rejects_acked_message() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let fee = U512::from(1_000u64);
+        let contract = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => unused_relayer_key(),
+                "message_fee" => fee,
+            },
+        );
+        set_supported_chain(&mut builder, contract, DST_CHAIN_ID, true);
+
+        let session = ExecuteRequestBuilder::standard(
+            *DEFAULT_ACCOUNT_ADDR,
+            SEND_WITH_FEE_WASM,
+            runtime_args! {
+                "gateway" => contract,
+                "amount" => fee,
+                "dst_chain_id" => DST_CHAIN_ID,
+                "receiver" => Bytes::from(vec![2u8; 32]),
+                "payload" => Bytes::from(vec![]),
+                "ttl_ms" => 1u64,
+            },
+        )
+        .build();
+        builder.exec(session).commit().expect_success();
+
+        let ids: Vec<String> = call_getter(
+            &mut builder,
+            contract,
+            "get_recent_message_ids",
+            runtime_args! {},
+        );
+
+        let ack = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "ack_message",
+            runtime_args! { "message_id" => ids[0].clone() },
+        )
+        .build();
+        builder.exec(ack).commit().expect_success();
+
+        let refund = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "request_refund",
+            runtime_args! { "message_id" => ids[0].clone() },
+        )
+        .with_block_time(1_000u64)
+        .build();
+        builder.exec(refund).commit().expect_failure();
+        assert_user_error(&builder, 10);
+    }
+
+    #[test]
+    fn soft_fail_records_error_event_instead_of_reverting() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let src_gateway = Bytes::from(vec![9u8; 32]);
+        let receiver = Bytes::from(vec![0u8; 32]);
+        let message = build_message_bytes(
+            1,
+            CASPER_CHAIN_ID,
+            src_gateway.as_ref(),
+            receiver.as_ref(),
+            0,
+            &[],
+        );
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+
+        let execute = |soft_fail: bool| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "execute_message",
+                runtime_args! {
+                    "src_chain_id" => 1u32,
+                    "src_gateway" => src_gateway.clone(),
+                    "receiver" => receiver.clone(),
+                    "nonce" => 0u64,
+                    "payload" => Bytes::from(vec![]),
+                    "signature" => sign_with(&signing_key, &message),
+                    "soft_fail" => soft_fail,
+                },
+            )
+            .build()
+        };
+
+        builder.exec(execute(false)).commit().expect_success();
+
+        // The replay still reverts unless the caller opts in.
+        builder.exec(execute(false)).commit().expect_failure();
+        assert_user_error(&builder, 2);
+        assert_eq!(
+            dictionary_item::<(u32, String)>(&builder, gateway, "error_events", "0"),
+            None
+        );
+
+        builder.exec(execute(true)).commit().expect_success();
+        assert_eq!(
+            dictionary_item::<(u32, String)>(&builder, gateway, "error_events", "0"),
+            Some((2, message_key(&message)))
+        );
+
+        let send = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "send_message",
+            runtime_args! {
+                "dst_chain_id" => 42u32,
+                "receiver" => Bytes::from(vec![2u8; 32]),
+                "payload" => Bytes::from(vec![]),
+                "soft_fail" => true,
+            },
+        )
+        .build();
+        builder.exec(send).commit().expect_success();
+        assert_eq!(
+            dictionary_item::<(u32, String)>(&builder, gateway, "error_events", "1"),
+            Some((1, "42".to_string()))
+        );
+        assert_eq!(
+            call_getter::<u64>(&mut builder, gateway, "get_nonce", runtime_args! {}),
+            0
+        );
+    }
+
+    #[test]
+    fn install_stores_typed_relayer_key() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let receiver = Bytes::from(vec![0u8; 32]);
+        let message =
+            build_message_bytes(1, CASPER_CHAIN_ID, &[9u8; 32], receiver.as_ref(), 0, &[]);
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let key = relayer_key(&signing_key);
+        let gateway = install_with_pubkey(&mut builder, key.clone());
+
+        let stored = builder
+            .query(
+                None,
+                Key::Hash(gateway.value()),
+                &["relayer_pubkey".to_string()],
+            )
+            .expect("relayer_pubkey named key")
+            .as_cl_value()
+            .expect("cl value")
+            .clone();
+        assert_eq!(*stored.cl_type(), CLType::PublicKey);
+        assert_eq!(stored.into_t::<PublicKey>().expect("public key"), key);
+
+        let call = execute_message_request(
+            gateway,
+            1,
+            Bytes::from(vec![9u8; 32]),
+            receiver,
+            0,
+            Bytes::from(vec![]),
+            sign_with(&signing_key, &message),
+        );
+        builder.exec(call).commit().expect_success();
+    }
+
+    #[test]
+    fn install_rejects_unsupported_relayer_key_type() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let install = ExecuteRequestBuilder::standard(
+            *DEFAULT_ACCOUNT_ADDR,
+            CONTRACT_WASM,
+            runtime_args! { "relayer_pubkey" => PublicKey::System },
+        )
+        .build();
+
+        builder.exec(install).commit().expect_failure();
+        assert_user_error(&builder, 5);
+    }
+
+    #[test]
+    fn execute_message_enforces_allowed_payload_versions() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let receiver_contract = install_receiver(&mut builder);
+        let receiver = Bytes::from(receiver_contract.value().to_vec());
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+
+        let allow = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "set_allowed_payload_versions",
+            runtime_args! {
+                "receiver" => receiver.clone(),
+                "versions" => Bytes::from(vec![2u8, 3u8]),
+            },
+        )
+        .build();
+        builder.exec(allow).commit().expect_success();
+
+        for (nonce, payload_version, accepted) in [(0u64, 1u8, false), (1, 2, true)] {
+            let payload = Bytes::from(vec![payload_version, 0xaa]);
+            let message = build_message_bytes(
+                1,
+                CASPER_CHAIN_ID,
+                &[9u8; 32],
+                receiver.as_ref(),
+                nonce,
+                payload.as_ref(),
+            );
+            let call = execute_message_request(
+                gateway,
+                1,
+                Bytes::from(vec![9u8; 32]),
+                receiver.clone(),
+                nonce,
+                payload,
+                sign_with(&signing_key, &message),
+            );
+            builder.exec(call).commit();
+
+            if accepted {
+                builder.expect_success();
+            } else {
+                builder.expect_failure();
+                assert_user_error(&builder, 32);
+            }
+        }
+
+        assert_eq!(receiver_count(&builder, receiver_contract), 1);
+    }
+
+    #[test]
+    fn get_stats_aggregates_counters() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+
+        set_supported_chain(&mut builder, gateway, DST_CHAIN_ID, true);
+        set_supported_chain(&mut builder, gateway, 2, true);
+        set_supported_chain(&mut builder, gateway, 2, false);
+        set_supported_chain(&mut builder, gateway, DST_CHAIN_ID, true);
+
+        for _ in 0..2 {
+            send_message(
+                &mut builder,
+                gateway,
+                DST_CHAIN_ID,
+                Bytes::from(vec![2u8; 32]),
+                Bytes::from(vec![]),
+            );
+        }
+
+        let receiver = Bytes::from(vec![0u8; 32]);
+        let message =
+            build_message_bytes(1, CASPER_CHAIN_ID, &[9u8; 32], receiver.as_ref(), 0, &[]);
+        let call = execute_message_request(
+            gateway,
+            1,
+            Bytes::from(vec![9u8; 32]),
+            receiver,
+            0,
+            Bytes::from(vec![]),
+            sign_with(&signing_key, &message),
+        );
+        builder.exec(call).commit().expect_success();
+
+        let stats: Stats = call_getter(&mut builder, gateway, "get_stats", runtime_args! {});
+        assert_eq!(
+            stats,
+            Stats {
+                sent: 2,
+                executed: 1,
+                nonce: 2,
+                supported_chains: 1,
+                paused: false,
+            }
+        );
+    }
+
+    #[test]
+    fn execute_message_rejects_oversized_payload_before_verifying() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => unused_relayer_key(),
+                "max_inbound_payload_len" => 4u32,
+            },
+        );
+
+        // The signature is garbage: the size check must fire first.
+        let call = execute_message_request(
+            gateway,
+            1,
+            Bytes::from(vec![9u8; 32]),
+            Bytes::from(vec![0u8; 32]),
+            0,
+            Bytes::from(vec![0u8; 5]),
+            Bytes::from(vec![0u8; 64]),
+        );
+        builder.exec(call).commit().expect_failure();
+        assert_user_error(&builder, 11);
+
+        // At the limit the payload passes the size check and fails on the signature.
+        let call = execute_message_request(
+            gateway,
+            1,
+            Bytes::from(vec![9u8; 32]),
+            Bytes::from(vec![0u8; 32]),
+            0,
+            Bytes::from(vec![0u8; 4]),
+            Bytes::from(vec![0u8; 64]),
+        );
+        builder.exec(call).commit().expect_failure();
+        assert_user_error(&builder, 5);
+    }
+
+    #[test]
+    fn send_message_uses_one_id_for_every_record() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+        set_supported_chain(&mut builder, contract, DST_CHAIN_ID, true);
+        send_message(
+            &mut builder,
+            contract,
+            DST_CHAIN_ID,
+            Bytes::from(vec![2u8; 32]),
+            Bytes::from(vec![1, 2, 3]),
+        );
+
+        let stored: Bytes =
+            dictionary_item(&builder, contract, KEY_MESSAGES, "0").expect("stored message");
+        let id = message_key(stored.as_ref());
+
+        let recent: Vec<String> = call_getter(
+            &mut builder,
+            contract,
+            "get_recent_message_ids",
+            runtime_args! {},
+        );
+        assert_eq!(recent, vec![id.clone()]);
+
+        let sender: Option<AccountHash> =
+            dictionary_item(&builder, contract, "message_senders", &id);
+        assert_eq!(sender, Some(*DEFAULT_ACCOUNT_ADDR));
+    }
+
+    #[test]
+    fn trusted_gateway_round_trips_and_gates_execution() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+
+        let trusted = Bytes::from(vec![9u8; 32]);
+        let set = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "set_trusted_gateway",
+            runtime_args! {
+                "src_chain_id" => 1u32,
+                "gateway" => trusted.clone(),
+            },
+        )
+        .build();
+        builder.exec(set).commit().expect_success();
+
+        let stored: Bytes = call_getter(
+            &mut builder,
+            gateway,
+            "get_trusted_gateway",
+            runtime_args! { "src_chain_id" => 1u32 },
+        );
+        assert_eq!(stored, trusted);
+
+        let unset = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "get_trusted_gateway",
+            runtime_args! { "src_chain_id" => 2u32 },
+        )
+        .build();
+        builder.exec(unset).commit().expect_failure();
+        assert_user_error(&builder, 33);
+
+        // A correctly signed message from another gateway on chain 1 is refused.
+        let receiver = Bytes::from(vec![0u8; 32]);
+        for (src_gateway, accepted) in [(vec![8u8; 32], false), (vec![9u8; 32], true)] {
+            let message =
+                build_message_bytes(1, CASPER_CHAIN_ID, &src_gateway, receiver.as_ref(), 0, &[]);
+            let call = execute_message_request(
+                gateway,
+                1,
+                Bytes::from(src_gateway),
+                receiver.clone(),
+                0,
+                Bytes::from(vec![]),
+                sign_with(&signing_key, &message),
+            );
+            builder.exec(call).commit();
+
+            if accepted {
+                builder.expect_success();
+            } else {
+                builder.expect_failure();
+                assert_user_error(&builder, 12);
+            }
+        }
+    }
+
+    #[test]
+    fn execute_message_fanout_calls_receivers_in_list_order() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let sequencer = install_receiver(&mut builder);
+
+        let mut install_sequenced = |name: &str| {
+            let install = ExecuteRequestBuilder::standard(
+                *DEFAULT_ACCOUNT_ADDR,
+                RECEIVER_WASM,
+                runtime_args! {
+                    "contract_key_name" => name.to_string(),
+                    "sequencer" => sequencer,
+                },
+            )
+            .build();
+            builder.exec(install).commit().expect_success();
+            named_contract(&builder, name)
+        };
+        let first = install_sequenced("receiver_first");
+        let second = install_sequenced("receiver_second");
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+
+        let last_sequence = |builder: &LmdbWasmTestBuilder, receiver: ContractHash| -> u64 {
+            builder
+                .query(
+                    None,
+                    Key::Hash(receiver.value()),
+                    &["last_sequence".to_string()],
+                )
+                .expect("last_sequence named key")
+                .as_cl_value()
+                .expect("cl value")
+                .clone()
+                .into_t()
+                .expect("u64")
+        };
+
+        for (nonce, order) in [(0u64, [second, first]), (1, [first, second])] {
+            let addrs: Vec<[u8; 32]> = order.iter().map(|hash| hash.value()).collect();
+            let commitment = Bytes::from(blake2b(addrs.concat()).to_vec());
+            let list: Vec<Bytes> = addrs
+                .iter()
+                .map(|addr| Bytes::from(addr.to_vec()))
+                .collect();
+
+            let message = build_message_bytes(
+                1,
+                CASPER_CHAIN_ID,
+                &[9u8; 32],
+                commitment.as_ref(),
+                nonce,
+                &[],
+            );
+            let call = ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "execute_message",
+                runtime_args! {
+                    "src_chain_id" => 1u32,
+                    "src_gateway" => Bytes::from(vec![9u8; 32]),
+                    "receiver" => commitment,
+                    "nonce" => nonce,
+                    "payload" => Bytes::from(vec![]),
+                    "signature" => sign_with(&signing_key, &message),
+                    "receivers" => list,
+                },
+            )
+            .build();
+            builder.exec(call).commit().expect_success();
+
+            // The first-listed receiver drew the lower sequence number.
+            assert_eq!(
+                last_sequence(&builder, order[0]) + 1,
+                last_sequence(&builder, order[1])
+            );
+        }
+
+        assert_eq!(last_sequence(&builder, first), 3);
+        assert_eq!(last_sequence(&builder, second), 4);
+    }
+
+    #[test]
+    fn signed_sends_mode_requires_relayer_signature() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => relayer_key(&signing_key),
+                "signed_sends" => true,
+            },
+        );
+        set_supported_chain(&mut builder, gateway, DST_CHAIN_ID, true);
+
+        let receiver = Bytes::from(vec![2u8; 32]);
+        let payload = Bytes::from(vec![1, 2, 3]);
+        let outbound = build_message_bytes(
+            CASPER_CHAIN_ID,
+            DST_CHAIN_ID,
+            &DEFAULT_ACCOUNT_ADDR.value(),
+            receiver.as_ref(),
+            0,
+            payload.as_ref(),
+        );
+
+        let send = |signature: Option<Bytes>| {
+            let mut args = runtime_args! {
+                "dst_chain_id" => DST_CHAIN_ID,
+                "receiver" => receiver.clone(),
+                "payload" => payload.clone(),
+            };
+            if let Some(signature) = signature {
+                args.insert("signature", signature).expect("signature arg");
+            }
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "send_message",
+                args,
+            )
+            .build()
+        };
+
+        builder.exec(send(None)).commit().expect_failure();
+        assert_user_error(&builder, 5);
+
+        // Signed by someone other than the relayer.
+        let stranger = SigningKey::random(&mut OsRng);
+        builder
+            .exec(send(Some(sign_with(&stranger, &outbound))))
+            .commit()
+            .expect_failure();
+        assert_user_error(&builder, 5);
+
+        let signature = sign_with(&signing_key, &outbound);
+        builder
+            .exec(send(Some(signature.clone())))
+            .commit()
+            .expect_success();
+        assert_eq!(
+            call_getter::<u64>(&mut builder, gateway, "get_nonce", runtime_args! {}),
+            1
+        );
+
+        // The nonce moved on, so the same signature does not authorize a second send.
+        builder
+            .exec(send(Some(signature)))
+            .commit()
+            .expect_failure();
+        assert_user_error(&builder, 5);
+    }
+
+    #[test]
+    fn pause_blocks_sends_and_drains_inbound_until_grace() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+        set_supported_chain(&mut builder, gateway, DST_CHAIN_ID, true);
+
+        let grace_until = 5_000u64;
+        let pause = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "pause",
+            runtime_args! { "grace_until" => grace_until },
+        )
+        .build();
+        builder.exec(pause).commit().expect_success();
+
+        let send = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "send_message",
+            runtime_args! {
+                "dst_chain_id" => DST_CHAIN_ID,
+                "receiver" => Bytes::from(vec![2u8; 32]),
+                "payload" => Bytes::from(vec![]),
+            },
+        )
+        .build();
+        builder.exec(send).commit().expect_failure();
+        assert_user_error(&builder, 13);
+
+        let receiver = Bytes::from(vec![0u8; 32]);
+        let deliver = |nonce: u64, block_time: u64| {
+            let message = build_message_bytes(
+                1,
+                CASPER_CHAIN_ID,
+                &[9u8; 32],
+                receiver.as_ref(),
+                nonce,
+                &[],
+            );
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "execute_message",
+                runtime_args! {
+                    "src_chain_id" => 1u32,
+                    "src_gateway" => Bytes::from(vec![9u8; 32]),
+                    "receiver" => receiver.clone(),
+                    "nonce" => nonce,
+                    "payload" => Bytes::from(vec![]),
+                    "signature" => sign_with(&signing_key, &message),
+                },
+            )
+            .with_block_time(block_time)
+            .build()
+        };
+
+        builder
+            .exec(deliver(0, grace_until))
+            .commit()
+            .expect_success();
+
+        builder
+            .exec(deliver(1, grace_until + 1))
+            .commit()
+            .expect_failure();
+        assert_user_error(&builder, 13);
+
+        let unpause = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "unpause",
+            runtime_args! {},
+        )
+        .build();
+        builder.exec(unpause).commit().expect_success();
+
+        builder
+            .exec(deliver(1, grace_until + 1))
+            .commit()
+            .expect_success();
+    }
+
+    #[test]
+    fn committee_threshold_and_count_are_readable() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let gateway = install(&mut builder);
+
+        // Without a committee the single relayer key is a 1-of-1.
+        assert_eq!(
+            call_getter::<u8>(&mut builder, gateway, "get_threshold", runtime_args! {}),
+            1
+        );
+        assert_eq!(
+            call_getter::<u32>(&mut builder, gateway, "get_relayer_count", runtime_args! {}),
+            1
+        );
+
+        let committee: Vec<SigningKey> = (0..3).map(|_| SigningKey::random(&mut OsRng)).collect();
+        set_committee(&mut builder, gateway, &committee, 2);
+
+        assert_eq!(
+            call_getter::<u8>(&mut builder, gateway, "get_threshold", runtime_args! {}),
+            2
+        );
+        assert_eq!(
+            call_getter::<u32>(&mut builder, gateway, "get_relayer_count", runtime_args! {}),
+            3
+        );
+    }
+
+    #[test]
+    fn committee_execute_requires_threshold_signatures() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let gateway = install(&mut builder);
+        let committee: Vec<SigningKey> = (0..3).map(|_| SigningKey::random(&mut OsRng)).collect();
+        set_committee(&mut builder, gateway, &committee, 2);
+
+        let receiver = Bytes::from(vec![0u8; 32]);
+        let message =
+            build_message_bytes(1, CASPER_CHAIN_ID, &[9u8; 32], receiver.as_ref(), 0, &[]);
+
+        let execute = |signers: &[usize]| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "execute_message",
+                runtime_args! {
+                    "src_chain_id" => 1u32,
+                    "src_gateway" => Bytes::from(vec![9u8; 32]),
+                    "receiver" => receiver.clone(),
+                    "nonce" => 0u64,
+                    "payload" => Bytes::from(vec![]),
+                    "signatures" => committee_signatures(&committee, signers, &message),
+                },
+            )
+            .build()
+        };
+
+        builder.exec(execute(&[1])).commit().expect_failure();
+        assert_user_error(&builder, 15);
+
+        builder.exec(execute(&[0, 2])).commit().expect_success();
+    }
+
+    #[test]
+    fn set_committee_rejects_bad_threshold() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let gateway = install(&mut builder);
+        let key = unused_relayer_key();
+
+        for (relayers, threshold) in [
+            (vec![key.clone()], 0u8),
+            (vec![key.clone()], 2),
+            (vec![key.clone(), key.clone()], 1),
+        ] {
+            let call = ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "set_committee",
+                runtime_args! {
+                    "relayers" => relayers,
+                    "threshold" => threshold,
+                },
+            )
+            .build();
+            builder.exec(call).commit().expect_failure();
+            assert_user_error(&builder, 14);
+        }
+    }
+
+    #[test]
+    fn execute_message_delivers_escrowed_value_to_receiver() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let receiver_contract = install_receiver(&mut builder);
+        let receiver = Bytes::from(receiver_contract.value().to_vec());
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let contract = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+        set_supported_chain(&mut builder, contract, DST_CHAIN_ID, true);
+
+        // 100 CSPR leaves with an outbound message and sits in escrow.
+        let value = U512::from(100_000_000_000u64);
+        let session = ExecuteRequestBuilder::standard(
+            *DEFAULT_ACCOUNT_ADDR,
+            SEND_WITH_FEE_WASM,
+            runtime_args! {
+                "gateway" => contract,
+                "amount" => value,
+                "value" => value,
+                "dst_chain_id" => DST_CHAIN_ID,
+                "receiver" => Bytes::from(vec![2u8; 32]),
+                "payload" => Bytes::from(vec![]),
+            },
+        )
+        .build();
+        builder.exec(session).commit().expect_success();
+
+        let escrow_purse = contract_uref(&builder, contract, "escrow_purse");
+        assert_eq!(builder.get_purse_balance(escrow_purse), value);
+
+        let execute = |nonce: u64| {
+            let payload = Bytes::from(vec![1, 2, 3]);
+            let message = build_message_bytes_with_amount(
+                1,
+                CASPER_CHAIN_ID,
+                &[9u8; 32],
+                receiver.as_ref(),
+                nonce,
+                value,
+                payload.as_ref(),
+            );
+
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                contract.into(),
+                "execute_message",
+                runtime_args! {
+                    "src_chain_id" => 1u32,
+                    "src_gateway" => Bytes::from(vec![9u8; 32]),
+                    "receiver" => receiver.clone(),
+                    "nonce" => nonce,
+                    "payload" => payload,
+                    "amount" => value,
+                    "signature" => sign_with(&signing_key, &message),
+                },
+            )
+            .build()
+        };
+
+        builder.exec(execute(0)).commit().expect_success();
+
+        let deposit_purse = contract_uref(&builder, receiver_contract, "deposit_purse");
+        assert_eq!(builder.get_purse_balance(deposit_purse), value);
+        assert_eq!(builder.get_purse_balance(escrow_purse), U512::zero());
+        assert_eq!(receiver_count(&builder, receiver_contract), 1);
+
+        // Escrow is spent; a second delivery cannot be paid for.
+        builder.exec(execute(1)).commit().expect_failure();
+        assert_user_error(&builder, 17);
+        assert_eq!(receiver_count(&builder, receiver_contract), 1);
+    }
+
+    #[test]
+    fn execute_message_counts_failed_delivery_attempts() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let receiver_contract = install_receiver(&mut builder);
+        let receiver = Bytes::from(receiver_contract.value().to_vec());
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+
+        // The receiver only takes version 2 payloads, so delivery fails.
+        let allow = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "set_allowed_payload_versions",
+            runtime_args! {
+                "receiver" => receiver.clone(),
+                "versions" => Bytes::from(vec![2u8]),
+            },
+        )
+        .build();
+        builder.exec(allow).commit().expect_success();
+
+        let payload = Bytes::from(vec![1u8, 0xaa]);
+        let message = build_message_bytes(
+            1,
+            CASPER_CHAIN_ID,
+            &[9u8; 32],
+            receiver.as_ref(),
+            0,
+            payload.as_ref(),
+        );
+        let message_id = message_key(&message);
+
+        for _ in 0..2 {
+            let call = ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "execute_message",
+                runtime_args! {
+                    "src_chain_id" => 1u32,
+                    "src_gateway" => Bytes::from(vec![9u8; 32]),
+                    "receiver" => receiver.clone(),
+                    "nonce" => 0u64,
+                    "payload" => payload.clone(),
+                    "signature" => sign_with(&signing_key, &message),
+                    "soft_fail" => true,
+                },
+            )
+            .build();
+            builder.exec(call).commit().expect_success();
+        }
+
+        assert_eq!(
+            call_getter::<u32>(
+                &mut builder,
+                gateway,
+                "get_delivery_attempts",
+                runtime_args! { "message_id" => message_id.clone() },
+            ),
+            2
+        );
+        assert!(!call_getter::<bool>(
+            &mut builder,
+            gateway,
+            "is_executed",
+            runtime_args! { "message_id" => message_id },
+        ));
+        assert_eq!(receiver_count(&builder, receiver_contract), 0);
+    }
+
+    #[test]
+    fn receiver_cannot_call_admin_methods_during_delivery() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+
+        let install = ExecuteRequestBuilder::standard(
+            *DEFAULT_ACCOUNT_ADDR,
+            RECEIVER_WASM,
+            runtime_args! {
+                "contract_key_name" => "hostile_receiver".to_string(),
+                "hostile_gateway" => gateway,
+            },
+        )
+        .build();
+        builder.exec(install).commit().expect_success();
+        let hostile = Bytes::from(
+            named_contract(&builder, "hostile_receiver")
+                .value()
+                .to_vec(),
+        );
+        let honest = Bytes::from(install_receiver(&mut builder).value().to_vec());
+
+        let execute = |receiver: &Bytes, nonce: u64| {
+            let message = build_message_bytes(
+                1,
+                CASPER_CHAIN_ID,
+                &[9u8; 32],
+                receiver.as_ref(),
+                nonce,
+                &[],
+            );
+            execute_message_request(
+                gateway,
+                1,
+                Bytes::from(vec![9u8; 32]),
+                receiver.clone(),
+                nonce,
+                Bytes::from(vec![]),
+                sign_with(&signing_key, &message),
+            )
+        };
+
+        builder.exec(execute(&hostile, 0)).commit().expect_failure();
+        assert_user_error(&builder, 18);
+        assert_eq!(
+            dictionary_item::<bool>(&builder, gateway, "supported_chains", "99"),
+            None
+        );
+
+        // The guard is lifted once a delivery returns.
+        builder.exec(execute(&honest, 1)).commit().expect_success();
+        set_supported_chain(&mut builder, gateway, 99, true);
+        assert_eq!(
+            dictionary_item::<bool>(&builder, gateway, "supported_chains", "99"),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn get_message_payload_returns_only_the_payload() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+        set_supported_chain(&mut builder, contract, DST_CHAIN_ID, true);
+
+        // A 20-byte EVM receiver: the header length varies with the receiver.
+        let payload = Bytes::from(b"hello world".to_vec());
+        send_message(
+            &mut builder,
+            contract,
+            DST_CHAIN_ID,
+            Bytes::from(vec![7u8; 20]),
+            payload.clone(),
+        );
+
+        let stored: Bytes = call_getter(
+            &mut builder,
+            contract,
+            "get_message_payload",
+            runtime_args! { "nonce" => 0u64 },
+        );
+        assert_eq!(stored, payload);
+
+        let missing = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "get_message_payload",
+            runtime_args! { "nonce" => 1u64 },
+        )
+        .build();
+        builder.exec(missing).commit().expect_failure();
+        assert_user_error(&builder, 7);
+    }
+
+    #[test]
+    fn chain_relayer_overrides_global_key_for_its_chain() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let global_key = SigningKey::random(&mut OsRng);
+        let chain_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_pubkey(&mut builder, relayer_key(&global_key));
+
+        let set_chain_relayer = |relayer: Option<PublicKey>| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "set_chain_relayer",
+                runtime_args! { "src_chain_id" => 1u32, "relayer" => relayer },
+            )
+            .build()
+        };
+        builder
+            .exec(set_chain_relayer(Some(relayer_key(&chain_key))))
+            .commit()
+            .expect_success();
+
+        let receiver = Bytes::from(vec![0u8; 32]);
+        let execute = |src_chain_id: u32, nonce: u64, signing_key: &SigningKey| {
+            let message = build_message_bytes(
+                src_chain_id,
+                CASPER_CHAIN_ID,
+                &[9u8; 32],
+                receiver.as_ref(),
+                nonce,
+                &[],
+            );
+            execute_message_request(
+                gateway,
+                src_chain_id,
+                Bytes::from(vec![9u8; 32]),
+                receiver.clone(),
+                nonce,
+                Bytes::from(vec![]),
+                sign_with(signing_key, &message),
+            )
+        };
+
+        // Chain 1 now only accepts its dedicated key.
+        builder
+            .exec(execute(1, 0, &global_key))
+            .commit()
+            .expect_failure();
+        assert_user_error(&builder, 5);
+        builder
+            .exec(execute(1, 0, &chain_key))
+            .commit()
+            .expect_success();
+
+        // Other chains keep the global key.
+        builder
+            .exec(execute(2, 0, &chain_key))
+            .commit()
+            .expect_failure();
+        assert_user_error(&builder, 5);
+        builder
+            .exec(execute(2, 0, &global_key))
+            .commit()
+            .expect_success();
+
+        // Clearing the override falls back to the global key.
+        builder
+            .exec(set_chain_relayer(None))
+            .commit()
+            .expect_success();
+        builder
+            .exec(execute(1, 1, &global_key))
+            .commit()
+            .expect_success();
+    }
+
+    #[test]
+    fn signed_admin_action_cannot_be_replayed() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let admin_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => unused_relayer_key(),
+                "admin_pubkey" => relayer_key(&admin_key),
+            },
+        );
+
+        // domain | admin_nonce | action length | action | params
+        let params = Bytes::from(0u64.to_be_bytes().to_vec());
+        let mut signed = b"knotx-admin-v1".to_vec();
+        signed.extend_from_slice(&0u64.to_be_bytes());
+        signed.extend_from_slice(&5u32.to_be_bytes());
+        signed.extend_from_slice(b"pause");
+        signed.extend_from_slice(params.as_ref());
+
+        let bundle = runtime_args! {
+            "action" => "pause".to_string(),
+            "params" => params,
+            "admin_nonce" => 0u64,
+            "signature" => sign_with(&admin_key, &signed),
+        };
+        let signed_action = || {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "signed_admin_action",
+                bundle.clone(),
+            )
+            .build()
+        };
+
+        builder.exec(signed_action()).commit().expect_success();
+        assert!(call_getter::<Stats>(&mut builder, gateway, "get_stats", runtime_args! {}).paused);
+        assert_eq!(
+            call_getter::<u64>(&mut builder, gateway, "get_admin_nonce", runtime_args! {}),
+            1
+        );
+
+        // Unpause directly, then replay the signed pause.
+        let unpause = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "unpause",
+            runtime_args! {},
+        )
+        .build();
+        builder.exec(unpause).commit().expect_success();
+
+        builder.exec(signed_action()).commit().expect_failure();
+        assert_user_error(&builder, 34);
+        assert!(!call_getter::<Stats>(&mut builder, gateway, "get_stats", runtime_args! {}).paused);
+    }
+
+    #[test]
+    fn execute_message_enforces_required_prefix() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let receiver_contract = install_receiver(&mut builder);
+        let receiver = Bytes::from(receiver_contract.value().to_vec());
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+
+        let require = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "set_required_prefix",
+            runtime_args! {
+                "receiver" => receiver.clone(),
+                "prefix" => Bytes::from(b"KNX1".to_vec()),
+            },
+        )
+        .build();
+        builder.exec(require).commit().expect_success();
+
+        for (nonce, payload, accepted) in [(0u64, &b"KNX1body"[..], true), (1, b"KNX2body", false)]
+        {
+            let message = build_message_bytes(
+                1,
+                CASPER_CHAIN_ID,
+                &[9u8; 32],
+                receiver.as_ref(),
+                nonce,
+                payload,
+            );
+            let call = execute_message_request(
+                gateway,
+                1,
+                Bytes::from(vec![9u8; 32]),
+                receiver.clone(),
+                nonce,
+                Bytes::from(payload.to_vec()),
+                sign_with(&signing_key, &message),
+            );
+            builder.exec(call).commit();
+
+            if accepted {
+                builder.expect_success();
+            } else {
+                builder.expect_failure();
+                assert_user_error(&builder, 35);
+            }
+        }
+
+        assert_eq!(receiver_count(&builder, receiver_contract), 1);
+    }
+
+    #[test]
+    fn are_executed_reports_each_id_in_order() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+
+        let receiver = Bytes::from(vec![0u8; 32]);
+        let messages: Vec<Vec<u8>> = (0..3u64)
+            .map(|nonce| {
+                build_message_bytes(
+                    1,
+                    CASPER_CHAIN_ID,
+                    &[9u8; 32],
+                    receiver.as_ref(),
+                    nonce,
+                    &[],
+                )
+            })
+            .collect();
+
+        let call = execute_message_request(
+            gateway,
+            1,
+            Bytes::from(vec![9u8; 32]),
+            receiver,
+            1,
+            Bytes::from(vec![]),
+            sign_with(&signing_key, &messages[1]),
+        );
+        builder.exec(call).commit().expect_success();
+
+        let flags: Vec<bool> = call_getter(
+            &mut builder,
+            gateway,
+            "are_executed",
+            runtime_args! {
+                "message_ids" => messages.iter().map(|m| message_key(m)).collect::<Vec<_>>(),
+            },
+        );
+        assert_eq!(flags, vec![false, true, false]);
+    }
+
+    #[test]
+    fn execute_message_pays_relayer_from_reward_purse() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let fee = U512::from(500u64);
+        let reward = U512::from(1_000u64);
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => relayer_key(&signing_key),
+                "message_fee" => fee,
+                "relayer_reward" => reward,
+            },
+        );
+        set_supported_chain(&mut builder, gateway, DST_CHAIN_ID, true);
+
+        let fund = ExecuteRequestBuilder::standard(
+            *DEFAULT_ACCOUNT_ADDR,
+            FUND_REWARDS_WASM,
+            runtime_args! {
+                "gateway" => gateway,
+                "amount" => U512::from(10_000u64),
+            },
+        )
+        .build();
+        builder.exec(fund).commit().expect_success();
+
+        let send = ExecuteRequestBuilder::standard(
+            *DEFAULT_ACCOUNT_ADDR,
+            SEND_WITH_FEE_WASM,
+            runtime_args! {
+                "gateway" => gateway,
+                "amount" => fee,
+                "dst_chain_id" => DST_CHAIN_ID,
+                "receiver" => Bytes::from(vec![2u8; 32]),
+                "payload" => Bytes::from(vec![]),
+            },
+        )
+        .build();
+        builder.exec(send).commit().expect_success();
+
+        let receiver = Bytes::from(vec![0u8; 32]);
+        let message =
+            build_message_bytes(1, CASPER_CHAIN_ID, &[9u8; 32], receiver.as_ref(), 0, &[]);
+        let call = execute_message_request(
+            gateway,
+            1,
+            Bytes::from(vec![9u8; 32]),
+            receiver,
+            0,
+            Bytes::from(vec![]),
+            sign_with(&signing_key, &message),
+        );
+        builder.exec(call).commit().expect_success();
+
+        assert_eq!(
+            call_getter::<U512>(
+                &mut builder,
+                gateway,
+                "get_reward_balance",
+                runtime_args! {}
+            ),
+            U512::from(9_000u64)
+        );
+        let fee_purse = contract_uref(&builder, gateway, "fee_purse");
+        assert_eq!(builder.get_purse_balance(fee_purse), fee);
+    }
+
+    #[test]
+    fn layout_selftest_matches_golden_vector() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let gateway = install(&mut builder);
+
+        // src | dst | src_gateway | receiver | nonce | amount | msg_type | sig_scheme | payload
+        let golden = concat!(
+            "00000001",
+            "00000003",
+            "1111111111111111111111111111111111111111111111111111111111111111",
+            "2222222222222222222222222222222222222222222222222222222222222222",
+            "0000000000000007",
+            "00000000000000000000000000000000000000000000000000000000000003e8",
+            "02",
+            "01",
+            "6b6e6f7478",
+        );
+
+        let (bytes, id): (Bytes, String) =
+            call_getter(&mut builder, gateway, "layout_selftest", runtime_args! {});
+        assert_eq!(hex::encode(bytes.as_ref()), golden);
+        assert_eq!(
+            id,
+            "9ccb65a1f4abfd34411a9dc13a69647cace68616293d2f18d7f76d8e295167ca"
+        );
+    }
+
+    #[test]
+    fn revoke_execution_only_within_dispute_window() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => relayer_key(&signing_key),
+                "dispute_window_ms" => 1_000u64,
+            },
+        );
+
+        let receiver = Bytes::from(install_receiver(&mut builder).value().to_vec());
+        let execute = |nonce: u64, payload: &[u8], block_time: u64| {
+            let message = build_message_bytes(
+                1,
+                CASPER_CHAIN_ID,
+                &[9u8; 32],
+                receiver.as_ref(),
+                nonce,
+                payload,
+            );
+            let call = ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "execute_message",
+                runtime_args! {
+                    "src_chain_id" => 1u32,
+                    "src_gateway" => Bytes::from(vec![9u8; 32]),
+                    "receiver" => receiver.clone(),
+                    "nonce" => nonce,
+                    "payload" => Bytes::from(payload.to_vec()),
+                    "signature" => sign_with(&signing_key, &message),
+                },
+            )
+            .with_block_time(block_time)
+            .build();
+            (call, message_key(&message))
+        };
+        let revoke = |message_id: &str, block_time: u64| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "revoke_execution",
+                runtime_args! { "message_id" => message_id.to_string() },
+            )
+            .with_block_time(block_time)
+            .build()
+        };
+
+        let (call, first) = execute(0, &[], 10_000);
+        builder.exec(call).commit().expect_success();
+        builder
+            .exec(revoke(&first, 10_500))
+            .commit()
+            .expect_success();
+        assert!(!call_getter::<bool>(
+            &mut builder,
+            gateway,
+            "is_executed",
+            runtime_args! { "message_id" => first },
+        ));
+        let traffic = |builder: &mut LmdbWasmTestBuilder| -> (u64, u64) {
+            call_getter(
+                builder,
+                gateway,
+                "get_chain_traffic",
+                runtime_args! { "chain_id" => 1u32 },
+            )
+        };
+        assert_eq!(traffic(&mut builder), (0, 0));
+
+        // The freed nonce takes a corrected message; its window runs out.
+        let (call, corrected) = execute(0, b"fixed", 20_000);
+        builder.exec(call).commit().expect_success();
+        assert_eq!(traffic(&mut builder), (0, 1));
+        builder
+            .exec(revoke(&corrected, 21_001))
+            .commit()
+            .expect_failure();
+        assert_user_error(&builder, 20);
+    }
+
+    #[test]
+    fn sender_rate_limit_is_per_account() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+        set_supported_chain(&mut builder, contract, DST_CHAIN_ID, true);
+
+        let limit = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "set_sender_rate_limit",
+            runtime_args! { "max_messages" => 2u32, "window_ms" => 60_000u64 },
+        )
+        .build();
+        builder.exec(limit).commit().expect_success();
+
+        let other = AccountHash::new([7u8; 32]);
+        let fund = TransferRequestBuilder::new(MINIMUM_ACCOUNT_CREATION_BALANCE, other).build();
+        builder.transfer_and_commit(fund).expect_success();
+
+        let send = |sender: AccountHash, block_time: u64| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                sender,
+                contract.into(),
+                "send_message",
+                runtime_args! {
+                    "dst_chain_id" => DST_CHAIN_ID,
+                    "receiver" => Bytes::from(vec![2u8; 32]),
+                    "payload" => Bytes::from(vec![]),
+                },
+            )
+            .with_block_time(block_time)
+            .build()
+        };
+
+        for block_time in [1_000u64, 2_000] {
+            builder
+                .exec(send(*DEFAULT_ACCOUNT_ADDR, block_time))
+                .commit()
+                .expect_success();
+        }
+        builder
+            .exec(send(*DEFAULT_ACCOUNT_ADDR, 3_000))
+            .commit()
+            .expect_failure();
+        assert_user_error(&builder, 21);
+
+        // Same window, different sender.
+        builder.exec(send(other, 3_000)).commit().expect_success();
+
+        // The next window starts a fresh quota.
+        builder
+            .exec(send(*DEFAULT_ACCOUNT_ADDR, 60_000))
+            .commit()
+            .expect_success();
+    }
+
+    #[test]
+    fn describe_error_names_error_codes() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let gateway = install(&mut builder);
+
+        for (code, name) in [
+            (2u32, "AlreadyExecuted"),
+            (35, "PrefixMismatch"),
+            (1_000, "Unknown"),
+            (70_000, "Unknown"),
+        ] {
+            let described: String = call_getter(
+                &mut builder,
+                gateway,
+                "describe_error",
+                runtime_args! { "code" => code },
+            );
+            assert_eq!(described, name);
+        }
+    }
+
+    #[test]
+    fn send_message_routes_chain_alias_to_canonical_id() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+        set_supported_chain(&mut builder, contract, DST_CHAIN_ID, true);
+
+        let alias = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "set_chain_alias",
+            runtime_args! { "alias" => 100u32, "chain_id" => DST_CHAIN_ID },
+        )
+        .build();
+        builder.exec(alias).commit().expect_success();
+
+        let receiver = Bytes::from(vec![2u8; 32]);
+        send_message(
+            &mut builder,
+            contract,
+            100,
+            receiver.clone(),
+            Bytes::from(vec![]),
+        );
+
+        let stored: Bytes = call_getter(
+            &mut builder,
+            contract,
+            "get_message",
+            runtime_args! { "nonce" => 0u64 },
+        );
+        let expected = build_message_bytes(
+            CASPER_CHAIN_ID,
+            DST_CHAIN_ID,
+            &DEFAULT_ACCOUNT_ADDR.value(),
+            receiver.as_ref(),
+            0,
+            &[],
+        );
+        assert_eq!(stored.as_ref(), expected.as_slice());
+        assert_eq!(
+            call_getter::<u64>(
+                &mut builder,
+                contract,
+                "get_chain_nonce",
+                runtime_args! { "chain_id" => DST_CHAIN_ID },
+            ),
+            1
+        );
+    }
+
+    #[test]
+    fn preview_delivery_describes_receiver_call() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let receiver_contract = install_receiver(&mut builder);
+        let gateway = install(&mut builder);
+
+        // The signature is not checked, so none is passed.
+        let calls: Vec<DeliveryCall> = call_getter(
+            &mut builder,
+            gateway,
+            "preview_delivery",
+            runtime_args! {
+                "src_chain_id" => 1u32,
+                "src_gateway" => Bytes::from(vec![9u8; 32]),
+                "receiver" => Bytes::from(receiver_contract.value().to_vec()),
+                "nonce" => 0u64,
+                "payload" => Bytes::from(vec![1, 2, 3]),
+            },
+        );
+        assert_eq!(
+            calls,
+            vec![DeliveryCall {
+                receiver: receiver_contract.value(),
+                version: None,
+                entry_point: "on_call".to_string(),
+                payload_len: 3,
+                amount: U512::zero(),
+            }]
+        );
+        assert_eq!(receiver_count(&builder, receiver_contract), 0);
+    }
+
+    #[test]
+    fn execution_record_matches_delivered_message() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! { "relayer_pubkey" => relayer_key(&signing_key) },
+        );
+
+        let receiver = Bytes::from(install_receiver(&mut builder).value().to_vec());
+        let src_gateway = Bytes::from(vec![9u8; 32]);
+        let message = build_message_bytes(
+            1,
+            CASPER_CHAIN_ID,
+            src_gateway.as_ref(),
+            receiver.as_ref(),
+            4,
+            &[],
+        );
+        let call = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "execute_message",
+            runtime_args! {
+                "src_chain_id" => 1u32,
+                "src_gateway" => src_gateway.clone(),
+                "receiver" => receiver,
+                "nonce" => 4u64,
+                "payload" => Bytes::new(),
+                "signature" => sign_with(&signing_key, &message),
+            },
+        )
+        .with_block_time(5_000)
+        .build();
+        builder.exec(call).commit().expect_success();
+
+        let record: ExecutionRecord = call_getter(
+            &mut builder,
+            gateway,
+            "get_execution_record",
+            runtime_args! { "message_id" => message_key(&message) },
+        );
+        assert_eq!(record.src_chain_id, 1);
+        assert_eq!(record.nonce, 4);
+        assert_eq!(record.src_gateway, src_gateway);
+        assert_eq!(record.executed_at, 5_000);
+
+        let unknown = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "get_execution_record",
+            runtime_args! { "message_id" => "00".repeat(32) },
+        )
+        .build();
+        builder.exec(unknown).commit().expect_failure();
+        assert_user_error(&builder, 7);
+    }
+
+    #[test]
+    fn reverting_receiver_is_redelivered_to_fallback() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! { "relayer_pubkey" => relayer_key(&signing_key) },
+        );
+
+        let install = ExecuteRequestBuilder::standard(
+            *DEFAULT_ACCOUNT_ADDR,
+            RECEIVER_WASM,
+            runtime_args! {
+                "contract_key_name" => "failing_receiver".to_string(),
+                "revert_on_call" => true,
+            },
+        )
+        .build();
+        builder.exec(install).commit().expect_success();
+        let primary = named_contract(&builder, "failing_receiver");
+        let fallback = install_receiver(&mut builder);
+
+        let call = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "set_fallback_receiver",
+            runtime_args! { "receiver" => Some(Bytes::from(fallback.value().to_vec())) },
+        )
+        .build();
+        builder.exec(call).commit().expect_success();
+
+        let primary_bytes = Bytes::from(primary.value().to_vec());
+        let message = build_message_bytes(
+            1,
+            CASPER_CHAIN_ID,
+            &[9u8; 32],
+            primary_bytes.as_ref(),
+            0,
+            &[],
+        );
+        let execute = |use_fallback: bool| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "execute_message",
+                runtime_args! {
+                    "src_chain_id" => 1u32,
+                    "src_gateway" => Bytes::from(vec![9u8; 32]),
+                    "receiver" => primary_bytes.clone(),
+                    "nonce" => 0u64,
+                    "payload" => Bytes::new(),
+                    "signature" => sign_with(&signing_key, &message),
+                    "use_fallback" => use_fallback,
+                },
+            )
+            .build()
+        };
+
+        // The primary's revert takes the whole delivery down with it.
+        builder.exec(execute(false)).commit().expect_failure();
+
+        builder.exec(execute(true)).commit().expect_success();
+        assert_eq!(receiver_count(&builder, fallback), 1);
+        assert_eq!(receiver_count(&builder, primary), 0);
+
+        let redirected: Option<Bytes> = dictionary_item(
+            &builder,
+            gateway,
+            "redirected_messages",
+            &message_key(&message),
+        );
+        assert_eq!(redirected, Some(primary_bytes));
+    }
+
+    #[test]
+    fn send_message_can_return_only_the_id() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => unused_relayer_key(),
+                "return_message_bytes" => false,
+            },
+        );
+        set_supported_chain(&mut builder, contract, DST_CHAIN_ID, true);
+
+        let returned: String = call_getter(
+            &mut builder,
+            contract,
+            "send_message",
+            runtime_args! {
+                "dst_chain_id" => DST_CHAIN_ID,
+                "receiver" => Bytes::from(vec![2u8; 32]),
+                "payload" => Bytes::from(vec![7u8; 256]),
+            },
+        );
+
+        let stored: Bytes =
+            dictionary_item(&builder, contract, KEY_MESSAGES, "0").expect("stored message");
+        assert_eq!(returned, message_key(stored.as_ref()));
+        assert_eq!(returned.len(), 64);
+    }
+
+    #[test]
+    fn wrongly_typed_dictionary_value_reverts_as_corrupt_state() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+
+        // Stand in for a faulty upgrade: a u64 where a bool belongs.
+        let supported_chains = contract_uref(&builder, contract, "supported_chains");
+        let chain_key = DST_CHAIN_ID.to_string();
+        let value = CLValueDictionary::new(
+            CLValue::from_t(7u64).unwrap(),
+            supported_chains.addr().to_vec(),
+            chain_key.as_bytes().to_vec(),
+        );
+        builder.write_data_and_commit(
+            [(
+                Key::dictionary(supported_chains, chain_key.as_bytes()),
+                StoredValue::CLValue(CLValue::from_t(value).unwrap()),
+            )]
+            .into_iter(),
+        );
+
+        let call = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "send_message",
+            runtime_args! {
+                "dst_chain_id" => DST_CHAIN_ID,
+                "receiver" => Bytes::from(vec![2u8; 32]),
+                "payload" => Bytes::from(vec![1u8]),
+            },
+        )
+        .build();
+        builder.exec(call).commit().expect_failure();
+        assert_user_error(&builder, 36);
+    }
+
+    #[test]
+    fn execute_message_rejects_oversized_gateway() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => unused_relayer_key(),
+                "max_gateway_len" => 64u32,
+            },
+        );
+
+        let execute = |src_gateway_len: usize| {
+            execute_message_request(
+                gateway,
+                1,
+                Bytes::from(vec![9u8; src_gateway_len]),
+                Bytes::from(vec![0u8; 32]),
+                0,
+                Bytes::new(),
+                Bytes::from(vec![0u8; 64]),
+            )
+        };
+
+        builder.exec(execute(1000)).commit().expect_failure();
+        assert_user_error(&builder, 37);
+
+        // At the cap the gateway passes and the bogus signature is what fails.
+        builder.exec(execute(64)).commit().expect_failure();
+        assert_user_error(&builder, 5);
+
+        // A trusted gateway over the cap could never match a message.
+        let trust = |gateway_len: usize| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "set_trusted_gateway",
+                runtime_args! {
+                    "src_chain_id" => 1u32,
+                    "gateway" => Bytes::from(vec![9u8; gateway_len]),
+                },
+            )
+            .build()
+        };
+
+        builder.exec(trust(65)).commit().expect_failure();
+        assert_user_error(&builder, 37);
+
+        builder.exec(trust(64)).commit().expect_success();
+    }
+
+    #[test]
+    fn nonce_range_spans_every_sent_message() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+        set_supported_chain(&mut builder, contract, DST_CHAIN_ID, true);
+
+        let range: (u64, u64) =
+            call_getter(&mut builder, contract, "get_nonce_range", runtime_args! {});
+        assert_eq!(range, (0, 0));
+
+        for _ in 0..3 {
+            send_message(
+                &mut builder,
+                contract,
+                DST_CHAIN_ID,
+                Bytes::from(vec![2u8; 32]),
+                Bytes::from(vec![1u8]),
+            );
+        }
+
+        let range: (u64, u64) =
+            call_getter(&mut builder, contract, "get_nonce_range", runtime_args! {});
+        assert_eq!(range, (0, 3));
+    }
+
+    #[test]
+    fn paused_receiver_is_skipped_by_delivery_only() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! { "relayer_pubkey" => relayer_key(&signing_key) },
+        );
+
+        let receivers: Vec<ContractHash> = ["receiver_paused", "receiver_live"]
+            .iter()
+            .map(|name| {
+                let install = ExecuteRequestBuilder::standard(
+                    *DEFAULT_ACCOUNT_ADDR,
+                    RECEIVER_WASM,
+                    runtime_args! { "contract_key_name" => name.to_string() },
+                )
+                .build();
+                builder.exec(install).commit().expect_success();
+                named_contract(&builder, name)
+            })
+            .collect();
+        let (paused, live) = (receivers[0], receivers[1]);
+
+        let call = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "pause_receiver",
+            runtime_args! { "receiver" => Bytes::from(paused.value().to_vec()) },
+        )
+        .build();
+        builder.exec(call).commit().expect_success();
+
+        let execute = |receiver: ContractHash, nonce: u64| {
+            let receiver = Bytes::from(receiver.value().to_vec());
+            let message = build_message_bytes(
+                1,
+                CASPER_CHAIN_ID,
+                &[9u8; 32],
+                receiver.as_ref(),
+                nonce,
+                &[],
+            );
+            execute_message_request(
+                gateway,
+                1,
+                Bytes::from(vec![9u8; 32]),
+                receiver,
+                nonce,
+                Bytes::new(),
+                sign_with(&signing_key, &message),
+            )
+        };
+
+        builder.exec(execute(paused, 0)).commit().expect_failure();
+        assert_user_error(&builder, 38);
+
+        builder.exec(execute(live, 1)).commit().expect_success();
+        assert_eq!(receiver_count(&builder, live), 1);
+
+        let call = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "unpause_receiver",
+            runtime_args! { "receiver" => Bytes::from(paused.value().to_vec()) },
+        )
+        .build();
+        builder.exec(call).commit().expect_success();
+
+        builder.exec(execute(paused, 0)).commit().expect_success();
+        assert_eq!(receiver_count(&builder, paused), 1);
+    }
+
+    #[test]
+    fn chain_ordering_flag_reads_back_and_gates_nonces() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! { "relayer_pubkey" => relayer_key(&signing_key) },
+        );
+
+        for (chain_id, ordered) in [(1u32, true), (2u32, false)] {
+            let call = ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "set_chain_ordered",
+                runtime_args! { "chain_id" => chain_id, "ordered" => ordered },
+            )
+            .build();
+            builder.exec(call).commit().expect_success();
+        }
+
+        for (chain_id, ordered) in [(1u32, true), (2u32, false)] {
+            let flag: bool = call_getter(
+                &mut builder,
+                gateway,
+                "is_chain_ordered",
+                runtime_args! { "chain_id" => chain_id },
+            );
+            assert_eq!(flag, ordered);
+
+            let config: ChainConfig = call_getter(
+                &mut builder,
+                gateway,
+                "get_chain_config",
+                runtime_args! { "chain_id" => chain_id },
+            );
+            assert_eq!(
+                config,
+                ChainConfig {
+                    status: 0,
+                    ordered,
+                    threshold: None,
+                    dst_gateway: None,
+                    required_sig_scheme: None,
+                    addr_len: None,
+                }
+            );
+        }
+
+        let execute = |chain_id: u32, nonce: u64| {
+            let message = build_message_bytes(
+                chain_id,
+                CASPER_CHAIN_ID,
+                &[9u8; 32],
+                &[0u8; 32],
+                nonce,
+                &[],
+            );
+            execute_message_request(
+                gateway,
+                chain_id,
+                Bytes::from(vec![9u8; 32]),
+                Bytes::from(vec![0u8; 32]),
+                nonce,
+                Bytes::new(),
+                sign_with(&signing_key, &message),
+            )
+        };
+
+        // Chain 1 must start at nonce 0; chain 2 takes any nonce.
+        builder.exec(execute(1, 1)).commit().expect_failure();
+        assert_user_error(&builder, 23);
+        builder.exec(execute(1, 0)).commit().expect_success();
+        builder.exec(execute(1, 1)).commit().expect_success();
+        builder.exec(execute(2, 5)).commit().expect_success();
+    }
+
+    #[test]
+    fn build_message_matches_local_encoder() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+
+        let built: Bytes = call_getter(
+            &mut builder,
+            contract,
+            "build_message",
+            runtime_args! {
+                "src_chain_id" => 1u32,
+                "dst_chain_id" => CASPER_CHAIN_ID,
+                "src_gateway" => Bytes::from(vec![0xabu8; 20]),
+                "receiver" => Bytes::from(vec![0x22u8; 32]),
+                "nonce" => 42u64,
+                "payload" => Bytes::from(b"hello".to_vec()),
+                "amount" => U512::from(5u64),
+            },
+        );
+
+        let expected = build_message_bytes_with_amount(
+            1,
+            CASPER_CHAIN_ID,
+            &[0xab; 20],
+            &[0x22; 32],
+            42,
+            U512::from(5u64),
+            b"hello",
+        );
+        assert_eq!(built.as_ref(), expected.as_slice());
+    }
+
+    #[test]
+    fn stale_signature_timestamp_is_rejected() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => relayer_key(&signing_key),
+                "max_sig_age_ms" => 60_000u64,
+            },
+        );
+
+        let execute = |nonce: u64, sig_timestamp: u64| {
+            let mut signed =
+                build_message_bytes(1, CASPER_CHAIN_ID, &[9u8; 32], &[0u8; 32], nonce, &[]);
+            signed.extend_from_slice(&sig_timestamp.to_be_bytes());
+
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "execute_message",
+                runtime_args! {
+                    "src_chain_id" => 1u32,
+                    "src_gateway" => Bytes::from(vec![9u8; 32]),
+                    "receiver" => Bytes::from(vec![0u8; 32]),
+                    "nonce" => nonce,
+                    "payload" => Bytes::new(),
+                    "signature" => sign_with(&signing_key, &signed),
+                    "sig_timestamp" => sig_timestamp,
+                },
+            )
+            .with_block_time(1_000_000)
+            .build()
+        };
+
+        builder.exec(execute(0, 990_000)).commit().expect_success();
+
+        builder.exec(execute(1, 900_000)).commit().expect_failure();
+        assert_user_error(&builder, 39);
+    }
+
+    #[test]
+    fn messages_meta_has_a_slot_per_requested_id() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+        set_supported_chain(&mut builder, contract, DST_CHAIN_ID, true);
+
+        let mut ids = Vec::new();
+        for (nonce, block_time) in [(0u64, 1_000u64), (1, 2_000)] {
+            let call = ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                contract.into(),
+                "send_message",
+                runtime_args! {
+                    "dst_chain_id" => DST_CHAIN_ID,
+                    "receiver" => Bytes::from(vec![2u8; 32]),
+                    "payload" => Bytes::from(vec![1u8]),
+                },
+            )
+            .with_block_time(block_time)
+            .build();
+            builder.exec(call).commit().expect_success();
+
+            let stored: Bytes =
+                dictionary_item(&builder, contract, KEY_MESSAGES, &nonce.to_string())
+                    .expect("stored message");
+            ids.push(message_key(stored.as_ref()));
+        }
+        ids.push("00".repeat(32));
+
+        let metas: Vec<Option<MessageMeta>> = call_getter(
+            &mut builder,
+            contract,
+            "get_messages_meta",
+            runtime_args! { "message_ids" => ids },
+        );
+
+        assert_eq!(
+            metas,
+            vec![
+                Some(MessageMeta {
+                    sender: *DEFAULT_ACCOUNT_ADDR,
+                    dst_chain_id: DST_CHAIN_ID,
+                    nonce: 0,
+                    timestamp: 1_000,
+                    deadline: 0,
+                }),
+                Some(MessageMeta {
+                    sender: *DEFAULT_ACCOUNT_ADDR,
+                    dst_chain_id: DST_CHAIN_ID,
+                    nonce: 1,
+                    timestamp: 2_000,
+                    deadline: 0,
+                }),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn expired_relayer_key_blocks_execution_until_rotated() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let old_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => relayer_key(&old_key),
+                "relayer_key_expiry" => 10_000u64,
+            },
+        );
+
+        let execute = |signing_key: &SigningKey, nonce: u64, block_time: u64| {
+            let message =
+                build_message_bytes(1, CASPER_CHAIN_ID, &[9u8; 32], &[0u8; 32], nonce, &[]);
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "execute_message",
+                runtime_args! {
+                    "src_chain_id" => 1u32,
+                    "src_gateway" => Bytes::from(vec![9u8; 32]),
+                    "receiver" => Bytes::from(vec![0u8; 32]),
+                    "nonce" => nonce,
+                    "payload" => Bytes::new(),
+                    "signature" => sign_with(signing_key, &message),
+                },
+            )
+            .with_block_time(block_time)
+            .build()
+        };
+
+        builder
+            .exec(execute(&old_key, 0, 9_999))
+            .commit()
+            .expect_success();
+
+        builder
+            .exec(execute(&old_key, 1, 10_000))
+            .commit()
+            .expect_failure();
+        assert_user_error(&builder, 40);
+
+        let new_key = SigningKey::random(&mut OsRng);
+        let rotate = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "set_relayer_key",
+            runtime_args! {
+                "relayer_pubkey" => relayer_key(&new_key),
+                "expiry" => 100_000u64,
+            },
+        )
+        .build();
+        builder.exec(rotate).commit().expect_success();
+
+        builder
+            .exec(execute(&new_key, 1, 20_000))
+            .commit()
+            .expect_success();
+    }
+
+    #[test]
+    fn msg_type_selects_the_mapped_receiver_handler() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! { "relayer_pubkey" => relayer_key(&signing_key) },
+        );
+        let receiver_contract = install_receiver(&mut builder);
+        let receiver = Bytes::from(receiver_contract.value().to_vec());
+
+        for (msg_type, entry_point) in [(1u8, "on_transfer"), (2u8, "on_message")] {
+            let call = ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "set_receiver_handler",
+                runtime_args! {
+                    "receiver" => receiver.clone(),
+                    "msg_type" => msg_type,
+                    "entry_point" => entry_point.to_string(),
+                },
+            )
+            .build();
+            builder.exec(call).commit().expect_success();
+        }
+
+        let execute = |nonce: u64, msg_type: u8| {
+            let message = build_typed_message_bytes(
+                1,
+                CASPER_CHAIN_ID,
+                &[9u8; 32],
+                receiver.as_ref(),
+                nonce,
+                U512::zero(),
+                msg_type,
+                0,
+                &[],
+            );
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "execute_message",
+                runtime_args! {
+                    "src_chain_id" => 1u32,
+                    "src_gateway" => Bytes::from(vec![9u8; 32]),
+                    "receiver" => receiver.clone(),
+                    "nonce" => nonce,
+                    "payload" => Bytes::new(),
+                    "signature" => sign_with(&signing_key, &message),
+                    "msg_type" => msg_type,
+                },
+            )
+            .build()
+        };
+        let last_handler = |builder: &LmdbWasmTestBuilder| -> String {
+            builder
+                .query(
+                    None,
+                    Key::Hash(receiver_contract.value()),
+                    &["last_handler".to_string()],
+                )
+                .expect("last_handler")
+                .as_cl_value()
+                .expect("cl value")
+                .clone()
+                .into_t()
+                .expect("string")
+        };
+
+        builder.exec(execute(0, 1)).commit().expect_success();
+        assert_eq!(last_handler(&builder), "on_transfer");
+        assert_eq!(receiver_count(&builder, receiver_contract), 0);
+
+        // Type 0 still reaches `on_call`.
+        builder.exec(execute(1, 0)).commit().expect_success();
+        assert_eq!(receiver_count(&builder, receiver_contract), 1);
+
+        builder.exec(execute(2, 7)).commit().expect_failure();
+        assert_user_error(&builder, 24);
+    }
+
+    #[test]
+    fn storage_stats_track_sent_and_pruned_messages() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+        set_supported_chain(&mut builder, contract, DST_CHAIN_ID, true);
+
+        for _ in 0..3 {
+            send_message(
+                &mut builder,
+                contract,
+                DST_CHAIN_ID,
+                Bytes::from(vec![2u8; 32]),
+                Bytes::from(vec![1u8]),
+            );
+        }
+
+        let stats: StorageStats = call_getter(
+            &mut builder,
+            contract,
+            "get_storage_stats",
+            runtime_args! {},
+        );
+        assert_eq!(
+            stats,
+            StorageStats {
+                messages: 3,
+                executed_messages: 0,
+                error_events: 0,
+            }
+        );
+
+        let prune = |up_to: u64| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                contract.into(),
+                "prune_messages",
+                runtime_args! { "up_to" => up_to },
+            )
+            .build()
+        };
+
+        builder.exec(prune(2)).commit().expect_success();
+        // Pruning again below the floor is a no-op.
+        builder.exec(prune(1)).commit().expect_success();
+
+        let stats: StorageStats = call_getter(
+            &mut builder,
+            contract,
+            "get_storage_stats",
+            runtime_args! {},
+        );
+        assert_eq!(stats.messages, 1);
+
+        let range: (u64, u64) =
+            call_getter(&mut builder, contract, "get_nonce_range", runtime_args! {});
+        assert_eq!(range, (2, 3));
+
+        let get_message = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "get_message",
+            runtime_args! { "nonce" => 1u64 },
+        )
+        .build();
+        builder.exec(get_message).commit().expect_failure();
+        assert_user_error(&builder, 7);
+
+        send_message(
+            &mut builder,
+            contract,
+            DST_CHAIN_ID,
+            Bytes::from(vec![2u8; 32]),
+            Bytes::from(vec![1u8]),
+        );
+        // The cap is the sent count, not `up_to`.
+        builder.exec(prune(100)).commit().expect_success();
+
+        let stats: StorageStats = call_getter(
+            &mut builder,
+            contract,
+            "get_storage_stats",
+            runtime_args! {},
+        );
+        assert_eq!(stats.messages, 0);
+        let range: (u64, u64) =
+            call_getter(&mut builder, contract, "get_nonce_range", runtime_args! {});
+        assert_eq!(range, (4, 4));
+    }
+
+    #[test]
+    fn execute_message_verifies_under_tagged_sig_scheme() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let secp_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! { "relayer_pubkey" => relayer_key(&secp_key) },
+        );
+
+        let ed25519_secret = SecretKey::ed25519_from_bytes([7u8; 32]).expect("key");
+        let ed25519_key = PublicKey::from(&ed25519_secret);
+        let ed25519_sign = |message: &[u8]| -> Bytes {
+            let signature = casper_types::crypto::sign(message, &ed25519_secret, &ed25519_key);
+            Bytes::from(Vec::<u8>::from(signature))
+        };
+
+        let receiver = Bytes::from(vec![0u8; 32]);
+        let message = |nonce: u64, sig_scheme: u8| {
+            build_typed_message_bytes(
+                1,
+                CASPER_CHAIN_ID,
+                &[9u8; 32],
+                receiver.as_ref(),
+                nonce,
+                U512::zero(),
+                0,
+                sig_scheme,
+                &[],
+            )
+        };
+        let execute = |nonce: u64, sig_scheme: u8, signature: Bytes| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "execute_message",
+                runtime_args! {
+                    "src_chain_id" => 1u32,
+                    "src_gateway" => Bytes::from(vec![9u8; 32]),
+                    "receiver" => receiver.clone(),
+                    "nonce" => nonce,
+                    "payload" => Bytes::new(),
+                    "signature" => signature,
+                    "sig_scheme" => sig_scheme,
+                },
+            )
+            .build()
+        };
+
+        let signature = sign_with(&secp_key, &message(0, 0));
+        builder
+            .exec(execute(0, 0, signature))
+            .commit()
+            .expect_success();
+
+        let rotate = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "set_relayer_key",
+            runtime_args! {
+                "relayer_pubkey" => ed25519_key.clone(),
+                "expiry" => 0u64,
+            },
+        )
+        .build();
+        builder.exec(rotate).commit().expect_success();
+
+        let signature = ed25519_sign(&message(1, 1));
+        builder
+            .exec(execute(1, 1, signature))
+            .commit()
+            .expect_success();
+
+        // An ed25519 signature tagged as secp256k1 does not verify.
+        let signature = ed25519_sign(&message(2, 0));
+        builder
+            .exec(execute(2, 0, signature))
+            .commit()
+            .expect_failure();
+        assert_user_error(&builder, 5);
+
+        let signature = ed25519_sign(&message(2, 9));
+        builder
+            .exec(execute(2, 9, signature))
+            .commit()
+            .expect_failure();
+        assert_user_error(&builder, 25);
+    }
+
+    #[test]
+    fn migrate_to_committee_seeds_committee_with_relayer_key() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let committee = [
+            SigningKey::random(&mut OsRng),
+            SigningKey::random(&mut OsRng),
+        ];
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! { "relayer_pubkey" => relayer_key(&committee[0]) },
+        );
+
+        let migrate = || {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "migrate_to_committee",
+                runtime_args! {},
+            )
+            .build()
+        };
+        builder.exec(migrate()).commit().expect_success();
+
+        let count: u32 = call_getter(&mut builder, gateway, "get_relayer_count", runtime_args! {});
+        assert_eq!(count, 1);
+        let threshold: u8 = call_getter(&mut builder, gateway, "get_threshold", runtime_args! {});
+        assert_eq!(threshold, 1);
+
+        // Only a single-key deployment can migrate.
+        builder.exec(migrate()).commit().expect_failure();
+
+        set_committee(&mut builder, gateway, &committee, 2);
+
+        let receiver = Bytes::from(vec![0u8; 32]);
+        let message =
+            build_message_bytes(1, CASPER_CHAIN_ID, &[9u8; 32], receiver.as_ref(), 0, &[]);
+        let execute = |signature_arg: &str, value: CLValue| {
+            let mut args = runtime_args! {
+                "src_chain_id" => 1u32,
+                "src_gateway" => Bytes::from(vec![9u8; 32]),
+                "receiver" => receiver.clone(),
+                "nonce" => 0u64,
+                "payload" => Bytes::new(),
+            };
+            args.insert_cl_value(signature_arg, value);
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "execute_message",
+                args,
+            )
+            .build()
+        };
+        let committee_call = |signers: &[usize]| {
+            let signatures = committee_signatures(&committee, signers, &message);
+            execute("signatures", CLValue::from_t(signatures).expect("cl value"))
+        };
+
+        // The old single-signature path no longer authorizes deliveries.
+        let signature = sign_with(&committee[0], &message);
+        builder
+            .exec(execute(
+                "signature",
+                CLValue::from_t(signature).expect("cl value"),
+            ))
+            .commit()
+            .expect_failure();
+        assert_user_error(&builder, 5);
+
+        builder.exec(committee_call(&[0])).commit().expect_failure();
+        assert_user_error(&builder, 15);
+
+        builder
+            .exec(committee_call(&[0, 1]))
+            .commit()
+            .expect_success();
+    }
+
+    #[test]
+    fn send_message_rejects_reused_idem_key() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => unused_relayer_key(),
+                "dedup_idem_keys" => true,
+            },
+        );
+        set_supported_chain(&mut builder, contract, DST_CHAIN_ID, true);
+
+        let send = |idem_key: &[u8]| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                contract.into(),
+                "send_message",
+                runtime_args! {
+                    "dst_chain_id" => DST_CHAIN_ID,
+                    "receiver" => Bytes::from(vec![2u8; 32]),
+                    "payload" => Bytes::from(vec![1u8]),
+                    "idem_key" => Bytes::from(idem_key.to_vec()),
+                },
+            )
+            .build()
+        };
+
+        builder.exec(send(b"order-1")).commit().expect_success();
+
+        builder.exec(send(b"order-1")).commit().expect_failure();
+        assert_user_error(&builder, 26);
+
+        builder.exec(send(b"order-2")).commit().expect_success();
+
+        let nonce: u64 = call_getter(&mut builder, contract, "get_nonce", runtime_args! {});
+        assert_eq!(nonce, 2);
+    }
+
+    #[test]
+    fn max_pending_caps_partly_confirmed_messages() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let gateway = install(&mut builder);
+        let committee = [
+            SigningKey::random(&mut OsRng),
+            SigningKey::random(&mut OsRng),
+        ];
+        set_committee(&mut builder, gateway, &committee, 2);
+
+        let set_max_pending = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "set_max_pending",
+            runtime_args! { "max_pending" => 1u32 },
+        )
+        .build();
+        builder.exec(set_max_pending).commit().expect_success();
+
+        let receiver = Bytes::from(vec![0u8; 32]);
+        let message = |nonce: u64| {
+            build_message_bytes(
+                1,
+                CASPER_CHAIN_ID,
+                &[9u8; 32],
+                receiver.as_ref(),
+                nonce,
+                &[],
+            )
+        };
+        let call = |entry_point: &str, nonce: u64, signers: &[usize]| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                entry_point,
+                runtime_args! {
+                    "src_chain_id" => 1u32,
+                    "src_gateway" => Bytes::from(vec![9u8; 32]),
+                    "receiver" => receiver.clone(),
+                    "nonce" => nonce,
+                    "payload" => Bytes::new(),
+                    "signatures" => committee_signatures(&committee, signers, &message(nonce)),
+                },
+            )
+            .build()
+        };
+
+        builder
+            .exec(call("confirm_message", 0, &[0]))
+            .commit()
+            .expect_success();
+
+        builder
+            .exec(call("confirm_message", 1, &[0]))
+            .commit()
+            .expect_failure();
+        assert_user_error(&builder, 41);
+
+        // The pending message still executes, on its stored confirmation
+        // plus one fresh signature, which frees the slot.
+        builder
+            .exec(call("execute_message", 0, &[1]))
+            .commit()
+            .expect_success();
+
+        builder
+            .exec(call("confirm_message", 1, &[0]))
+            .commit()
+            .expect_success();
+    }
+
+    #[test]
+    fn get_relayers_lists_committee_keys() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let relayer = unused_relayer_key();
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! { "relayer_pubkey" => relayer.clone() },
+        );
+
+        let keys: Vec<Bytes> = call_getter(&mut builder, gateway, "get_relayers", runtime_args! {});
+        assert_eq!(keys, vec![Bytes::from(relayer.to_bytes().unwrap())]);
+
+        let committee = [
+            SigningKey::random(&mut OsRng),
+            SigningKey::random(&mut OsRng),
+            SigningKey::random(&mut OsRng),
+        ];
+        set_committee(&mut builder, gateway, &committee, 2);
+
+        let keys: Vec<Bytes> = call_getter(&mut builder, gateway, "get_relayers", runtime_args! {});
+        let expected: Vec<Bytes> = committee
+            .iter()
+            .map(|key| Bytes::from(relayer_key(key).to_bytes().unwrap()))
+            .collect();
+        assert_eq!(keys, expected);
+    }
+
+    #[test]
+    fn enabling_a_supported_chain_again_is_idempotent() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+
+        set_supported_chain(&mut builder, contract, 1, true);
+        set_supported_chain(&mut builder, contract, 1, true);
+
+        let chains: Vec<u32> = call_getter(
+            &mut builder,
+            contract,
+            "get_supported_chains",
+            runtime_args! {},
+        );
+        assert_eq!(chains, vec![1]);
+
+        set_supported_chain(&mut builder, contract, 2, true);
+        set_supported_chain(&mut builder, contract, 1, false);
+
+        let chains: Vec<u32> = call_getter(
+            &mut builder,
+            contract,
+            "get_supported_chains",
+            runtime_args! {},
+        );
+        assert_eq!(chains, vec![2]);
+    }
+
+    #[test]
+    fn receiver_runs_after_message_is_marked_executed() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+
+        let install = ExecuteRequestBuilder::standard(
+            *DEFAULT_ACCOUNT_ADDR,
+            RECEIVER_WASM,
+            runtime_args! {
+                "contract_key_name" => "checking_receiver".to_string(),
+                "checked_gateway" => gateway,
+            },
+        )
+        .build();
+        builder.exec(install).commit().expect_success();
+        let receiver_contract = named_contract(&builder, "checking_receiver");
+        let receiver = Bytes::from(receiver_contract.value().to_vec());
+
+        let message =
+            build_message_bytes(1, CASPER_CHAIN_ID, &[9u8; 32], receiver.as_ref(), 0, &[]);
+        let execute = execute_message_request(
+            gateway,
+            1,
+            Bytes::from(vec![9u8; 32]),
+            receiver,
+            0,
+            Bytes::new(),
+            sign_with(&signing_key, &message),
+        );
+
+        // The receiver reverts if the gateway has not marked the message yet.
+        builder.exec(execute).commit().expect_success();
+        assert_eq!(receiver_count(&builder, receiver_contract), 1);
+    }
+
+    #[test]
+    fn get_self_identity_matches_installed_hashes() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        install(&mut builder);
+        let gateway = named_contract(&builder, "knotx_gateway");
+        let package_hash = builder
+            .get_contract(gateway)
+            .expect("contract")
+            .contract_package_hash();
+
+        let identity: Bytes =
+            call_getter(&mut builder, gateway, "get_self_identity", runtime_args! {});
+        assert_eq!(&identity[..32], &gateway.value()[..]);
+        assert_eq!(&identity[32..], &package_hash.value()[..]);
+    }
+
+    #[test]
+    fn execute_message_accepts_arbitrary_nonces_when_unordered() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+        let receiver = Bytes::from(vec![0u8; 32]);
+
+        let execute = |nonce: u64| {
+            let message = build_message_bytes(
+                1,
+                CASPER_CHAIN_ID,
+                &[9u8; 32],
+                receiver.as_ref(),
+                nonce,
+                &[],
+            );
+            let request = execute_message_request(
+                gateway,
+                1,
+                Bytes::from(vec![9u8; 32]),
+                receiver.clone(),
+                nonce,
+                Bytes::new(),
+                sign_with(&signing_key, &message),
+            );
+            (request, message_key(&message))
+        };
+
+        let nonces = [5u64, 5_000_000, u64::MAX, 0];
+        for nonce in nonces {
+            let (request, _) = execute(nonce);
+            builder.exec(request).commit().expect_success();
+        }
+
+        for nonce in nonces {
+            let (request, message_id) = execute(nonce);
+            assert!(call_getter::<bool>(
+                &mut builder,
+                gateway,
+                "is_executed",
+                runtime_args! { "message_id" => message_id },
+            ));
+
+            builder.exec(request).commit().expect_failure();
+            assert_user_error(&builder, 2);
+        }
+
+        let stats: Stats = call_getter(&mut builder, gateway, "get_stats", runtime_args! {});
+        assert_eq!(stats.executed, 4);
+    }
+
+    #[test]
+    fn bump_version_only_moves_forward_and_records_event() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let gateway = install(&mut builder);
+
+        let version: String = call_getter(
+            &mut builder,
+            gateway,
+            "get_protocol_version",
+            runtime_args! {},
+        );
+        assert_eq!(version, "1.0.0");
+
+        let bump = |version: &str| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "bump_version",
+                runtime_args! { "version" => version.to_string() },
+            )
+            .build()
+        };
+
+        builder.exec(bump("1.1.0")).commit().expect_success();
+
+        builder.exec(bump("1.0.5")).commit().expect_failure();
+        assert_user_error(&builder, 28);
+
+        builder.exec(bump("1.1.0")).commit().expect_failure();
+        assert_user_error(&builder, 28);
+
+        builder.exec(bump("1.2")).commit().expect_failure();
+        assert_user_error(&builder, 27);
+
+        let version: String = call_getter(
+            &mut builder,
+            gateway,
+            "get_protocol_version",
+            runtime_args! {},
+        );
+        assert_eq!(version, "1.1.0");
+
+        let (event_version, _): (String, u64) =
+            dictionary_item(&builder, gateway, "version_events", "0").expect("version event");
+        assert_eq!(event_version, "1.1.0");
+        assert_eq!(
+            dictionary_item::<(String, u64)>(&builder, gateway, "version_events", "1"),
+            None
+        );
+    }
+
+    #[test]
+    fn chain_threshold_overrides_global_threshold() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let gateway = install(&mut builder);
+        let committee = [
+            SigningKey::random(&mut OsRng),
+            SigningKey::random(&mut OsRng),
+        ];
+        set_committee(&mut builder, gateway, &committee, 1);
+
+        let set_chain_threshold = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "set_chain_threshold",
+            runtime_args! { "chain_id" => 1u32, "threshold" => Some(2u8) },
+        )
+        .build();
+        builder.exec(set_chain_threshold).commit().expect_success();
+
+        let config: ChainConfig = call_getter(
+            &mut builder,
+            gateway,
+            "get_chain_config",
+            runtime_args! { "chain_id" => 1u32 },
+        );
+        assert_eq!(config.threshold, Some(2));
+
+        let receiver = Bytes::from(vec![0u8; 32]);
+        let execute = |chain_id: u32, signers: &[usize]| {
+            let message = build_message_bytes(
+                chain_id,
+                CASPER_CHAIN_ID,
+                &[9u8; 32],
+                receiver.as_ref(),
+                0,
+                &[],
+            );
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "execute_message",
+                runtime_args! {
+                    "src_chain_id" => chain_id,
+                    "src_gateway" => Bytes::from(vec![9u8; 32]),
+                    "receiver" => receiver.clone(),
+                    "nonce" => 0u64,
+                    "payload" => Bytes::new(),
+                    "signatures" => committee_signatures(&committee, signers, &message),
+                },
+            )
+            .build()
+        };
+
+        builder.exec(execute(2, &[0])).commit().expect_success();
+
+        builder.exec(execute(1, &[0])).commit().expect_failure();
+        assert_user_error(&builder, 15);
+
+        builder.exec(execute(1, &[0, 1])).commit().expect_success();
+    }
+
+    #[test]
+    fn get_message_id_by_nonce_finds_outbound_message() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+        set_supported_chain(&mut builder, contract, 1, true);
+        send_message(
+            &mut builder,
+            contract,
+            1,
+            Bytes::from(vec![5u8; 32]),
+            Bytes::from(vec![1u8, 2, 3]),
+        );
+
+        let stored: Bytes = call_getter(
+            &mut builder,
+            contract,
+            "get_message",
+            runtime_args! { "nonce" => 0u64 },
+        );
+        let message_id: String = call_getter(
+            &mut builder,
+            contract,
+            "get_message_id_by_nonce",
+            runtime_args! { "dst_chain_id" => 1u32, "nonce" => 0u64 },
+        );
+        assert_eq!(message_id, message_key(&stored));
+
+        let unknown = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "get_message_id_by_nonce",
+            runtime_args! { "dst_chain_id" => 1u32, "nonce" => 1u64 },
+        )
+        .build();
+        builder.exec(unknown).commit().expect_failure();
+        assert_user_error(&builder, 7);
+    }
+
+    #[test]
+    fn check_receivers_rejects_missing_pinned_receiver() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        install_receiver(&mut builder);
+        let package = named_contract(&builder, "mock_receiver_package");
+        let src_gateway = Bytes::from(vec![9u8; 32]);
+        let payload = Bytes::from(vec![1u8]);
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => relayer_key(&signing_key),
+                "check_receivers" => true,
+            },
+        );
+
+        let deliver =
+            |builder: &mut LmdbWasmTestBuilder, receiver: Bytes, pin: Option<u32>, nonce: u64| {
+                if let Some(version) = pin {
+                    let pin = ExecuteRequestBuilder::contract_call_by_hash(
+                        *DEFAULT_ACCOUNT_ADDR,
+                        gateway.into(),
+                        "set_receiver_version",
+                        runtime_args! {
+                            "receiver" => receiver.clone(),
+                            "version" => version,
+                        },
+                    )
+                    .build();
+                    builder.exec(pin).commit().expect_success();
+                }
+
+                let message = build_message_bytes(
+                    1,
+                    CASPER_CHAIN_ID,
+                    src_gateway.as_ref(),
+                    receiver.as_ref(),
+                    nonce,
+                    payload.as_ref(),
+                );
+                let call = execute_message_request(
+                    gateway,
+                    1,
+                    src_gateway.clone(),
+                    receiver,
+                    nonce,
+                    payload.clone(),
+                    sign_with(&signing_key, &message),
+                );
+                builder.exec(call).commit();
+            };
+
+        deliver(
+            &mut builder,
+            Bytes::from(package.value().to_vec()),
+            Some(1),
+            0,
+        );
+        builder.expect_success();
+
+        // Nothing is stored under a random hash.
+        deliver(&mut builder, Bytes::from(vec![0x5au8; 32]), Some(1), 1);
+        builder.expect_failure();
+        assert_user_error(&builder, 42);
+
+        // The package exists, the pinned version does not.
+        deliver(
+            &mut builder,
+            Bytes::from(package.value().to_vec()),
+            Some(7),
+            1,
+        );
+        builder.expect_failure();
+        assert_user_error(&builder, 42);
+
+        // Unpinned hashes are not probed: the receiver call itself traps.
+        deliver(&mut builder, Bytes::from(vec![0x5bu8; 32]), None, 1);
+        builder.expect_failure();
+        let err = builder.get_error().expect("execution error");
+        assert!(
+            !matches!(err, Error::Exec(ExecError::Revert(ApiError::User(_)))),
+            "expected a host error, got {:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn gateway_namespace_separates_message_ids() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let receiver = Bytes::from(vec![5u8; 32]);
+        let payload = Bytes::from(vec![1u8, 2, 3]);
+
+        let mut sent = Vec::new();
+        for namespace in ["A", "B"] {
+            let name = format!("gateway_{}", namespace);
+            let install = ExecuteRequestBuilder::standard(
+                *DEFAULT_ACCOUNT_ADDR,
+                CONTRACT_WASM,
+                runtime_args! {
+                    "relayer_pubkey" => unused_relayer_key(),
+                    "contract_key_name" => name.clone(),
+                    "gateway_namespace" => namespace.to_string(),
+                },
+            )
+            .build();
+            builder.exec(install).commit().expect_success();
+
+            let gateway = named_contract(&builder, &name);
+            set_supported_chain(&mut builder, gateway, 1, true);
+            send_message(&mut builder, gateway, 1, receiver.clone(), payload.clone());
+
+            let stored: Bytes = call_getter(
+                &mut builder,
+                gateway,
+                "get_message",
+                runtime_args! { "nonce" => 0u64 },
+            );
+            let message_id: String = call_getter(
+                &mut builder,
+                gateway,
+                "get_message_id_by_nonce",
+                runtime_args! { "dst_chain_id" => 1u32, "nonce" => 0u64 },
+            );
+            sent.push((stored, message_id));
+        }
+
+        assert_eq!(sent[0].0, sent[1].0);
+        assert_ne!(sent[0].1, sent[1].1);
+        assert_ne!(sent[0].1, message_key(&sent[0].0));
+    }
+
+    #[test]
+    fn get_chain_traffic_counts_both_directions() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let contract = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+        set_supported_chain(&mut builder, contract, 1, true);
+
+        let receiver = Bytes::from(vec![0u8; 32]);
+        for _ in 0..2 {
+            send_message(&mut builder, contract, 1, receiver.clone(), Bytes::new());
+        }
+
+        let src_gateway = Bytes::from(vec![9u8; 32]);
+        let message = build_message_bytes(
+            1,
+            CASPER_CHAIN_ID,
+            src_gateway.as_ref(),
+            receiver.as_ref(),
+            0,
+            &[],
+        );
+        let call = execute_message_request(
+            contract,
+            1,
+            src_gateway,
+            receiver,
+            0,
+            Bytes::new(),
+            sign_with(&signing_key, &message),
+        );
+        builder.exec(call).commit().expect_success();
+
+        let traffic: (u64, u64) = call_getter(
+            &mut builder,
+            contract,
+            "get_chain_traffic",
+            runtime_args! { "chain_id" => 1u32 },
+        );
+        assert_eq!(traffic, (2, 1));
+
+        let untouched: (u64, u64) = call_getter(
+            &mut builder,
+            contract,
+            "get_chain_traffic",
+            runtime_args! { "chain_id" => 2u32 },
+        );
+        assert_eq!(untouched, (0, 0));
+    }
+
+    #[test]
+    fn send_ttl_sets_deadline_enforced_on_execute() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let contract = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+        set_supported_chain(&mut builder, contract, 1, true);
+
+        let receiver = Bytes::from(vec![0u8; 32]);
+        let send = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "send_message",
+            runtime_args! {
+                "dst_chain_id" => 1u32,
+                "receiver" => receiver.clone(),
+                "payload" => Bytes::new(),
+                "ttl_ms" => 1_000u64,
+            },
+        )
+        .with_block_time(5_000u64)
+        .build();
+        builder.exec(send).commit().expect_success();
+
+        let message_id: String = call_getter(
+            &mut builder,
+            contract,
+            "get_message_id_by_nonce",
+            runtime_args! { "dst_chain_id" => 1u32, "nonce" => 0u64 },
+        );
+        let metas: Vec<Option<MessageMeta>> = call_getter(
+            &mut builder,
+            contract,
+            "get_messages_meta",
+            runtime_args! { "message_ids" => vec![message_id] },
+        );
+        let deadline = metas[0].as_ref().expect("meta recorded").deadline;
+        assert_eq!(deadline, 6_000);
+
+        // The relayer carries the deadline to the destination and signs it.
+        let src_gateway = Bytes::from(vec![9u8; 32]);
+        let execute = |nonce: u64, block_time: u64| {
+            let mut signed = build_message_bytes(
+                1,
+                CASPER_CHAIN_ID,
+                src_gateway.as_ref(),
+                receiver.as_ref(),
+                nonce,
+                &[],
+            );
+            signed.extend_from_slice(&deadline.to_be_bytes());
+
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                contract.into(),
+                "execute_message",
+                runtime_args! {
+                    "src_chain_id" => 1u32,
+                    "src_gateway" => src_gateway.clone(),
+                    "receiver" => receiver.clone(),
+                    "nonce" => nonce,
+                    "payload" => Bytes::new(),
+                    "signature" => sign_with(&signing_key, &signed),
+                    "deadline" => deadline,
+                },
+            )
+            .with_block_time(block_time)
+            .build()
+        };
+
+        builder.exec(execute(0, 7_000)).commit().expect_failure();
+        assert_user_error(&builder, 45);
+
+        builder.exec(execute(0, 5_500)).commit().expect_success();
+    }
+
+    #[test]
+    fn execute_messages_batch_rejects_batch_over_cap() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let contract = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+
+        let set_max_batch_size = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "set_max_batch_size",
+            runtime_args! { "max_batch_size" => 2u32 },
+        )
+        .build();
+        builder.exec(set_max_batch_size).commit().expect_success();
+
+        let src_gateway = Bytes::from(vec![9u8; 32]);
+        let receiver = Bytes::from(vec![0u8; 32]);
+        let messages: Vec<(String, Bytes)> = (0..3u64)
+            .map(|nonce| {
+                let message = build_message_bytes(
+                    1,
+                    CASPER_CHAIN_ID,
+                    src_gateway.as_ref(),
+                    receiver.as_ref(),
+                    nonce,
+                    &[],
+                );
+                let args = runtime_args! {
+                    "src_chain_id" => 1u32,
+                    "src_gateway" => src_gateway.clone(),
+                    "receiver" => receiver.clone(),
+                    "nonce" => nonce,
+                    "payload" => Bytes::new(),
+                    "signature" => sign_with(&signing_key, &message),
+                };
+                (
+                    message_key(&message),
+                    Bytes::from(args.to_bytes().expect("serialize args")),
+                )
+            })
+            .collect();
+
+        let batch = |builder: &mut LmdbWasmTestBuilder, messages: &[(String, Bytes)]| {
+            let call = ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                contract.into(),
+                "execute_messages_batch",
+                runtime_args! {
+                    "messages" => messages.iter().map(|(_, args)| args.clone()).collect::<Vec<_>>(),
+                },
+            )
+            .build();
+            builder.exec(call).commit();
+        };
+
+        batch(&mut builder, &messages);
+        builder.expect_failure();
+        assert_user_error(&builder, 43);
+
+        let executed = |builder: &mut LmdbWasmTestBuilder, message_id: &str| -> bool {
+            call_getter(
+                builder,
+                contract,
+                "is_executed",
+                runtime_args! { "message_id" => message_id.to_string() },
+            )
+        };
+        assert!(!executed(&mut builder, &messages[0].0));
+
+        batch(&mut builder, &messages[..2]);
+        builder.expect_success();
+        assert!(executed(&mut builder, &messages[0].0));
+        assert!(executed(&mut builder, &messages[1].0));
+        assert!(!executed(&mut builder, &messages[2].0));
+    }
+
+    #[test]
+    fn governance_messages_go_to_the_governance_receiver() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+        let app_receiver = install_receiver(&mut builder);
+
+        let install = ExecuteRequestBuilder::standard(
+            *DEFAULT_ACCOUNT_ADDR,
+            RECEIVER_WASM,
+            runtime_args! { "contract_key_name" => "governance_receiver".to_string() },
+        )
+        .build();
+        builder.exec(install).commit().expect_success();
+        let governance_receiver = named_contract(&builder, "governance_receiver");
+
+        let receiver = Bytes::from(app_receiver.value().to_vec());
+        let execute = |nonce: u64| {
+            let message = build_typed_message_bytes(
+                1,
+                CASPER_CHAIN_ID,
+                &[9u8; 32],
+                receiver.as_ref(),
+                nonce,
+                U512::zero(),
+                255,
+                0,
+                &[],
+            );
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "execute_message",
+                runtime_args! {
+                    "src_chain_id" => 1u32,
+                    "src_gateway" => Bytes::from(vec![9u8; 32]),
+                    "receiver" => receiver.clone(),
+                    "nonce" => nonce,
+                    "payload" => Bytes::new(),
+                    "signature" => sign_with(&signing_key, &message),
+                    "msg_type" => 255u8,
+                },
+            )
+            .build()
+        };
+
+        // Nothing handles governance until the owner names a receiver.
+        builder.exec(execute(0)).commit().expect_failure();
+        assert_user_error(&builder, 46);
+
+        let set_governance_receiver = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "set_governance_receiver",
+            runtime_args! {
+                "receiver" => Some(Bytes::from(governance_receiver.value().to_vec())),
+            },
+        )
+        .build();
+        builder
+            .exec(set_governance_receiver)
+            .commit()
+            .expect_success();
+
+        builder.exec(execute(0)).commit().expect_success();
+        assert_eq!(receiver_count(&builder, governance_receiver), 1);
+        assert_eq!(receiver_count(&builder, app_receiver), 0);
+    }
+
+    #[test]
+    fn verify_message_id_checks_id_against_fields() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+
+        let message = build_message_bytes(1, CASPER_CHAIN_ID, &[9u8; 32], &[5u8; 32], 3, b"hello");
+        let message_id = message_key(&message);
+
+        let mut verify = |nonce: u64| -> bool {
+            call_getter(
+                &mut builder,
+                contract,
+                "verify_message_id",
+                runtime_args! {
+                    "message_id" => message_id.clone(),
+                    "src_chain_id" => 1u32,
+                    "dst_chain_id" => CASPER_CHAIN_ID,
+                    "src_gateway" => Bytes::from(vec![9u8; 32]),
+                    "receiver" => Bytes::from(vec![5u8; 32]),
+                    "nonce" => nonce,
+                    "payload" => Bytes::from(b"hello".to_vec()),
+                },
+            )
+        };
+
+        assert!(verify(3));
+        assert!(!verify(4));
+    }
+
+    #[test]
+    fn events_carry_consecutive_global_seq() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => unused_relayer_key(),
+                "emit_events" => true,
+            },
+        );
+        set_supported_chain(&mut builder, contract, 1, true);
+        send_message(
+            &mut builder,
+            contract,
+            1,
+            Bytes::from(vec![5u8; 32]),
+            Bytes::from(vec![1u8]),
+        );
+
+        let set_max_pending = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "set_max_pending",
+            runtime_args! { "max_pending" => 4u32 },
+        )
+        .build();
+        builder.exec(set_max_pending).commit().expect_success();
+
+        let event = |global_seq: u64| -> GatewayEvent {
+            dictionary_item(&builder, contract, "events", &global_seq.to_string())
+                .expect("event recorded")
+        };
+
+        let sent = event(2);
+        let admin = event(3);
+        assert_eq!(sent.kind, "message_sent");
+        assert_eq!(admin.kind, "admin_action");
+        assert_eq!(admin.global_seq, sent.global_seq + 1);
+        assert_eq!(event(1).kind, "chain_support");
+        assert_eq!(event(0).kind, "admin_action");
+
+        let event_count: u64 = builder
+            .query(
+                None,
+                Key::Hash(contract.value()),
+                &["event_count".to_string()],
+            )
+            .expect("event_count named key")
+            .as_cl_value()
+            .expect("cl value")
+            .clone()
+            .into_t()
+            .expect("u64");
+        assert_eq!(event_count, 4);
+    }
+
+    #[test]
+    fn ces_backend_writes_standard_event_layout() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => unused_relayer_key(),
+                "emit_events" => true,
+                "event_backend" => 1u8,
+            },
+        );
+        set_supported_chain(&mut builder, contract, 1, true);
+        send_message(
+            &mut builder,
+            contract,
+            1,
+            Bytes::from(vec![5u8; 32]),
+            Bytes::from(vec![1u8]),
+        );
+
+        let named_value = |name: &str| -> CLValue {
+            builder
+                .query(None, Key::Hash(contract.value()), &[name.to_string()])
+                .expect("named key")
+                .as_cl_value()
+                .expect("cl value")
+                .clone()
+        };
+
+        assert_eq!(named_value("__events_length").into_t::<u32>().unwrap(), 3);
+        assert_eq!(
+            named_value("__events_ces_version")
+                .into_t::<String>()
+                .unwrap(),
+            "0.1.0"
+        );
+
+        // Schemas: one event, with the dictionary log's fields in order.
+        let schema = named_value("__events_schema");
+        let (count, rem) = u32::from_bytes(schema.inner_bytes()).unwrap();
+        let (name, rem) = String::from_bytes(rem).unwrap();
+        let (mut fields, mut rem) = (Vec::new(), u32::from_bytes(rem).unwrap().1);
+        while !rem.is_empty() {
+            let (field, next) = String::from_bytes(rem).unwrap();
+            let (cl_type, next) = CLType::from_bytes(next).unwrap();
+            fields.push((field, cl_type));
+            rem = next;
+        }
+        assert_eq!((count, name.as_str()), (1, "GatewayEvent"));
+        assert_eq!(
+            fields,
+            vec![
+                ("global_seq".to_string(), CLType::U64),
+                ("kind".to_string(), CLType::String),
+                ("subject".to_string(), CLType::String),
+                ("timestamp".to_string(), CLType::U64),
+            ]
+        );
+
+        let stored: Bytes =
+            dictionary_item(&builder, contract, "__events", "2").expect("ces event");
+        let (name, rem) = String::from_bytes(stored.as_ref()).unwrap();
+        let (event, rem) = GatewayEvent::from_bytes(rem).unwrap();
+        assert_eq!(name, "event_GatewayEvent");
+        assert!(rem.is_empty());
+        assert_eq!(event.kind, "message_sent");
+        assert_eq!(event.global_seq, 2);
+
+        // Nothing goes to the dictionary log.
+        assert_eq!(
+            dictionary_item::<GatewayEvent>(&builder, contract, "events", "2"),
+            None
+        );
+        assert_eq!(named_value("event_count").into_t::<u64>().unwrap(), 0);
+    }
+
+    #[test]
+    fn deprecated_chain_blocks_sends_but_still_executes() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let contract = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => relayer_key(&signing_key),
+                "strict_source_chains" => true,
+            },
+        );
+        set_supported_chain(&mut builder, contract, 1, true);
+
+        let set_status = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "set_chain_status",
+            runtime_args! { "chain_id" => 1u32, "status" => 2u8 },
+        )
+        .build();
+        builder.exec(set_status).commit().expect_success();
+
+        let config: ChainConfig = call_getter(
+            &mut builder,
+            contract,
+            "get_chain_config",
+            runtime_args! { "chain_id" => 1u32 },
+        );
+        assert_eq!(config.status, 2);
+
+        // Re-supporting the chain does not clear the deprecation, whoever asks.
+        let other = AccountHash::new([7u8; 32]);
+        let fund = TransferRequestBuilder::new(MINIMUM_ACCOUNT_CREATION_BALANCE, other).build();
+        builder.transfer_and_commit(fund).expect_success();
+
+        let resupport = |caller: AccountHash| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                caller,
+                contract.into(),
+                "set_supported_chain",
+                runtime_args! { "chain_id" => 1u32, "supported" => true },
+            )
+            .build()
+        };
+        builder.exec(resupport(other)).commit().expect_failure();
+        assert_user_error(&builder, 8);
+        builder
+            .exec(resupport(*DEFAULT_ACCOUNT_ADDR))
+            .commit()
+            .expect_success();
+
+        let config: ChainConfig = call_getter(
+            &mut builder,
+            contract,
+            "get_chain_config",
+            runtime_args! { "chain_id" => 1u32 },
+        );
+        assert_eq!(config.status, 2);
+
+        let receiver = Bytes::from(vec![0u8; 32]);
+        let send = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "send_message",
+            runtime_args! {
+                "dst_chain_id" => 1u32,
+                "receiver" => receiver.clone(),
+                "payload" => Bytes::new(),
+            },
+        )
+        .build();
+        builder.exec(send).commit().expect_failure();
+        assert_user_error(&builder, 47);
+
+        let src_gateway = Bytes::from(vec![9u8; 32]);
+        let message = build_message_bytes(
+            1,
+            CASPER_CHAIN_ID,
+            src_gateway.as_ref(),
+            receiver.as_ref(),
+            0,
+            &[],
+        );
+        let call = execute_message_request(
+            contract,
+            1,
+            src_gateway,
+            receiver,
+            0,
+            Bytes::new(),
+            sign_with(&signing_key, &message),
+        );
+        builder.exec(call).commit().expect_success();
+    }
+
+    #[test]
+    fn store_only_delivery_is_readable_and_calls_nothing() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+        let receiver = install_receiver(&mut builder);
+        let receiver_bytes = Bytes::from(receiver.value().to_vec());
+        let src_gateway = Bytes::from(vec![9u8; 32]);
+        let payload = Bytes::from(b"price:42".to_vec());
+
+        let message = build_message_bytes(
+            1,
+            CASPER_CHAIN_ID,
+            src_gateway.as_ref(),
+            receiver_bytes.as_ref(),
+            0,
+            payload.as_ref(),
+        );
+        let mut signed = message.clone();
+        signed.push(1);
+
+        let execute = |signature: Bytes| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "execute_message",
+                runtime_args! {
+                    "src_chain_id" => 1u32,
+                    "src_gateway" => src_gateway.clone(),
+                    "receiver" => receiver_bytes.clone(),
+                    "nonce" => 0u64,
+                    "payload" => payload.clone(),
+                    "signature" => signature,
+                    "store_only" => true,
+                },
+            )
+            .build()
+        };
+
+        // A signature over the plain message does not cover the flag.
+        builder
+            .exec(execute(sign_with(&signing_key, &message)))
+            .commit()
+            .expect_failure();
+        assert_user_error(&builder, 5);
+
+        builder
+            .exec(execute(sign_with(&signing_key, &signed)))
+            .commit()
+            .expect_success();
+
+        let stored: Bytes = call_getter(
+            &mut builder,
+            gateway,
+            "get_message",
+            runtime_args! { "message_id" => message_key(&message) },
+        );
+        assert_eq!(stored.as_ref(), message.as_slice());
+        assert_eq!(receiver_count(&builder, receiver), 0);
+    }
+
+    #[test]
+    fn tampered_message_fails_its_keyed_tag_on_read() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => unused_relayer_key(),
+                "mac_key" => Bytes::from(vec![0x5au8; 32]),
+            },
+        );
+        set_supported_chain(&mut builder, contract, DST_CHAIN_ID, true);
+
+        let receiver = Bytes::from(vec![2u8; 32]);
+        send_message(
+            &mut builder,
+            contract,
+            DST_CHAIN_ID,
+            receiver.clone(),
+            Bytes::from(vec![1u8]),
+        );
+
+        // Messages tagged under an older key still verify after a rotation.
+        let rotate = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "rotate_mac_key",
+            runtime_args! { "mac_key" => Bytes::from(vec![0xa5u8; 32]) },
+        )
+        .build();
+        builder.exec(rotate).commit().expect_success();
+        send_message(
+            &mut builder,
+            contract,
+            DST_CHAIN_ID,
+            receiver,
+            Bytes::from(vec![2u8]),
+        );
+
+        let tags = (0..2u64)
+            .map(|nonce| {
+                dictionary_item::<(u32, [u8; 32])>(
+                    &builder,
+                    contract,
+                    "message_tags",
+                    &nonce.to_string(),
+                )
+                .expect("message tagged")
+                .0
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(tags, vec![1, 2]);
+
+        let original: Bytes = call_getter(
+            &mut builder,
+            contract,
+            "get_message",
+            runtime_args! { "nonce" => 0u64 },
+        );
+        let _: Bytes = call_getter(
+            &mut builder,
+            contract,
+            "get_message",
+            runtime_args! { "nonce" => 1u64 },
+        );
+
+        // Flip one payload byte behind the contract's back.
+        let messages = contract_uref(&builder, contract, KEY_MESSAGES);
+        let mut tampered = original.to_vec();
+        *tampered.last_mut().expect("payload byte") ^= 0xff;
+        let value = CLValueDictionary::new(
+            CLValue::from_t(Bytes::from(tampered)).unwrap(),
+            messages.addr().to_vec(),
+            b"0".to_vec(),
+        );
+        builder.write_data_and_commit(
+            [(
+                Key::dictionary(messages, b"0"),
+                StoredValue::CLValue(CLValue::from_t(value).unwrap()),
+            )]
+            .into_iter(),
+        );
+
+        let call = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "get_message",
+            runtime_args! { "nonce" => 0u64 },
+        )
+        .build();
+        builder.exec(call).commit().expect_failure();
+        assert_user_error(&builder, 48);
+    }
+
+    #[test]
+    fn reward_token_pays_relayer_in_cep18() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let reward = U512::from(500u64);
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => relayer_key(&signing_key),
+                "relayer_reward" => reward,
+            },
+        );
+
+        let install_token = ExecuteRequestBuilder::standard(
+            *DEFAULT_ACCOUNT_ADDR,
+            MOCK_TOKEN_WASM,
+            runtime_args! {},
+        )
+        .build();
+        builder.exec(install_token).commit().expect_success();
+        let token = named_contract(&builder, "mock_token");
+
+        // The token knows the gateway by its package hash.
+        let identity: Bytes =
+            call_getter(&mut builder, gateway, "get_self_identity", runtime_args! {});
+        let gateway_holder = Key::Hash(identity[32..].try_into().expect("package hash"));
+        let relayer_holder = Key::Account(*DEFAULT_ACCOUNT_ADDR);
+
+        let mint = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            token.into(),
+            "mint",
+            runtime_args! { "owner" => gateway_holder, "amount" => U256::from(10_000u64) },
+        )
+        .build();
+        builder.exec(mint).commit().expect_success();
+
+        let set_token = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "set_reward_token",
+            runtime_args! { "token" => Some(token) },
+        )
+        .build();
+        builder.exec(set_token).commit().expect_success();
+
+        let receiver = Bytes::from(vec![0u8; 32]);
+        let message =
+            build_message_bytes(1, CASPER_CHAIN_ID, &[9u8; 32], receiver.as_ref(), 0, &[]);
+        let call = execute_message_request(
+            gateway,
+            1,
+            Bytes::from(vec![9u8; 32]),
+            receiver,
+            0,
+            Bytes::from(vec![]),
+            sign_with(&signing_key, &message),
+        );
+        builder.exec(call).commit().expect_success();
+
+        let balance_of = |builder: &mut LmdbWasmTestBuilder, holder: Key| -> U256 {
+            call_getter(
+                builder,
+                token,
+                "balance_of",
+                runtime_args! { "address" => holder },
+            )
+        };
+        assert_eq!(balance_of(&mut builder, relayer_holder), U256::from(500u64));
+        assert_eq!(
+            balance_of(&mut builder, gateway_holder),
+            U256::from(9_500u64)
+        );
+        // The CSPR reward purse was never touched.
+        assert_eq!(
+            call_getter::<U512>(
+                &mut builder,
+                gateway,
+                "get_reward_balance",
+                runtime_args! {}
+            ),
+            U512::zero()
+        );
+    }
+
+    #[test]
+    fn failed_delivery_is_dead_lettered_past_retry_cap() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => relayer_key(&signing_key),
+                "max_delivery_attempts" => 3u32,
+            },
+        );
+        let receiver = install_receiver(&mut builder);
+        let receiver_bytes = Bytes::from(receiver.value().to_vec());
+
+        let set_paused = |builder: &mut LmdbWasmTestBuilder, entry_point: &str| {
+            let call = ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                entry_point,
+                runtime_args! { "receiver" => receiver_bytes.clone() },
+            )
+            .build();
+            builder.exec(call).commit().expect_success();
+        };
+        let retry = |message_id: &str| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "retry_failed",
+                runtime_args! { "message_id" => message_id.to_string() },
+            )
+            .build()
+        };
+
+        set_paused(&mut builder, "pause_receiver");
+
+        let message_ids: Vec<String> = (0..2u64)
+            .map(|nonce| {
+                let message = build_message_bytes(
+                    1,
+                    CASPER_CHAIN_ID,
+                    &[9u8; 32],
+                    receiver_bytes.as_ref(),
+                    nonce,
+                    &[],
+                );
+                let call = ExecuteRequestBuilder::contract_call_by_hash(
+                    *DEFAULT_ACCOUNT_ADDR,
+                    gateway.into(),
+                    "execute_message",
+                    runtime_args! {
+                        "src_chain_id" => 1u32,
+                        "src_gateway" => Bytes::from(vec![9u8; 32]),
+                        "receiver" => receiver_bytes.clone(),
+                        "nonce" => nonce,
+                        "payload" => Bytes::new(),
+                        "signature" => sign_with(&signing_key, &message),
+                        "soft_fail" => true,
+                    },
+                )
+                .build();
+                builder.exec(call).commit().expect_success();
+                message_key(&message)
+            })
+            .collect();
+        let (dead, revived) = (&message_ids[0], &message_ids[1]);
+
+        // Attempts two and three; the third failure is final.
+        builder.exec(retry(dead)).commit().expect_success();
+        assert!(
+            dictionary_item::<Option<Bytes>>(&builder, gateway, "failed_messages", dead)
+                .flatten()
+                .is_some()
+        );
+        builder.exec(retry(dead)).commit().expect_success();
+        assert_eq!(
+            dictionary_item::<Option<Bytes>>(&builder, gateway, "failed_messages", dead),
+            Some(None)
+        );
+
+        let dead_letters: Vec<String> = call_getter(
+            &mut builder,
+            gateway,
+            "get_dead_letters",
+            runtime_args! { "offset" => 0u64, "limit" => 10u32 },
+        );
+        assert_eq!(dead_letters, vec![dead.clone()]);
+
+        builder.exec(retry(dead)).commit().expect_failure();
+        assert_user_error(&builder, 7);
+
+        // Once the receiver is back, a retry delivers.
+        set_paused(&mut builder, "unpause_receiver");
+        builder.exec(retry(revived)).commit().expect_success();
+        assert_eq!(receiver_count(&builder, receiver), 1);
+        assert_eq!(
+            dictionary_item::<Option<Bytes>>(&builder, gateway, "failed_messages", revived),
+            Some(None)
+        );
+    }
+
+    #[test]
+    fn execute_message_rejects_short_gateway_before_signature_check() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! { "relayer_pubkey" => unused_relayer_key() },
+        );
+
+        let execute = |src_gateway_len: usize| {
+            execute_message_request(
+                gateway,
+                1,
+                Bytes::from(vec![9u8; src_gateway_len]),
+                Bytes::from(vec![0u8; 32]),
+                0,
+                Bytes::new(),
+                Bytes::from(vec![0u8; 64]),
+            )
+        };
+
+        builder.exec(execute(4)).commit().expect_failure();
+        assert_user_error(&builder, 49);
+
+        // An EVM-sized gateway clears the floor and reaches the signature check.
+        builder.exec(execute(20)).commit().expect_failure();
+        assert_user_error(&builder, 5);
+    }
+
+    #[test]
+    fn max_payload_len_read_follows_owner_updates() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! { "relayer_pubkey" => unused_relayer_key() },
+        );
+
+        let max_payload_len: u32 = call_getter(
+            &mut builder,
+            gateway,
+            "get_max_payload_len",
+            runtime_args! {},
+        );
+        assert_eq!(max_payload_len, 64 * 1024);
+
+        let set_max_payload_len = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "set_max_payload_len",
+            runtime_args! { "max_payload_len" => 4096u32 },
+        )
+        .build();
+        builder.exec(set_max_payload_len).commit().expect_success();
+
+        let max_payload_len: u32 = call_getter(
+            &mut builder,
+            gateway,
+            "get_max_payload_len",
+            runtime_args! {},
+        );
+        assert_eq!(max_payload_len, 4096);
+    }
+
+    #[test]
+    fn outbound_message_carries_configured_dst_gateway() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+        set_supported_chain(&mut builder, contract, 2, true);
+
+        let dst_gateway = Bytes::from(vec![0xd5u8; 20]);
+        let configure = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "set_supported_chain",
+            runtime_args! {
+                "chain_id" => DST_CHAIN_ID,
+                "supported" => true,
+                "dst_gateway" => dst_gateway.clone(),
+            },
+        )
+        .build();
+        builder.exec(configure).commit().expect_success();
+
+        let config: ChainConfig = call_getter(
+            &mut builder,
+            contract,
+            "get_chain_config",
+            runtime_args! { "chain_id" => DST_CHAIN_ID },
+        );
+        assert_eq!(config.dst_gateway, Some(dst_gateway.clone()));
+
+        let receiver = Bytes::from(vec![7u8; 20]);
+        let payload = Bytes::from(b"hello".to_vec());
+        send_message(
+            &mut builder,
+            contract,
+            DST_CHAIN_ID,
+            receiver.clone(),
+            payload.clone(),
+        );
+        send_message(&mut builder, contract, 2, receiver, payload.clone());
+
+        // The gateway sits between the header and the payload.
+        let bound: Bytes = dictionary_item(&builder, contract, "messages", "0").unwrap();
+        let mut tail = dst_gateway.to_vec();
+        tail.extend_from_slice(payload.as_ref());
+        assert!(bound.ends_with(&tail));
+
+        let stored: Bytes = call_getter(
+            &mut builder,
+            contract,
+            "get_message_payload",
+            runtime_args! { "nonce" => 0u64 },
+        );
+        assert_eq!(stored, payload);
+
+        // A chain without one keeps the plain layout.
+        let unbound: Bytes = dictionary_item(&builder, contract, "messages", "1").unwrap();
+        assert_eq!(unbound.len(), bound.len() - dst_gateway.len());
+    }
+
+    #[test]
+    fn chain_configs_round_trip_through_export_and_import() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+
+        let owner_call = |entry_point: &str, args: RuntimeArgs| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                contract.into(),
+                entry_point,
+                args,
+            )
+            .build()
+        };
+
+        let setup = [
+            owner_call(
+                "set_supported_chain",
+                runtime_args! {
+                    "chain_id" => 1u32,
+                    "supported" => true,
+                    "dst_gateway" => Bytes::from(vec![0xd5u8; 20]),
+                },
+            ),
+            owner_call(
+                "set_chain_ordered",
+                runtime_args! { "chain_id" => 1u32, "ordered" => true },
+            ),
+            owner_call(
+                "set_chain_status",
+                runtime_args! { "chain_id" => 5u32, "status" => 2u8 },
+            ),
+        ];
+        for call in setup {
+            builder.exec(call).commit().expect_success();
+        }
+
+        let config_of = |builder: &mut LmdbWasmTestBuilder, chain_id: u32| -> ChainConfig {
+            call_getter(
+                builder,
+                contract,
+                "get_chain_config",
+                runtime_args! { "chain_id" => chain_id },
+            )
+        };
+        let before = [config_of(&mut builder, 1), config_of(&mut builder, 5)];
+
+        let export: Bytes = call_getter(
+            &mut builder,
+            contract,
+            "export_chain_configs",
+            runtime_args! {},
+        );
+
+        let clear = [
+            owner_call(
+                "set_supported_chain",
+                runtime_args! { "chain_id" => 1u32, "supported" => false, "dst_gateway" => Bytes::new() },
+            ),
+            owner_call(
+                "set_chain_ordered",
+                runtime_args! { "chain_id" => 1u32, "ordered" => false },
+            ),
+            owner_call(
+                "set_supported_chain",
+                runtime_args! { "chain_id" => 5u32, "supported" => false },
+            ),
+        ];
+        for call in clear {
+            builder.exec(call).commit().expect_success();
+        }
+
+        let chains: Vec<u32> = call_getter(
+            &mut builder,
+            contract,
+            "get_supported_chains",
+            runtime_args! {},
+        );
+        assert!(chains.is_empty());
+        assert_ne!(config_of(&mut builder, 1), before[0]);
+
+        let import = owner_call(
+            "import_chain_configs",
+            runtime_args! { "configs" => export },
+        );
+        builder.exec(import).commit().expect_success();
+
+        assert_eq!(
+            [config_of(&mut builder, 1), config_of(&mut builder, 5)],
+            before
+        );
+
+        let mut chains: Vec<u32> = call_getter(
+            &mut builder,
+            contract,
+            "get_supported_chains",
+            runtime_args! {},
+        );
+        chains.sort_unstable();
+        assert_eq!(chains, vec![1, 5]);
+    }
+
+    #[test]
+    fn loopback_delivers_sent_message_to_its_receiver_only() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => relayer_key(&signing_key),
+                "loopback" => true,
+            },
+        );
+        let receiver = install_receiver(&mut builder);
+        set_supported_chain(&mut builder, gateway, CASPER_CHAIN_ID, true);
+
+        let receiver_bytes = Bytes::from(receiver.value().to_vec());
+        let payload = Bytes::from(b"round trip".to_vec());
+        send_message(
+            &mut builder,
+            gateway,
+            CASPER_CHAIN_ID,
+            receiver_bytes.clone(),
+            payload.clone(),
+        );
+
+        let sent: Bytes = dictionary_item(&builder, gateway, "messages", "0").unwrap();
+        let sender = Bytes::from(DEFAULT_ACCOUNT_ADDR.value().to_vec());
+
+        // Aimed at another receiver, the bytes match nothing that was sent.
+        let other = Bytes::from(vec![7u8; 32]);
+        let redirected = build_message_bytes(
+            CASPER_CHAIN_ID,
+            CASPER_CHAIN_ID,
+            sender.as_ref(),
+            other.as_ref(),
+            0,
+            payload.as_ref(),
+        );
+        let misdelivery = execute_message_request(
+            gateway,
+            CASPER_CHAIN_ID,
+            sender.clone(),
+            other,
+            0,
+            payload.clone(),
+            sign_with(&signing_key, &redirected),
+        );
+        builder.exec(misdelivery).commit().expect_failure();
+        assert_user_error(&builder, 50);
+
+        let delivery = execute_message_request(
+            gateway,
+            CASPER_CHAIN_ID,
+            sender,
+            receiver_bytes,
+            0,
+            payload,
+            sign_with(&signing_key, sent.as_ref()),
+        );
+        builder.exec(delivery).commit().expect_success();
+
+        assert_eq!(receiver_count(&builder, receiver), 1);
+    }
+
+    #[test]
+    fn chain_requiring_ed25519_rejects_valid_secp256k1_signature() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let secp_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! { "relayer_pubkey" => relayer_key(&secp_key) },
+        );
+
+        let require_ed25519 = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "set_chain_sig_scheme",
+            runtime_args! { "chain_id" => 1u32, "sig_scheme" => Some(1u8) },
+        )
+        .build();
+        builder.exec(require_ed25519).commit().expect_success();
+
+        let config: ChainConfig = call_getter(
+            &mut builder,
+            gateway,
+            "get_chain_config",
+            runtime_args! { "chain_id" => 1u32 },
+        );
+        assert_eq!(config.required_sig_scheme, Some(1));
+
+        let src_gateway = Bytes::from(vec![9u8; 32]);
+        let receiver = Bytes::from(vec![0u8; 32]);
+        let execute = |src_chain_id: u32| {
+            let message = build_message_bytes(
+                src_chain_id,
+                CASPER_CHAIN_ID,
+                src_gateway.as_ref(),
+                receiver.as_ref(),
+                0,
+                &[],
+            );
+            execute_message_request(
+                gateway,
+                src_chain_id,
+                src_gateway.clone(),
+                receiver.clone(),
+                0,
+                Bytes::new(),
+                sign_with(&secp_key, &message),
+            )
+        };
+
+        builder.exec(execute(1)).commit().expect_failure();
+        assert_user_error(&builder, 5);
+
+        // The same signature is good for a chain without the requirement.
+        builder.exec(execute(2)).commit().expect_success();
+    }
+
+    #[test]
+    fn configure_chain_applies_every_setting_in_one_call() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+        let trusted_gateway = Bytes::from(vec![0xabu8; 20]);
+
+        let configure = |args: RuntimeArgs| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                contract.into(),
+                "configure_chain",
+                args,
+            )
+            .build()
+        };
+
+        // One bad field sinks the whole call.
+        builder
+            .exec(configure(runtime_args! {
+                "chain_id" => 1u32,
+                "supported" => true,
+                "trusted_gateway" => trusted_gateway.clone(),
+                "addr_len" => 0u8,
+            }))
+            .commit()
+            .expect_failure();
+        let chains: Vec<u32> = call_getter(
+            &mut builder,
+            contract,
+            "get_supported_chains",
+            runtime_args! {},
+        );
+        assert!(chains.is_empty());
+
+        builder
+            .exec(configure(runtime_args! {
+                "chain_id" => 1u32,
+                "supported" => true,
+                "trusted_gateway" => trusted_gateway.clone(),
+                "addr_len" => 20u8,
+                "ordered" => false,
+            }))
+            .commit()
+            .expect_success();
+
+        let chains: Vec<u32> = call_getter(
+            &mut builder,
+            contract,
+            "get_supported_chains",
+            runtime_args! {},
+        );
+        assert_eq!(chains, vec![1]);
+
+        let gateway: Bytes = call_getter(
+            &mut builder,
+            contract,
+            "get_trusted_gateway",
+            runtime_args! { "src_chain_id" => 1u32 },
+        );
+        assert_eq!(gateway, trusted_gateway);
+
+        let ordered: bool = call_getter(
+            &mut builder,
+            contract,
+            "is_chain_ordered",
+            runtime_args! { "chain_id" => 1u32 },
+        );
+        assert!(!ordered);
+
+        let config: ChainConfig = call_getter(
+            &mut builder,
+            contract,
+            "get_chain_config",
+            runtime_args! { "chain_id" => 1u32 },
+        );
+        assert_eq!(config.addr_len, Some(20));
+
+        // Sends to the chain must name a receiver of its address length.
+        let send = |receiver_len: usize| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                contract.into(),
+                "send_message",
+                runtime_args! {
+                    "dst_chain_id" => 1u32,
+                    "receiver" => Bytes::from(vec![7u8; receiver_len]),
+                    "payload" => Bytes::new(),
+                },
+            )
+            .build()
+        };
+        builder.exec(send(32)).commit().expect_failure();
+        assert_user_error(&builder, 3);
+        builder.exec(send(20)).commit().expect_success();
+    }
+
+    #[test]
+    fn receiver_reply_of_wrong_type_is_recorded_not_trapped() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => relayer_key(&signing_key),
+                "receiver_returns_bool" => true,
+            },
+        );
+
+        let install_receiver_returning = |builder: &mut LmdbWasmTestBuilder, returns: &str| {
+            let install = ExecuteRequestBuilder::standard(
+                *DEFAULT_ACCOUNT_ADDR,
+                RECEIVER_WASM,
+                runtime_args! {
+                    "contract_key_name" => format!("{}_receiver", returns),
+                    "returns" => returns.to_string(),
+                },
+            )
+            .build();
+            builder.exec(install).commit().expect_success();
+            named_contract(builder, &format!("{}_receiver", returns))
+        };
+        let bool_receiver = install_receiver_returning(&mut builder, "bool");
+        let u64_receiver = install_receiver_returning(&mut builder, "u64");
+
+        let src_gateway = Bytes::from(vec![9u8; 32]);
+        let message = |receiver: ContractHash, nonce: u64| {
+            build_message_bytes(
+                1,
+                CASPER_CHAIN_ID,
+                src_gateway.as_ref(),
+                &receiver.value(),
+                nonce,
+                &[],
+            )
+        };
+        let execute = |receiver: ContractHash, nonce: u64, soft_fail: bool| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "execute_message",
+                runtime_args! {
+                    "src_chain_id" => 1u32,
+                    "src_gateway" => src_gateway.clone(),
+                    "receiver" => Bytes::from(receiver.value().to_vec()),
+                    "nonce" => nonce,
+                    "payload" => Bytes::new(),
+                    "signature" => sign_with(&signing_key, &message(receiver, nonce)),
+                    "soft_fail" => soft_fail,
+                },
+            )
+            .build()
+        };
+
+        builder
+            .exec(execute(bool_receiver, 0, false))
+            .commit()
+            .expect_success();
+        assert_eq!(receiver_count(&builder, bool_receiver), 1);
+
+        // A u64 where a bool is expected is a delivery error, not a trap.
+        builder
+            .exec(execute(u64_receiver, 1, false))
+            .commit()
+            .expect_failure();
+        assert_user_error(&builder, 51);
+
+        builder
+            .exec(execute(u64_receiver, 1, true))
+            .commit()
+            .expect_success();
+        assert_eq!(
+            dictionary_item::<(u32, String)>(&builder, gateway, "error_events", "0"),
+            Some((51, message_key(&message(u64_receiver, 1))))
+        );
+        assert_eq!(receiver_count(&builder, u64_receiver), 1);
+    }
+
+    #[test]
+    fn send_message_refuses_new_sends_at_storage_cap_until_pruned() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => unused_relayer_key(),
+                "max_active_messages" => 2u64,
+            },
+        );
+        set_supported_chain(&mut builder, contract, DST_CHAIN_ID, true);
+
+        let send = || {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                contract.into(),
+                "send_message",
+                runtime_args! {
+                    "dst_chain_id" => DST_CHAIN_ID,
+                    "receiver" => Bytes::from(vec![7u8; 32]),
+                    "payload" => Bytes::new(),
+                },
+            )
+            .build()
+        };
+
+        builder.exec(send()).commit().expect_success();
+        builder.exec(send()).commit().expect_success();
+
+        builder.exec(send()).commit().expect_failure();
+        assert_user_error(&builder, 44);
+
+        let prune = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "prune_messages",
+            runtime_args! { "up_to" => 1u64 },
+        )
+        .build();
+        builder.exec(prune).commit().expect_success();
+
+        builder.exec(send()).commit().expect_success();
+    }
+
+    #[test]
+    fn delivery_weight_estimate_grows_with_payload_and_fanout() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+
+        let estimate =
+            |builder: &mut LmdbWasmTestBuilder, payload_len: u32, receiver_count: u32| {
+                call_getter::<u64>(
+                    builder,
+                    contract,
+                    "estimate_delivery_weight",
+                    runtime_args! {
+                        "payload_len" => payload_len,
+                        "receiver_count" => receiver_count,
+                    },
+                )
+            };
+
+        let small = estimate(&mut builder, 32, 1);
+        let large = estimate(&mut builder, 4096, 1);
+        assert!(large > small);
+        assert!(estimate(&mut builder, 32, 3) > small);
+
+        let retune = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "set_weight_coefficients",
+            runtime_args! {
+                "base" => 100u64,
+                "per_byte" => 2u64,
+                "per_receiver" => 10u64,
+            },
+        )
+        .build();
+        builder.exec(retune).commit().expect_success();
+
+        assert_eq!(estimate(&mut builder, 32, 3), 100 + 2 * 32 + 10 * 3);
+    }
+
+    #[test]
+    fn install_bootstraps_committee_from_initial_relayers() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let committee: Vec<Bytes> = (0..3)
+            .map(|_| {
+                let key = relayer_key(&SigningKey::random(&mut OsRng));
+                Bytes::from(key.to_bytes().expect("key bytes"))
+            })
+            .collect();
+
+        let install = |relayers: Vec<Bytes>, threshold: u8| {
+            ExecuteRequestBuilder::standard(
+                *DEFAULT_ACCOUNT_ADDR,
+                CONTRACT_WASM,
+                runtime_args! {
+                    "relayer_pubkey" => unused_relayer_key(),
+                    "initial_relayers" => relayers,
+                    "initial_threshold" => threshold,
+                },
+            )
+            .build()
+        };
+
+        // More signatures than keys, or a key cut short, fail the install.
+        builder
+            .exec(install(committee.clone(), 4))
+            .commit()
+            .expect_failure();
+        assert_user_error(&builder, 14);
+
+        let mut truncated = committee.clone();
+        truncated[1] = Bytes::from(truncated[1][..20].to_vec());
+        builder
+            .exec(install(truncated, 2))
+            .commit()
+            .expect_failure();
+        assert_user_error(&builder, 14);
+
+        builder
+            .exec(install(committee.clone(), 2))
+            .commit()
+            .expect_success();
+        let gateway = named_contract(&builder, GATEWAY_KEY);
+
+        let threshold: u8 = call_getter(&mut builder, gateway, "get_threshold", runtime_args! {});
+        assert_eq!(threshold, 2);
+        let count: u32 = call_getter(&mut builder, gateway, "get_relayer_count", runtime_args! {});
+        assert_eq!(count, 3);
+
+        let relayers: Vec<Bytes> =
+            call_getter(&mut builder, gateway, "get_relayers", runtime_args! {});
+        assert_eq!(relayers, committee);
+    }
+
+    #[test]
+    fn replayable_receiver_takes_the_same_message_twice() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_pubkey(&mut builder, relayer_key(&signing_key));
+        let receiver = install_receiver(&mut builder);
+        let receiver_bytes = Bytes::from(receiver.value().to_vec());
+
+        let allow_replay = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "set_allow_replay",
+            runtime_args! {
+                "receiver" => receiver_bytes.clone(),
+                "allow_replay" => true,
+            },
+        )
+        .build();
+        builder.exec(allow_replay).commit().expect_success();
+
+        let src_gateway = Bytes::from(vec![9u8; 32]);
+        let message = build_message_bytes(
+            1,
+            CASPER_CHAIN_ID,
+            src_gateway.as_ref(),
+            receiver_bytes.as_ref(),
+            0,
+            &[],
+        );
+        let deliver = || {
+            execute_message_request(
+                gateway,
+                1,
+                src_gateway.clone(),
+                receiver_bytes.clone(),
+                0,
+                Bytes::new(),
+                sign_with(&signing_key, &message),
+            )
+        };
+
+        builder.exec(deliver()).commit().expect_success();
+        builder.exec(deliver()).commit().expect_success();
+
+        assert_eq!(receiver_count(&builder, receiver), 2);
+    }
+
+    #[test]
+    fn unsupported_chain_diagnostic_carries_the_chain_id() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+
+        let send = |soft_fail: bool| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                contract.into(),
+                "send_message",
+                runtime_args! {
+                    "dst_chain_id" => 999u32,
+                    "receiver" => Bytes::from(vec![7u8; 32]),
+                    "payload" => Bytes::new(),
+                    "soft_fail" => soft_fail,
+                },
+            )
+            .build()
+        };
+
+        // A hard revert only has room for the error code.
+        builder.exec(send(false)).commit().expect_failure();
+        assert_user_error(&builder, 1);
+
+        builder.exec(send(true)).commit().expect_success();
+
+        let event: (u32, String, Option<u32>) = call_getter(
+            &mut builder,
+            contract,
+            "get_error_event",
+            runtime_args! { "index" => 0u64 },
+        );
+        assert_eq!(event, (1, "999".to_string(), Some(999)));
+    }
+
+    #[test]
+    fn set_supported_chain_is_owner_only() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+
+        let other = AccountHash::new([7u8; 32]);
+        let fund = TransferRequestBuilder::new(MINIMUM_ACCOUNT_CREATION_BALANCE, other).build();
+        builder.transfer_and_commit(fund).expect_success();
+
+        let call = ExecuteRequestBuilder::contract_call_by_hash(
+            other,
+            contract.into(),
+            "set_supported_chain",
+            runtime_args! {
+                "chain_id" => 1u32,
+                "supported" => true,
+                "dst_gateway" => Bytes::from(vec![0xd5u8; 20]),
+            },
+        )
+        .build();
+        builder.exec(call).commit().expect_failure();
+        assert_user_error(&builder, 8);
+
+        let config: ChainConfig = call_getter(
+            &mut builder,
+            contract,
+            "get_chain_config",
+            runtime_args! { "chain_id" => 1u32 },
+        );
+        assert_eq!(config.dst_gateway, None);
+    }
+
+    #[test]
+    fn set_receiver_version_is_owner_only() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        install_receiver(&mut builder);
+        let package = named_contract(&builder, "mock_receiver_package");
+        let contract = install(&mut builder);
+
+        let other = AccountHash::new([7u8; 32]);
+        let fund = TransferRequestBuilder::new(MINIMUM_ACCOUNT_CREATION_BALANCE, other).build();
+        builder.transfer_and_commit(fund).expect_success();
+
+        let call = ExecuteRequestBuilder::contract_call_by_hash(
+            other,
+            contract.into(),
+            "set_receiver_version",
+            runtime_args! {
+                "receiver" => Bytes::from(package.value().to_vec()),
+                "version" => 1u32,
+            },
+        )
+        .build();
+        builder.exec(call).commit().expect_failure();
+        assert_user_error(&builder, 8);
+    }
+
+    #[test]
+    fn signed_admin_action_supports_chain_for_non_owner_submitter() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let admin_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => unused_relayer_key(),
+                "admin_pubkey" => relayer_key(&admin_key),
+            },
+        );
+
+        let other = AccountHash::new([7u8; 32]);
+        let fund = TransferRequestBuilder::new(MINIMUM_ACCOUNT_CREATION_BALANCE, other).build();
+        builder.transfer_and_commit(fund).expect_success();
+
+        let direct = ExecuteRequestBuilder::contract_call_by_hash(
+            other,
+            gateway.into(),
+            "set_supported_chain",
+            runtime_args! { "chain_id" => DST_CHAIN_ID, "supported" => true },
+        )
+        .build();
+        builder.exec(direct).commit().expect_failure();
+        assert_user_error(&builder, 8);
+
+        let mut params = DST_CHAIN_ID.to_be_bytes().to_vec();
+        params.push(1);
+        let action = "set_supported_chain";
+        let mut signed = b"knotx-admin-v1".to_vec();
+        signed.extend_from_slice(&0u64.to_be_bytes());
+        signed.extend_from_slice(&(action.len() as u32).to_be_bytes());
+        signed.extend_from_slice(action.as_bytes());
+        signed.extend_from_slice(&params);
+
+        let signed_action = ExecuteRequestBuilder::contract_call_by_hash(
+            other,
+            gateway.into(),
+            "signed_admin_action",
+            runtime_args! {
+                "action" => action.to_string(),
+                "params" => Bytes::from(params),
+                "admin_nonce" => 0u64,
+                "signature" => sign_with(&admin_key, &signed),
+            },
+        )
+        .build();
+        builder.exec(signed_action).commit().expect_success();
+        assert_eq!(
+            dictionary_item::<bool>(&builder, gateway, "supported_chains", "1"),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn rotate_fee_purse_without_sweep_keeps_refundable_fees_payable() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let fee = U512::from(1_000u64);
+        let contract = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => unused_relayer_key(),
+                "message_fee" => fee,
+            },
+        );
+        set_supported_chain(&mut builder, contract, DST_CHAIN_ID, true);
+
+        let deadline = 10_000u64;
+        let paid_send = |builder: &mut LmdbWasmTestBuilder, ttl_ms: Option<u64>| {
+            let mut args = runtime_args! {
+                "gateway" => contract,
+                "amount" => fee,
+                "dst_chain_id" => DST_CHAIN_ID,
+                "receiver" => Bytes::from(vec![2u8; 32]),
+                "payload" => Bytes::from(vec![]),
+            };
+            if let Some(ttl_ms) = ttl_ms {
+                args.insert("ttl_ms", ttl_ms).unwrap();
+            }
+            let session =
+                ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, SEND_WITH_FEE_WASM, args)
+                    .build();
+            builder.exec(session).commit().expect_success();
+        };
+
+        // One refundable fee, one that is not.
+        paid_send(&mut builder, Some(deadline));
+        let ids: Vec<String> = call_getter(
+            &mut builder,
+            contract,
+            "get_recent_message_ids",
+            runtime_args! {},
+        );
+        let refundable_id = ids[0].clone();
+        paid_send(&mut builder, None);
+
+        let old_purse = contract_uref(&builder, contract, "fee_purse");
+        let new_purse: URef = call_getter(
+            &mut builder,
+            contract,
+            "rotate_fee_purse",
+            runtime_args! { "sweep" => false },
+        );
+        assert_eq!(builder.get_purse_balance(old_purse), fee);
+        assert_eq!(builder.get_purse_balance(new_purse), fee);
+
+        let refund = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "request_refund",
+            runtime_args! { "message_id" => refundable_id },
+        )
+        .with_block_time(deadline + 1)
+        .build();
+        builder.exec(refund).commit().expect_success();
+        assert_eq!(builder.get_purse_balance(old_purse), fee);
+        assert_eq!(builder.get_purse_balance(new_purse), U512::zero());
+
+        // Nothing is left to refund, so the next rotation leaves it all behind.
+        paid_send(&mut builder, None);
+        let newest_purse: URef = call_getter(
+            &mut builder,
+            contract,
+            "rotate_fee_purse",
+            runtime_args! { "sweep" => false },
+        );
+        assert_eq!(builder.get_purse_balance(new_purse), fee);
+        assert_eq!(builder.get_purse_balance(newest_purse), U512::zero());
+    }
+
+    #[test]
+    fn fanout_keeps_delivering_past_a_wrong_reply() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => relayer_key(&signing_key),
+                "receiver_returns_bool" => true,
+            },
+        );
+
+        let install_receiver_returning = |builder: &mut LmdbWasmTestBuilder, returns: &str| {
+            let install = ExecuteRequestBuilder::standard(
+                *DEFAULT_ACCOUNT_ADDR,
+                RECEIVER_WASM,
+                runtime_args! {
+                    "contract_key_name" => format!("{}_receiver", returns),
+                    "returns" => returns.to_string(),
+                },
+            )
+            .build();
+            builder.exec(install).commit().expect_success();
+            named_contract(builder, &format!("{}_receiver", returns))
+        };
+        let u64_receiver = install_receiver_returning(&mut builder, "u64");
+        let bool_receiver = install_receiver_returning(&mut builder, "bool");
+
+        // The bad receiver is listed first.
+        let addrs = [u64_receiver.value(), bool_receiver.value()];
+        let commitment = Bytes::from(blake2b(addrs.concat()).to_vec());
+        let list: Vec<Bytes> = addrs
+            .iter()
+            .map(|addr| Bytes::from(addr.to_vec()))
+            .collect();
+        let message =
+            build_message_bytes(1, CASPER_CHAIN_ID, &[9u8; 32], commitment.as_ref(), 0, &[]);
+        let execute = |soft_fail: bool| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "execute_message",
+                runtime_args! {
+                    "src_chain_id" => 1u32,
+                    "src_gateway" => Bytes::from(vec![9u8; 32]),
+                    "receiver" => commitment.clone(),
+                    "nonce" => 0u64,
+                    "payload" => Bytes::new(),
+                    "signature" => sign_with(&signing_key, &message),
+                    "receivers" => list.clone(),
+                    "soft_fail" => soft_fail,
+                },
+            )
+            .build()
+        };
+
+        builder.exec(execute(false)).commit().expect_failure();
+        assert_user_error(&builder, 51);
+
+        builder.exec(execute(true)).commit().expect_success();
+        assert_eq!(
+            dictionary_item::<(u32, String)>(&builder, gateway, "error_events", "0"),
+            Some((51, message_key(&message)))
+        );
+        assert_eq!(receiver_count(&builder, u64_receiver), 1);
+        assert_eq!(receiver_count(&builder, bool_receiver), 1);
+    }
+
+    #[test]
+    fn confirm_message_resolves_message_as_execute_does() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => unused_relayer_key(),
+                "loopback" => true,
+            },
+        );
+        let committee = [
+            SigningKey::random(&mut OsRng),
+            SigningKey::random(&mut OsRng),
+        ];
+        set_committee(&mut builder, gateway, &committee, 2);
+
+        let owner_call = |entry_point: &str, args: RuntimeArgs| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                entry_point,
+                args,
+            )
+            .build()
+        };
+        let bind = owner_call(
+            "set_supported_chain",
+            runtime_args! {
+                "chain_id" => CASPER_CHAIN_ID,
+                "supported" => true,
+                "dst_gateway" => Bytes::from(vec![0xd5u8; 20]),
+            },
+        );
+        builder.exec(bind).commit().expect_success();
+
+        let receiver = install_receiver(&mut builder);
+        let receiver_bytes = Bytes::from(receiver.value().to_vec());
+        send_message(
+            &mut builder,
+            gateway,
+            CASPER_CHAIN_ID,
+            receiver_bytes.clone(),
+            Bytes::new(),
+        );
+        let sent: Bytes = dictionary_item(&builder, gateway, "messages", "0").unwrap();
+
+        // The loopback carries the configured gateway, for confirmations too.
+        let args = |src_chain_id: u32, signers: &[usize]| {
+            runtime_args! {
+                "src_chain_id" => src_chain_id,
+                "src_gateway" => Bytes::from(DEFAULT_ACCOUNT_ADDR.value().to_vec()),
+                "receiver" => receiver_bytes.clone(),
+                "nonce" => 0u64,
+                "payload" => Bytes::new(),
+                "signatures" => committee_signatures(&committee, signers, sent.as_ref()),
+            }
+        };
+        builder
+            .exec(owner_call("confirm_message", args(CASPER_CHAIN_ID, &[0])))
+            .commit()
+            .expect_success();
+        builder
+            .exec(owner_call("execute_message", args(CASPER_CHAIN_ID, &[1])))
+            .commit()
+            .expect_success();
+        assert_eq!(receiver_count(&builder, receiver), 1);
+
+        // A chain verified by its own relayer key takes no confirmations.
+        let set_chain_relayer = owner_call(
+            "set_chain_relayer",
+            runtime_args! {
+                "src_chain_id" => 1u32,
+                "relayer" => Some(relayer_key(&committee[0])),
+            },
+        );
+        builder.exec(set_chain_relayer).commit().expect_success();
+        builder
+            .exec(owner_call("confirm_message", args(1, &[0])))
+            .commit()
+            .expect_failure();
+        assert_user_error(&builder, 14);
+    }
+
+    #[test]
+    fn confirmations_only_count_for_the_signed_delivery_args() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let gateway = install(&mut builder);
+        let committee = [
+            SigningKey::random(&mut OsRng),
+            SigningKey::random(&mut OsRng),
+        ];
+        set_committee(&mut builder, gateway, &committee, 2);
+
+        let receiver = Bytes::from(install_receiver(&mut builder).value().to_vec());
+        let message =
+            build_message_bytes(1, CASPER_CHAIN_ID, &[9u8; 32], receiver.as_ref(), 0, &[]);
+        let mut store_only_bytes = message.clone();
+        store_only_bytes.push(1);
+
+        let call = |entry_point: &str, signers: &[usize], store_only: bool| {
+            let signed = if store_only {
+                &store_only_bytes
+            } else {
+                &message
+            };
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                entry_point,
+                runtime_args! {
+                    "src_chain_id" => 1u32,
+                    "src_gateway" => Bytes::from(vec![9u8; 32]),
+                    "receiver" => receiver.clone(),
+                    "nonce" => 0u64,
+                    "payload" => Bytes::new(),
+                    "signatures" => committee_signatures(&committee, signers, signed),
+                    "store_only" => store_only,
+                },
+            )
+            .build()
+        };
+
+        builder
+            .exec(call("confirm_message", &[0], false))
+            .commit()
+            .expect_success();
+
+        // One member cannot turn the confirmed push delivery into a
+        // store-only one by signing the flag alone.
+        builder
+            .exec(call("execute_message", &[1], true))
+            .commit()
+            .expect_failure();
+        assert_user_error(&builder, 15);
+
+        // Nor add a confirmation over different delivery args.
+        builder
+            .exec(call("confirm_message", &[1], true))
+            .commit()
+            .expect_failure();
+        assert_user_error(&builder, 52);
+
+        builder
+            .exec(call("execute_message", &[1], false))
+            .commit()
+            .expect_success();
+    }
+
+    #[test]
+    fn build_message_fills_in_configured_dst_gateway() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let contract = install(&mut builder);
+        let bind = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract.into(),
+            "set_supported_chain",
+            runtime_args! {
+                "chain_id" => DST_CHAIN_ID,
+                "supported" => true,
+                "dst_gateway" => Bytes::from(vec![0xd5u8; 20]),
+            },
+        )
+        .build();
+        builder.exec(bind).commit().expect_success();
+
+        let receiver = Bytes::from(vec![5u8; 32]);
+        let payload = Bytes::from(b"hello".to_vec());
+        send_message(
+            &mut builder,
+            contract,
+            DST_CHAIN_ID,
+            receiver.clone(),
+            payload.clone(),
+        );
+        let sent: Bytes = dictionary_item(&builder, contract, "messages", "0").unwrap();
+
+        let fields = runtime_args! {
+            "src_chain_id" => CASPER_CHAIN_ID,
+            "dst_chain_id" => DST_CHAIN_ID,
+            "src_gateway" => Bytes::from(DEFAULT_ACCOUNT_ADDR.value().to_vec()),
+            "receiver" => receiver,
+            "nonce" => 0u64,
+            "payload" => payload,
+        };
+        let built: Bytes = call_getter(&mut builder, contract, "build_message", fields.clone());
+        assert_eq!(built, sent);
+
+        // An explicit `dst_gateway` wins over the configured one.
+        let verify = |builder: &mut LmdbWasmTestBuilder, dst_gateway: Option<Bytes>| -> bool {
+            let mut args = fields.clone();
+            args.insert("message_id", message_key(sent.as_ref()))
+                .unwrap();
+            if let Some(dst_gateway) = dst_gateway {
+                args.insert("dst_gateway", dst_gateway).unwrap();
+            }
+            call_getter(builder, contract, "verify_message_id", args)
+        };
+        assert!(verify(&mut builder, None));
+        assert!(verify(&mut builder, Some(Bytes::from(vec![0xd5u8; 20]))));
+        assert!(!verify(&mut builder, Some(Bytes::new())));
+    }
+
+    #[test]
+    fn replayable_receiver_replays_move_no_funds() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let reward = U512::from(1_000u64);
+        let signing_key = SigningKey::random(&mut OsRng);
+        let gateway = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => relayer_key(&signing_key),
+                "relayer_reward" => reward,
+            },
+        );
+        let receiver = install_receiver(&mut builder);
+        let receiver_bytes = Bytes::from(receiver.value().to_vec());
+
+        let fund = ExecuteRequestBuilder::standard(
+            *DEFAULT_ACCOUNT_ADDR,
+            FUND_REWARDS_WASM,
+            runtime_args! {
+                "gateway" => gateway,
+                "amount" => U512::from(10_000u64),
+            },
+        )
+        .build();
+        builder.exec(fund).commit().expect_success();
+
+        let allow_replay = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "set_allow_replay",
+            runtime_args! {
+                "receiver" => receiver_bytes.clone(),
+                "allow_replay" => true,
+            },
+        )
+        .build();
+        builder.exec(allow_replay).commit().expect_success();
+
+        let src_gateway = Bytes::from(vec![9u8; 32]);
+        let deliver = |nonce: u64, amount: U512| {
+            let message = build_message_bytes_with_amount(
+                1,
+                CASPER_CHAIN_ID,
+                src_gateway.as_ref(),
+                receiver_bytes.as_ref(),
+                nonce,
+                amount,
+                &[],
+            );
+
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "execute_message",
+                runtime_args! {
+                    "src_chain_id" => 1u32,
+                    "src_gateway" => src_gateway.clone(),
+                    "receiver" => receiver_bytes.clone(),
+                    "nonce" => nonce,
+                    "payload" => Bytes::new(),
+                    "amount" => amount,
+                    "signature" => sign_with(&signing_key, &message),
+                },
+            )
+            .build()
+        };
+
+        // Value would leave escrow on every replay, so it is refused outright.
+        builder
+            .exec(deliver(0, U512::from(5u64)))
+            .commit()
+            .expect_failure();
+        assert_user_error(&builder, 16);
+
+        // The reward is paid for the first delivery only.
+        builder
+            .exec(deliver(1, U512::zero()))
+            .commit()
+            .expect_success();
+        builder
+            .exec(deliver(1, U512::zero()))
+            .commit()
+            .expect_success();
+
+        assert_eq!(receiver_count(&builder, receiver), 2);
+        assert_eq!(
+            call_getter::<U512>(
+                &mut builder,
+                gateway,
+                "get_reward_balance",
+                runtime_args! {}
+            ),
+            U512::from(9_000u64)
+        );
+    }
+
+    #[test]
+    fn request_refund_rejects_an_instant_refund() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let fee = U512::from(1_000u64);
+        let contract = install_with_args(
+            &mut builder,
+            runtime_args! {
+                "relayer_pubkey" => unused_relayer_key(),
+                "message_fee" => fee,
+            },
+        );
+        set_supported_chain(&mut builder, contract, DST_CHAIN_ID, true);
+
+        let paid_send = |builder: &mut LmdbWasmTestBuilder, arg: &str, value: u64| {
+            let mut args = runtime_args! {
+                "gateway" => contract,
+                "amount" => fee,
+                "dst_chain_id" => DST_CHAIN_ID,
+                "receiver" => Bytes::from(vec![2u8; 32]),
+                "payload" => Bytes::from(vec![]),
+            };
+            args.insert(arg, value).unwrap();
+            let session =
+                ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, SEND_WITH_FEE_WASM, args)
+                    .build();
+            builder.exec(session).commit().expect_success();
+
+            let ids: Vec<String> = call_getter(
+                builder,
+                contract,
+                "get_recent_message_ids",
+                runtime_args! {},
+            );
+            ids[0].clone()
+        };
+        let refund = |message_id: String, block_time: u64| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                contract.into(),
+                "request_refund",
+                runtime_args! { "message_id" => message_id },
+            )
+            .with_block_time(block_time)
+            .build()
+        };
+
+        // A deadline of the sender's choosing does not make a fee refundable.
+        let message_id = paid_send(&mut builder, "deadline", 0);
+        builder
+            .exec(refund(message_id, 1))
+            .commit()
+            .expect_failure();
+        assert_user_error(&builder, 10);
+
+        // With a ttl, the refund waits until the destination would reject it.
+        let message_id = paid_send(&mut builder, "ttl_ms", 60_000);
+        builder
+            .exec(refund(message_id.clone(), 1))
+            .commit()
+            .expect_failure();
+        assert_user_error(&builder, 10);
+
+        builder
+            .exec(refund(message_id, 60_001))
+            .commit()
+            .expect_success();
+    }
+
+    #[test]
+    fn set_committee_rejects_shrinking_below_a_chain_threshold() {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let gateway = install(&mut builder);
+        let committee = [
+            SigningKey::random(&mut OsRng),
+            SigningKey::random(&mut OsRng),
+            SigningKey::random(&mut OsRng),
+        ];
+        set_committee(&mut builder, gateway, &committee, 1);
+        set_supported_chain(&mut builder, gateway, 1, true);
+
+        let chain_threshold = |chain_id: u32, threshold: Option<u8>| {
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "set_chain_threshold",
+                runtime_args! { "chain_id" => chain_id, "threshold" => threshold },
+            )
+            .build()
+        };
+        let shrink = || {
+            let relayers: Vec<PublicKey> = committee[..2].iter().map(relayer_key).collect();
+            ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                gateway.into(),
+                "set_committee",
+                runtime_args! { "relayers" => relayers, "threshold" => 1u8 },
+            )
+            .build()
+        };
+
+        builder
+            .exec(chain_threshold(1, Some(3)))
+            .commit()
+            .expect_success();
+        builder
+            .exec(chain_threshold(2, Some(3)))
+            .commit()
+            .expect_success();
+
+        // Chain 1 would need three signatures from two keys.
+        builder.exec(shrink()).commit().expect_failure();
+        assert_user_error(&builder, 14);
+        let count: u32 = call_getter(&mut builder, gateway, "get_relayer_count", runtime_args! {});
+        assert_eq!(count, 3);
+
+        builder
+            .exec(chain_threshold(1, None))
+            .commit()
+            .expect_success();
+        builder.exec(shrink()).commit().expect_success();
+
+        // Chain 2 was not supported; it cannot be enabled with its override.
+        let enable = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            gateway.into(),
+            "set_supported_chain",
+            runtime_args! { "chain_id" => 2u32, "supported" => true },
+        )
+        .build();
+        builder.exec(enable).commit().expect_failure();
+        assert_user_error(&builder, 14);
+    }
 }
@@ -119,9 +119,13 @@ export class CasperExecutor {
     srcGateway: Uint8Array,
     receiver: Uint8Array,
     nonce: number,
-    payload: Uint8Array
+    payload: Uint8Array,
+    amount: bigint = 0n,
+    msgType: number = 0,
+    sigScheme: number = 0,
+    dstGateway: Uint8Array = new Uint8Array(0)
   ): Uint8Array {
-    const buffer = Buffer.alloc(4 + 4 + 32 + 32 + 8 + payload.length);
+    const buffer = Buffer.alloc(4 + 4 + 32 + 32 + 8 + 32 + 1 + 1 + dstGateway.length + payload.length);
     let offset = 0;
 
     buffer.writeUInt32BE(srcChainId, offset);
@@ -139,6 +143,25 @@ export class CasperExecutor {
     buffer.writeBigUInt64BE(BigInt(nonce), offset);
     offset += 8;
 
+    // amount: 32-byte big-endian CSPR value carried with the message
+    for (let i = 31; i >= 0; i--) {
+      buffer[offset + i] = Number((amount >> BigInt(8 * (31 - i))) & 0xffn);
+    }
+    offset += 32;
+
+    // msg_type: selects the receiver entry point (0 = on_call)
+    buffer.writeUInt8(msgType, offset);
+    offset += 1;
+
+    // sig_scheme: how the attached signature is made (0 = secp256k1, 1 = ed25519)
+    buffer.writeUInt8(sigScheme, offset);
+    offset += 1;
+
+    // dst_gateway: the gateway bound into the message, empty for inbound messages
+    // from other chains (the contract only fills it in on Casper loopback)
+    buffer.set(dstGateway, offset);
+    offset += dstGateway.length;
+
     buffer.set(payload, offset);
 
     return new Uint8Array(buffer);
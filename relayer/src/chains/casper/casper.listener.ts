@@ -23,6 +23,7 @@ export class CasperListener extends EventEmitter {
   private readonly CONTRACT_HASH: string;
   private readonly KEY_NONCE = 'nonce';
   private readonly KEY_MESSAGES = 'messages';
+  private readonly KEY_MESSAGE_PAYLOAD_LENS = 'message_payload_lens';
 
   constructor() {
     super();
@@ -86,9 +87,9 @@ export class CasperListener extends EventEmitter {
   }
 
   /**
-   * Get messages dictionary URef from contract
+   * Get a dictionary URef (e.g. messages) from the contract's named keys
    */
-  private async getMessagesDictionaryURef(): Promise<string> {
+  private async getDictionaryURef(name: string): Promise<string> {
     try {
       const queryResult = await this.rpcManager.executeWithRotation(async (rpcClient) => {
         return await rpcClient.queryLatestGlobalState(this.CONTRACT_HASH, []);
@@ -114,15 +115,15 @@ export class CasperListener extends EventEmitter {
             namedKeysMap = namedKeys;
           }
           
-          if (namedKeysMap[this.KEY_MESSAGES]) {
-            return namedKeysMap[this.KEY_MESSAGES];
+          if (namedKeysMap[name]) {
+            return namedKeysMap[name];
           }
         }
       }
       
-      throw new Error('Messages dictionary URef not found');
+      throw new Error(`${name} dictionary URef not found`);
     } catch (error: any) {
-      logger.error({ error: error.message }, 'Failed to get messages dictionary URef');
+      logger.error({ error: error.message, name }, 'Failed to get dictionary URef');
       throw error;
     }
   }
@@ -266,15 +267,57 @@ export class CasperListener extends EventEmitter {
     }
   }
 
+  /**
+   * Get the payload length stored next to a message. The message bytes carry
+   * dst_gateway and payload back to back with no length, so this is what
+   * splits them.
+   */
+  private async getPayloadLength(stateRootHash: string, dictionaryURef: string, nonce: number): Promise<number | null> {
+    try {
+      const rpcRequest = {
+        jsonrpc: '2.0',
+        id: Date.now(),
+        method: 'state_get_dictionary_item',
+        params: {
+          state_root_hash: stateRootHash,
+          dictionary_identifier: {
+            URef: {
+              seed_uref: dictionaryURef,
+              dictionary_item_key: nonce.toString()
+            }
+          }
+        }
+      };
+      
+      const response = await this.rpcManager.fetchWithRotation(rpcRequest);
+      const data = await response.json() as any;
+      
+      const bytes = data.result?.stored_value?.CLValue?.bytes;
+      if (data.error || typeof bytes !== 'string' || bytes.length !== 8) {
+        return null;
+      }
+      
+      // CLValue U32: 4 bytes, little-endian
+      return Buffer.from(bytes, 'hex').readUInt32LE(0);
+    } catch (error: any) {
+      logger.debug({ error: error.message, nonce }, 'Failed to get payload length');
+      return null;
+    }
+  }
+
   /**
    * Parse message bytes into structured format
    */
-  private parseMessageBytes(messageBytes: Uint8Array): {
+  private parseMessageBytes(messageBytes: Uint8Array, payloadLength: number | null = null): {
     srcChainId: number;
     dstChainId: number;
     srcGateway: Uint8Array;
     receiver: Uint8Array;
     nonce: number;
+    amount: bigint;
+    msgType: number;
+    sigScheme: number;
+    dstGateway: Uint8Array;
     payload: Uint8Array;
   } | null {
     try {
@@ -309,6 +352,7 @@ export class CasperListener extends EventEmitter {
       const srcGatewayLength = 32;
       const receiverLength = 32;
       const nonceLength = 8;
+      const amountLength = 32;
       
       const srcGateway = messageBytes.slice(offset, offset + srcGatewayLength);
       offset += srcGatewayLength;
@@ -327,8 +371,24 @@ export class CasperListener extends EventEmitter {
       const nonce = Number(Buffer.from(messageBytes.slice(offset, offset + nonceLength)).readBigUInt64BE(0));
       offset += nonceLength;
       
+      const amount = BigInt('0x' + Buffer.from(messageBytes.slice(offset, offset + amountLength)).toString('hex'));
+      offset += amountLength;
+
+      const msgType = messageBytes[offset];
+      offset += 1;
+
+      const sigScheme = messageBytes[offset];
+      offset += 1;
+      
+      // dst_gateway (raw, empty when unbound) runs up to the payload, whose
+      // length is stored separately
+      let dstGateway: Uint8Array = new Uint8Array(0);
       let payload: Uint8Array;
-      if (messageBytes.length >= offset + 4) {
+      if (payloadLength !== null && messageBytes.length >= offset + payloadLength) {
+        const payloadStart = messageBytes.length - payloadLength;
+        dstGateway = messageBytes.slice(offset, payloadStart);
+        payload = messageBytes.slice(payloadStart);
+      } else if (messageBytes.length >= offset + 4) {
         const possibleLength = Buffer.from(messageBytes.slice(offset, offset + 4)).readUInt32BE(0);
         if (possibleLength > 0 && possibleLength < 10000 && messageBytes.length >= offset + 4 + possibleLength) {
           payload = messageBytes.slice(offset + 4, offset + 4 + possibleLength);
@@ -345,6 +405,10 @@ export class CasperListener extends EventEmitter {
         srcGateway,
         receiver,
         nonce,
+        amount,
+        msgType,
+        sigScheme,
+        dstGateway,
         payload,
       };
     } catch (error) {
@@ -359,7 +423,7 @@ export class CasperListener extends EventEmitter {
   private async processMessage(nonce: number): Promise<RelayMessage | null> {
     try {
       const stateRootHash = await this.getStateRootHash();
-      const dictionaryURef = await this.getMessagesDictionaryURef();
+      const dictionaryURef = await this.getDictionaryURef(this.KEY_MESSAGES);
       
       const messageBytes = await this.getMessageBytes(stateRootHash, dictionaryURef, nonce);
       
@@ -378,7 +442,12 @@ export class CasperListener extends EventEmitter {
         '📥 Raw message bytes received from Casper'
       );
       
-      const parsed = this.parseMessageBytes(messageBytes);
+      const payloadLensURef = await this.getDictionaryURef(this.KEY_MESSAGE_PAYLOAD_LENS).catch(() => null);
+      const payloadLength = payloadLensURef
+        ? await this.getPayloadLength(stateRootHash, payloadLensURef, nonce)
+        : null;
+      
+      const parsed = this.parseMessageBytes(messageBytes, payloadLength);
       if (!parsed) {
         logger.warn({ nonce, messageLength: messageBytes.length }, 'Failed to parse message bytes');
         return null;
@@ -396,6 +465,7 @@ export class CasperListener extends EventEmitter {
           srcGatewayLength: parsed.srcGateway.length,
           receiverHex: Buffer.from(parsed.receiver).toString('hex'),
           receiverLength: parsed.receiver.length,
+          dstGatewayHex: Buffer.from(parsed.dstGateway).toString('hex'),
           payloadHex: Buffer.from(parsed.payload).toString('hex'),
           payloadLength: parsed.payload.length,
           payloadAsString: Buffer.from(parsed.payload).toString('utf8'),
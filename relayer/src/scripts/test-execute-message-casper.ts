@@ -168,7 +168,7 @@ function getSecp256k1PrivateKey(): Uint8Array {
 
 /**
  * Build message bytes according to contract's build_message_bytes function
- * Format: src_chain_id (4) + dst_chain_id (4) + src_gateway (32) + receiver (32) + nonce (8) + payload (variable)
+ * Format: src_chain_id (4) + dst_chain_id (4) + src_gateway (32) + receiver (32) + nonce (8) + amount (32) + msg_type (1) + sig_scheme (1) + dst_gateway (variable, empty when unbound) + payload (variable)
  */
 function buildMessageBytes(
   srcChainId: number,
@@ -176,9 +176,10 @@ function buildMessageBytes(
   srcGateway: Uint8Array,
   receiver: Uint8Array,
   nonce: number,
-  payload: Uint8Array
+  payload: Uint8Array,
+  dstGateway: Uint8Array = new Uint8Array(0)
 ): Uint8Array {
-  const buffer = Buffer.alloc(4 + 4 + 32 + 32 + 8 + payload.length);
+  const buffer = Buffer.alloc(4 + 4 + 32 + 32 + 8 + 32 + 1 + 1 + dstGateway.length + payload.length);
   let offset = 0;
 
   // src_chain_id (4 bytes, big-endian)
@@ -201,6 +202,19 @@ function buildMessageBytes(
   buffer.writeBigUInt64BE(BigInt(nonce), offset);
   offset += 8;
 
+  // amount (32 bytes, big-endian) - no CSPR carried, left zero
+  offset += 32;
+
+  // msg_type (1 byte) - 0 delivers to on_call
+  offset += 1;
+
+  // sig_scheme (1 byte) - 0 is secp256k1
+  offset += 1;
+
+  // dst_gateway (variable length) - empty unless the gateway is its own source
+  buffer.set(dstGateway, offset);
+  offset += dstGateway.length;
+
   // payload (variable length)
   buffer.set(payload, offset);
 
@@ -241,6 +255,9 @@ async function getRelayerPubkey(rpcClient: RpcClient): Promise<Uint8Array | null
         if (bytes.length === 33) {
           // Already 33 bytes
           keyBytes = new Uint8Array(bytes);
+        } else if (bytes.length === 34 && bytes[0] === 0x02) {
+          // Typed PublicKey: secp256k1 tag (0x02) + 33-byte compressed key
+          keyBytes = new Uint8Array(bytes.slice(1));
         } else if (bytes.length === 37 && bytes[0] === 0x21) {
           // CLValue with length prefix: 0x21 (33) + 33 bytes of key
           keyBytes = new Uint8Array(bytes.slice(4, 37));
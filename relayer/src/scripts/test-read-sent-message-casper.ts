@@ -8,6 +8,7 @@
  * 
  * Usage:
  *   ts-node src/scripts/test-read-sent-message-casper.ts
+ *   ts-node src/scripts/test-read-sent-message-casper.ts --fixture   (offline parser check)
  * 
  * ⚠️  RATE LIMITING WARNING:
  * The RPC endpoint has strict rate limits. This script makes minimal RPC calls:
@@ -21,6 +22,7 @@ import {
   HttpHandler,
 } from 'casper-js-sdk';
 import { logger } from '../utils/logger';
+import dstGatewayFixture from './fixtures/casper-message-dst-gateway.json';
 
 // Configuration
 const RPC_URL = 'https://node.testnet.cspr.cloud/rpc';
@@ -30,6 +32,7 @@ const CONTRACT_HASH = 'hash-a595eebdfe2f22390873593f94f247824d0edc7e9a9d53003af2
 // Constants from contract
 const KEY_NONCE = 'nonce';
 const KEY_MESSAGES = 'messages';
+const KEY_MESSAGE_PAYLOAD_LENS = 'message_payload_lens';
 
 // Note: Based on test file (send_message_stores_message_by_nonce), 
 // dictionary keys are used as raw strings (e.g., "0", "1", "2"), not hashed.
@@ -135,7 +138,7 @@ async function getNonceFromContract(rpcClient: RpcClient): Promise<number> {
  * Use queryLatestGlobalState to get contract info, then extract URef from raw JSON
  * Returns both the URef and the state root hash used
  */
-async function getMessagesDictionaryURef(rpcClient: RpcClient): Promise<{ dictionaryURef: string; stateRootHash: string }> {
+async function getMessagesDictionaryURef(rpcClient: RpcClient): Promise<{ dictionaryURef: string; payloadLensURef: string | null; stateRootHash: string }> {
   try {
     // Query the contract to get its full state info
     // We'll query an empty path to get the contract's root info
@@ -213,7 +216,10 @@ async function getMessagesDictionaryURef(rpcClient: RpcClient): Promise<{ dictio
               stateRootHashSource: stateRootHash ? 'from query result' : 'will query without state_root_hash'
             }, '✅ Found messages dictionary URef');
             
-            return { dictionaryURef: messagesURef, stateRootHash };
+            // Payload lengths split dst_gateway from payload; older deployments don't have them
+            const payloadLensURef = namedKeysMap[KEY_MESSAGE_PAYLOAD_LENS] ?? null;
+            
+            return { dictionaryURef: messagesURef, payloadLensURef, stateRootHash };
           } else {
             console.log('⚠️  messages key not found in NamedKeys');
             console.log('Available named keys:', Object.keys(namedKeysMap));
@@ -375,6 +381,50 @@ async function getMessageByNonceDirect(
   }
 }
 
+/**
+ * Get the payload length stored for a nonce (CLValue U32, little-endian)
+ */
+async function getPayloadLengthDirect(
+  stateRootHash: string,
+  payloadLensURef: string,
+  nonce: number
+): Promise<number | null> {
+  try {
+    const response = await fetch(RPC_URL, {
+      method: 'POST',
+      headers: {
+        'Content-Type': 'application/json',
+        'Authorization': API_KEY,
+      },
+      body: JSON.stringify({
+        jsonrpc: '2.0',
+        id: Date.now(),
+        method: 'state_get_dictionary_item',
+        params: {
+          state_root_hash: stateRootHash,
+          dictionary_identifier: {
+            URef: {
+              seed_uref: payloadLensURef,
+              dictionary_item_key: nonce.toString()
+            }
+          }
+        }
+      }),
+    });
+    
+    const data = await response.json() as any;
+    const bytes = data.result?.stored_value?.CLValue?.bytes;
+    if (data.error || typeof bytes !== 'string' || bytes.length !== 8) {
+      return null;
+    }
+    
+    return Buffer.from(bytes, 'hex').readUInt32LE(0);
+  } catch (error: any) {
+    logger.debug({ error: error.message, nonce }, 'Error fetching payload length');
+    return null;
+  }
+}
+
 /**
  * Get state root hash from latest block
  * Use direct RPC call to ensure we get the absolute latest
@@ -500,16 +550,26 @@ async function getStateRootHash(_rpcClient?: RpcClient): Promise<string> {
  * - src_gateway: variable length bytes (typically 32 bytes for account/contract hash)
  * - receiver: variable length bytes (typically 32 bytes for contract hash)
  * - nonce: u64 (8 bytes, big-endian)
+ * - amount: CSPR value carried with the message (32 bytes, big-endian)
+ * - msg_type: selects the receiver entry point (1 byte)
+ * - sig_scheme: how the attached signature is made (1 byte)
+ * - dst_gateway: gateway on the destination chain, empty when unbound (variable)
  * - payload: variable length bytes
  * 
- * Note: We parse backwards from the end since nonce and receiver have known/fixed sizes
+ * dst_gateway and payload have no length of their own; pass the payload length
+ * from the message_payload_lens dictionary to split them. Without it
+ * dst_gateway is taken as empty.
  */
-function parseMessageBytes(messageBytes: Uint8Array): {
+function parseMessageBytes(messageBytes: Uint8Array, payloadLength: number | null = null): {
   srcChainId: number;
   dstChainId: number;
   srcGateway: Uint8Array;
   receiver: Uint8Array;
   nonce: number;
+  amount: bigint;
+  msgType: number;
+  sigScheme: number;
+  dstGateway: Uint8Array;
   payload: Uint8Array;
 } | null {
   try {
@@ -549,14 +609,17 @@ function parseMessageBytes(messageBytes: Uint8Array): {
     offset = srcGatewayStart;
 
     // Parse the message structure (from contract's build_message_bytes):
-    // src_chain_id (4) + dst_chain_id (4) + src_gateway (32) + receiver (32) + nonce (8) + payload (variable)
+    // src_chain_id (4) + dst_chain_id (4) + src_gateway (32) + receiver (32) + nonce (8) + amount (32) + msg_type (1) + sig_scheme (1) + payload (variable)
     // Based on test file, src_gateway and receiver are 32 bytes each
     
     const nonceLength = 8;
+    const amountLength = 32;
+    const msgTypeLength = 1;
+    const sigSchemeLength = 1;
     const receiverLength = 32; // Standard contract hash length (from test file)
     const srcGatewayLength = 32; // Standard account/contract hash length (from test file)
     
-    const totalFixedLength = 4 + 4 + srcGatewayLength + receiverLength + nonceLength;
+    const totalFixedLength = 4 + 4 + srcGatewayLength + receiverLength + nonceLength + amountLength + msgTypeLength + sigSchemeLength;
     
     if (messageBytes.length < totalFixedLength) {
       logger.warn({ 
@@ -594,10 +657,27 @@ function parseMessageBytes(messageBytes: Uint8Array): {
     const nonce = Number(Buffer.from(messageBytes.slice(offset, offset + nonceLength)).readBigUInt64BE(0));
     offset += nonceLength;
     
-    // Parse payload (remaining bytes)
-    // Note: Payload might have a 4-byte length prefix
+    // Parse amount (32 bytes, big-endian)
+    const amount = BigInt('0x' + Buffer.from(messageBytes.slice(offset, offset + amountLength)).toString('hex'));
+    offset += amountLength;
+    
+    // Parse msg_type (1 byte)
+    const msgType = messageBytes[offset];
+    offset += msgTypeLength;
+    
+    // Parse sig_scheme (1 byte)
+    const sigScheme = messageBytes[offset];
+    offset += sigSchemeLength;
+    
+    // Parse dst_gateway (up to the payload) and payload (the last payloadLength bytes)
+    // Note: Without a stored length, payload might have a 4-byte length prefix
+    let dstGateway: Uint8Array = new Uint8Array(0);
     let payload: Uint8Array;
-    if (messageBytes.length >= offset + 4) {
+    if (payloadLength !== null && messageBytes.length >= offset + payloadLength) {
+      const payloadStart = messageBytes.length - payloadLength;
+      dstGateway = messageBytes.slice(offset, payloadStart);
+      payload = messageBytes.slice(payloadStart);
+    } else if (messageBytes.length >= offset + 4) {
       const possibleLength = Buffer.from(messageBytes.slice(offset, offset + 4)).readUInt32BE(0);
       // If the first 4 bytes look like a length prefix and the remaining bytes match that length
       if (possibleLength > 0 && possibleLength < 10000 && messageBytes.length >= offset + 4 + possibleLength) {
@@ -617,6 +697,10 @@ function parseMessageBytes(messageBytes: Uint8Array): {
       srcGateway,
       receiver,
       nonce,
+      amount,
+      msgType,
+      sigScheme,
+      dstGateway,
       payload,
     };
   } catch (error) {
@@ -629,8 +713,9 @@ function parseMessageBytes(messageBytes: Uint8Array): {
  * Get message by nonce and parse it
  */
 async function getMessageByNonce(
-  stateRootHash: string | undefined,
+  stateRootHash: string,
   dictionaryURef: string,
+  payloadLensURef: string | null,
   nonce: number
 ): Promise<{
   srcChainId: number;
@@ -638,6 +723,7 @@ async function getMessageByNonce(
   srcGateway: string;
   receiver: string;
   nonce: number;
+  dstGateway: string;
   payload: string;
   payloadHex: string;
   payloadLength: number;
@@ -658,11 +744,18 @@ async function getMessageByNonce(
   console.log('Bytes 8-39 (src_gateway, 32 bytes):', Buffer.from(messageBytes.slice(8, 40)).toString('hex'));
   console.log('Bytes 40-71 (receiver, 32 bytes):', Buffer.from(messageBytes.slice(40, 72)).toString('hex'));
   console.log('Bytes 72-79 (nonce, 8 bytes):', Buffer.from(messageBytes.slice(72, 80)).toString('hex'), '=', Buffer.from(messageBytes.slice(72, 80)).readBigUInt64BE(0).toString());
-  console.log('Bytes 80+ (payload):', Buffer.from(messageBytes.slice(80)).toString('hex'));
-  console.log('Payload length:', messageBytes.length - 80, 'bytes');
+  console.log('Bytes 80-111 (amount, 32 bytes):', Buffer.from(messageBytes.slice(80, 112)).toString('hex'));
+  console.log('Byte 112 (msg_type):', messageBytes[112]);
+  console.log('Byte 113 (sig_scheme):', messageBytes[113]);
+  console.log('Bytes 114+ (dst_gateway + payload):', Buffer.from(messageBytes.slice(114)).toString('hex'));
   console.log('\n');
 
-  const parsed = parseMessageBytes(messageBytes);
+  const payloadLength = payloadLensURef
+    ? await getPayloadLengthDirect(stateRootHash, payloadLensURef, nonce)
+    : null;
+  console.log('Stored payload length:', payloadLength ?? 'unknown', payloadLength !== null ? 'bytes' : '');
+
+  const parsed = parseMessageBytes(messageBytes, payloadLength);
   if (!parsed) {
     logger.warn({ nonce, messageLength: messageBytes.length }, 'Failed to parse message bytes');
     return null;
@@ -678,6 +771,8 @@ async function getMessageByNonce(
   console.log('Parsed receiver length:', parsed.receiver.length, 'bytes');
   console.log('Parsed receiver (as CLValue List<U8> format):', '20000000' + Buffer.from(parsed.receiver).toString('hex'));
   console.log('Parsed nonce:', parsed.nonce);
+  console.log('Parsed dstGateway (hex):', Buffer.from(parsed.dstGateway).toString('hex') || '(unbound)');
+  console.log('Parsed dstGateway length:', parsed.dstGateway.length, 'bytes');
   console.log('Parsed payload (hex):', Buffer.from(parsed.payload).toString('hex'));
   console.log('Parsed payload length:', parsed.payload.length, 'bytes');
   
@@ -737,6 +832,7 @@ async function getMessageByNonce(
     srcGateway: Buffer.from(parsed.srcGateway).toString('hex'),
     receiver: Buffer.from(parsed.receiver).toString('hex'),
     nonce: parsed.nonce,
+    dstGateway: Buffer.from(parsed.dstGateway).toString('hex'),
     payload: payloadDisplay,
     payloadHex: Buffer.from(parsed.payload).toString('hex'),
     payloadLength: parsed.payload.length,
@@ -791,7 +887,7 @@ async function readAllSentMessages(): Promise<void> {
     
     // Step 2: Get messages dictionary URef (this gets a state root hash internally)
     logger.info('Getting messages dictionary URef from contract state...');
-    const { dictionaryURef, stateRootHash: initialStateRootHash } = await getMessagesDictionaryURef(rpcClient);
+    const { dictionaryURef, payloadLensURef, stateRootHash: initialStateRootHash } = await getMessagesDictionaryURef(rpcClient);
     
     // Verify the dictionary URef by checking it exists in the contract
     logger.info(
@@ -850,7 +946,7 @@ async function readAllSentMessages(): Promise<void> {
           stateRootHashShort: freshStateRootHash.substring(0, 16) + '...'
         }, `State root hash for nonce ${nonce}`);
         
-        const message = await getMessageByNonce(freshStateRootHash, dictionaryURef, nonce);
+        const message = await getMessageByNonce(freshStateRootHash, dictionaryURef, payloadLensURef, nonce);
         
         if (message) {
           // Use the dictionary key nonce (which we know is correct) instead of parsed nonce
@@ -954,8 +1050,49 @@ async function readAllSentMessages(): Promise<void> {
   }
 }
 
+/**
+ * Parse the stored fixture (a message with a bound dst_gateway) and compare
+ * against its expected fields. Needs no RPC access.
+ */
+function checkDstGatewayFixture(): void {
+  const messageBytes = Uint8Array.from(Buffer.from(dstGatewayFixture.messageBytesHex, 'hex'));
+  const parsed = parseMessageBytes(messageBytes, dstGatewayFixture.payloadLength);
+  if (!parsed) {
+    throw new Error('Fixture did not parse');
+  }
+
+  const expected = dstGatewayFixture.expected;
+  const actual = {
+    srcChainId: parsed.srcChainId,
+    dstChainId: parsed.dstChainId,
+    srcGateway: Buffer.from(parsed.srcGateway).toString('hex'),
+    receiver: Buffer.from(parsed.receiver).toString('hex'),
+    nonce: parsed.nonce,
+    amount: parsed.amount.toString(),
+    msgType: parsed.msgType,
+    sigScheme: parsed.sigScheme,
+    dstGateway: Buffer.from(parsed.dstGateway).toString('hex'),
+    payload: Buffer.from(parsed.payload).toString('hex'),
+  };
+
+  for (const [field, value] of Object.entries(expected)) {
+    if ((actual as Record<string, unknown>)[field] !== value) {
+      throw new Error(`Fixture field ${field}: expected ${value}, got ${(actual as Record<string, unknown>)[field]}`);
+    }
+  }
+  logger.info({ dstGateway: actual.dstGateway, payload: actual.payload }, '✅ dst_gateway fixture parsed as expected');
+}
+
 // Run the test
-if (require.main === module) {
+if (require.main === module && process.argv.includes('--fixture')) {
+  try {
+    checkDstGatewayFixture();
+    process.exit(0);
+  } catch (error) {
+    logger.error({ error }, 'Fixture check failed');
+    process.exit(1);
+  }
+} else if (require.main === module) {
   readAllSentMessages()
     .then(() => {
       logger.info('Test completed');
@@ -967,5 +1104,5 @@ if (require.main === module) {
     });
 }
 
-export { readAllSentMessages };
+export { readAllSentMessages, checkDstGatewayFixture };
 